@@ -0,0 +1,632 @@
+use std::collections::{hash_map::Entry, HashMap, HashSet};
+
+use super::validation::*;
+use super::{Context, Variable};
+use crate::parser::{Class, MemberModifier, Modifier, Program};
+
+#[derive(Debug, Default)]
+pub(super) struct ClassType {
+    pub modifier: Option<Modifier>,
+    pub is_interface: bool,
+    pub constructor: Vec<(String, ExprType)>,
+    pub supertypes: Vec<String>,
+    /// Direct subtypes declared in the program, used to check `when` over sealed types
+    pub subtypes: Vec<String>,
+    pub entries: Vec<String>,
+    pub properties: HashMap<String, Variable>,
+    pub methods: HashMap<String, FunType>,
+    pub open_methods: HashSet<String>,
+    pub abstract_methods: Vec<String>,
+}
+
+impl ClassType {
+    pub(super) fn is(&self, modifier: Modifier) -> bool {
+        self.modifier == Some(modifier)
+    }
+
+    /// Interfaces, abstract and sealed classes can't be instantiated,
+    /// so they may have abstract members
+    fn is_abstract(&self) -> bool {
+        self.is_interface || self.is(Modifier::Abstract) || self.is(Modifier::Sealed)
+    }
+
+    fn is_open(&self) -> bool {
+        self.is_abstract() || self.is(Modifier::Open)
+    }
+
+    /// Members generated for data classes, unless they are declared explicitly.
+    /// `copy` isn't a regular method because it accepts named arguments
+    fn add_data_methods(&mut self, this: ExprType) {
+        let method = |args, ret_type| FunType { args, ret_type };
+        let generated = [
+            (
+                "equals".to_owned(),
+                method(
+                    vec![this.nullable()],
+                    ExprType::Primitive(Primitive::Boolean),
+                ),
+            ),
+            (
+                "hashCode".to_owned(),
+                method(vec![], ExprType::Primitive(Primitive::Int)),
+            ),
+            (
+                "toString".to_owned(),
+                method(vec![], ExprType::Primitive(Primitive::String)),
+            ),
+        ];
+
+        let components = self.constructor.iter().enumerate().map(|(index, (_, ty))| {
+            (
+                format!("component{}", index + 1),
+                method(vec![], ty.clone()),
+            )
+        });
+
+        for (name, ty) in generated.into_iter().chain(components) {
+            self.methods.entry(name).or_insert(ty);
+        }
+    }
+
+    fn add_enum_properties(&mut self) {
+        let properties = [
+            ("name", ExprType::Primitive(Primitive::String)),
+            ("ordinal", ExprType::Primitive(Primitive::Int)),
+        ];
+        for (name, ty) in properties {
+            let property = Variable {
+                ty,
+                is_mut: false,
+                cast_from: None,
+            };
+            self.properties.insert(name.to_owned(), property);
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub(super) struct CoveredCases {
+    pub types: Vec<String>,
+    pub entries: Vec<String>,
+    pub has_null: bool,
+}
+
+impl<'a> Context<'a> {
+    /// Class names are known before any signature is resolved,
+    /// so classes can refer to each other in any order
+    pub(super) fn declare_classes(&'_ mut self, prog: &'a Program) {
+        for class in &prog.classes {
+            let name = class.name.0.as_str();
+            let mut entries: Vec<String> = vec![];
+            for entry in &class.entries {
+                let entry_name = &entry.name.0;
+                if entries.contains(entry_name) {
+                    self.error_with_span(
+                        format!("conflicting declarations: enum entry {entry_name}"),
+                        entry.name.span,
+                    );
+                } else {
+                    entries.push(entry_name.clone());
+                }
+            }
+            let class_type = ClassType {
+                modifier: class.modifier,
+                is_interface: class.is_interface,
+                entries,
+                ..Default::default()
+            };
+
+            if let Entry::Vacant(entry) = self.classes.entry(name) {
+                entry.insert(class_type);
+            } else {
+                self.error_with_span(format!("class {name} already defined"), class.name.span);
+            }
+        }
+
+        for class in &prog.classes {
+            self.declare_supertypes(class);
+        }
+
+        // Subtypes are only recorded for a hierarchy without cycles
+        for class in &prog.classes {
+            let name = class.name.0.as_str();
+            let supertypes = &self.classes[name].supertypes;
+            if supertypes.iter().any(|s| self.is_subclass(s, name)) {
+                self.error_with_span(
+                    format!("there's a cycle in the inheritance hierarchy for {name}"),
+                    class.name.span,
+                );
+                self.classes.get_mut(name).unwrap().supertypes.clear();
+            }
+        }
+
+        for class in &prog.classes {
+            let name = class.name.0.as_str();
+            for supertype in self.classes[name].supertypes.clone() {
+                let subtypes = &mut self.classes.get_mut(supertype.as_str()).unwrap().subtypes;
+                subtypes.push(name.to_owned());
+            }
+        }
+    }
+
+    fn declare_supertypes(&mut self, class: &'a Class) {
+        let mut supertypes = vec![];
+        let mut has_superclass = false;
+
+        for supertype in &class.supertypes {
+            let name = supertype.name.0.as_str();
+            let span = supertype.name.span;
+
+            let Some(parent) = self.classes.get(name) else {
+                self.error_with_span(format!("unresolved reference: {name}"), span);
+                continue;
+            };
+
+            let error = if !parent.is_open() {
+                Some("this type is final, so it cannot be inherited from".to_string())
+            } else if class.is_interface && !parent.is_interface {
+                Some("an interface cannot inherit from a class".to_string())
+            } else if parent.is_interface && supertype.args.is_some() {
+                Some(format!("interface {name} does not have constructors"))
+            } else if !parent.is_interface && supertype.args.is_none() {
+                Some("this type has a constructor, and thus must be initialized here".to_string())
+            } else if !parent.is_interface && has_superclass {
+                Some("only one class may appear in a supertype list".to_string())
+            } else {
+                None
+            };
+
+            if let Some(error) = error {
+                self.error_with_span(error, span);
+            } else {
+                has_superclass |= !parent.is_interface;
+                supertypes.push(name.to_owned());
+            }
+        }
+
+        if let Some(class_type) = self.classes.get_mut(class.name.0.as_str()) {
+            class_type.supertypes = supertypes;
+        }
+    }
+
+    pub(super) fn get_classes(&'_ mut self, prog: &'a Program) {
+        for class in &prog.classes {
+            let name = class.name.0.as_str();
+            let mut class_type = ClassType::default();
+
+            if class.is(Modifier::Data) && class.params.is_empty() {
+                self.error_with_span(
+                    "data class must have at least one primary constructor parameter".to_string(),
+                    class.name.span,
+                );
+            }
+
+            for param in &class.params {
+                let ty = self.resolve_type(&param.ty).unwrap_or(ExprType::Unresolved);
+                class_type
+                    .constructor
+                    .push((param.name.0.clone(), ty.clone()));
+
+                if class.is(Modifier::Data) && !param.is_property {
+                    self.error_with_span(
+                        "data class primary constructor must only have property (val / var) parameters"
+                            .to_string(),
+                        class.name.span,
+                    );
+                }
+
+                if param.is_property {
+                    let name = param.name.0.clone();
+                    let property = Variable {
+                        ty,
+                        is_mut: param.is_mut,
+                        cast_from: None,
+                    };
+                    if class_type.properties.insert(name, property).is_some() {
+                        let name = &param.name.0;
+                        self.error(format!("property {name} already defined"));
+                    }
+                }
+            }
+
+            let declared = &self.classes[name];
+            let (is_abstract_class, is_open_class) = (declared.is_abstract(), declared.is_open());
+
+            for method in &class.methods {
+                self.resolve_signature(method);
+
+                let name = method.name.0.clone();
+                if class_type
+                    .methods
+                    .insert(name.clone(), self.fun_type(method))
+                    .is_some()
+                {
+                    self.error_with_span(
+                        format!("method {name} already defined"),
+                        method.name.span,
+                    );
+                }
+
+                // Members of interfaces are abstract unless they have a body
+                let is_abstract = method.is(MemberModifier::Abstract)
+                    || class.is_interface && method.body.is_none();
+                let class_name = &class.name.0;
+                let error = if method.is(MemberModifier::Abstract) && method.body.is_some() {
+                    Some(format!("abstract function {name} cannot have a body"))
+                } else if !is_abstract && method.body.is_none() {
+                    Some(format!("function {name} without a body must be abstract"))
+                } else if is_abstract && !is_abstract_class {
+                    Some(format!(
+                        "abstract function {name} in non-abstract class {class_name}"
+                    ))
+                } else {
+                    None
+                };
+                if let Some(error) = error {
+                    self.error_with_span(error, method.name.span);
+                }
+
+                let is_open = method.is(MemberModifier::Open)
+                    || method.is(MemberModifier::Override)
+                    || is_abstract
+                    || class.is_interface;
+                if is_open && is_open_class {
+                    class_type.open_methods.insert(name.clone());
+                }
+                if is_abstract && is_abstract_class {
+                    class_type.abstract_methods.push(name);
+                }
+            }
+
+            let declared = self.classes.get_mut(name).expect("class declared");
+            declared.constructor = class_type.constructor;
+            declared.properties = class_type.properties;
+            declared.methods = class_type.methods;
+            declared.open_methods = class_type.open_methods;
+            declared.abstract_methods = class_type.abstract_methods;
+
+            if class.is(Modifier::Data) {
+                declared.add_data_methods(ExprType::Class(name.to_owned()));
+            }
+            if class.is(Modifier::Enum) {
+                declared.add_enum_properties();
+            }
+        }
+    }
+
+    /// Methods must be marked with `override` exactly when they override a method of a supertype,
+    /// and non-abstract classes must implement every inherited abstract method
+    pub(super) fn check_overrides(&mut self, prog: &'a Program) {
+        for class in &prog.classes {
+            let name = class.name.0.as_str();
+            let mut errors = vec![];
+
+            for method in &class.methods {
+                let method_name = method.name.0.as_str();
+                let Some(ty) = self.classes[name].methods.get(method_name) else {
+                    continue;
+                };
+
+                let overridden: Vec<_> = self.ancestors(name)[1..]
+                    .iter()
+                    .filter_map(|&parent| {
+                        let parent_type = &self.classes[parent];
+                        let parent_method = parent_type.methods.get(method_name)?;
+                        let is_open = parent_type.open_methods.contains(method_name);
+                        Some((parent, parent_method, is_open))
+                    })
+                    .collect();
+
+                let is_override = method.is(MemberModifier::Override);
+                let span = method.name.span;
+                if overridden.is_empty() && is_override && !is_any_member(method_name, ty) {
+                    errors.push((span, format!("{method_name} overrides nothing")));
+                } else if overridden.is_empty() && !is_override && is_any_member(method_name, ty) {
+                    errors.push((
+                        span,
+                        format!("{method_name} hides member of supertype Any and needs 'override' modifier"),
+                    ));
+                }
+
+                for (parent, parent_method, is_open) in overridden {
+                    let error = if !is_override {
+                        format!("{method_name} hides member of supertype {parent} and needs 'override' modifier")
+                    } else if !is_open {
+                        format!("{method_name} in {parent} is final and cannot be overridden")
+                    } else if parent_method.args != ty.args {
+                        format!("{method_name} overrides nothing")
+                    } else if !self.is_assignable(&parent_method.ret_type, &ty.ret_type) {
+                        format!("return type of {method_name} is not a subtype of the return type of the overridden member")
+                    } else {
+                        continue;
+                    };
+                    errors.push((span, error));
+                }
+            }
+
+            if !self.classes[name].is_abstract() {
+                for member in self.unimplemented_members(name) {
+                    errors.push((
+                        class.name.span,
+                        format!("class {name} is not abstract and does not implement abstract member {member}"),
+                    ));
+                }
+            }
+
+            for (span, error) in errors {
+                self.error_with_span(error, span);
+            }
+        }
+    }
+
+    fn unimplemented_members<'c>(&'c self, class: &'c str) -> Vec<&'c str> {
+        let ancestors = self.ancestors(class);
+        let is_implemented = |method: &str| {
+            ancestors.iter().any(|ancestor| {
+                let class_type = &self.classes[ancestor];
+                class_type.methods.contains_key(method)
+                    && !class_type.abstract_methods.iter().any(|m| m == method)
+            })
+        };
+
+        let mut members = vec![];
+        for ancestor in &ancestors {
+            for method in &self.classes[ancestor].abstract_methods {
+                if !is_implemented(method) && !members.contains(&method.as_str()) {
+                    members.push(method.as_str());
+                }
+            }
+        }
+        members
+    }
+
+    pub(super) fn validate_properties(&'_ mut self, prog: &'a Program) {
+        for class in &prog.classes {
+            let name = class.name.0.as_str();
+            let this = ExprType::Class(name.to_owned());
+
+            // `return` isn't allowed in initializers
+            self.current_ret_type = Some(ExprType::Nothing);
+
+            for entry in &class.entries {
+                let fun = self.constructor_type(name);
+                self.push_scope();
+                let args = self.validate_args(&entry.args, &fun.args);
+                self.pop_scope();
+
+                let span = self.last_span.replace(entry.name.span);
+                self.check_call(name, &fun, args.as_deref());
+                self.last_span = span;
+            }
+
+            let mut scope: HashMap<_, _> = class
+                .params
+                .iter()
+                .map(|param| {
+                    let ty = self.convert_type(&param.ty).unwrap_or(ExprType::Unresolved);
+                    (
+                        param.name.0.clone(),
+                        Variable {
+                            ty,
+                            is_mut: false,
+                            cast_from: None,
+                        },
+                    )
+                })
+                .collect();
+            scope.insert(
+                "this".to_owned(),
+                Variable {
+                    ty: this,
+                    is_mut: false,
+                    cast_from: None,
+                },
+            );
+            self.scopes.push(scope);
+
+            for supertype in &class.supertypes {
+                let Some(ref args) = supertype.args else {
+                    continue;
+                };
+                let parent = supertype.name.0.as_str();
+                if !self.classes.contains_key(parent) {
+                    self.validate_args(args, &[]);
+                    continue;
+                }
+                let fun = self.constructor_type(parent);
+                let args = self.validate_args(args, &fun.args);
+                let span = self.last_span.replace(supertype.name.span);
+                self.check_call(parent, &fun, args.as_deref());
+                self.last_span = span;
+            }
+
+            for property in &class.properties {
+                let Some(ty) = property.validate(self) else {
+                    continue;
+                };
+                let property_name = property.set.name.0.clone();
+                let variable = Variable {
+                    ty,
+                    is_mut: property.is_mut,
+                    cast_from: None,
+                };
+
+                let properties = &mut self
+                    .classes
+                    .get_mut(name)
+                    .expect("class declared")
+                    .properties;
+                if properties.insert(property_name, variable).is_some() {
+                    let property_name = &property.set.name.0;
+                    self.error_with_span(
+                        format!("property {property_name} already defined"),
+                        property.set.name.span,
+                    );
+                }
+            }
+
+            self.pop_scope();
+        }
+    }
+
+    pub(super) fn constructor_type(&self, class: &str) -> FunType {
+        FunType {
+            args: self.classes[class]
+                .constructor
+                .iter()
+                .map(|(_, ty)| ty.clone())
+                .collect(),
+            ret_type: ExprType::Class(class.to_owned()),
+        }
+    }
+
+    pub(super) fn find_constructor(&mut self, class: &str) -> Option<FunType> {
+        let class_type = &self.classes[class];
+        let error = if class_type.is_interface {
+            format!("interface {class} does not have constructors")
+        } else if class_type.is(Modifier::Sealed) {
+            "sealed types cannot be instantiated".to_string()
+        } else if class_type.is(Modifier::Enum) {
+            "enum types cannot be instantiated".to_string()
+        } else if class_type.is(Modifier::Abstract) {
+            format!("cannot create an instance of an abstract class {class}")
+        } else {
+            return Some(self.constructor_type(class));
+        };
+        self.error(error);
+        None
+    }
+
+    pub(super) fn ancestors<'c>(&'c self, class: &'c str) -> Vec<&'c str> {
+        let mut ancestors = vec![class];
+        let mut visited = HashSet::from([class]);
+        let mut index = 0;
+
+        while let Some(&class) = ancestors.get(index) {
+            index += 1;
+            let Some(class_type) = self.classes.get(class) else {
+                continue;
+            };
+            for supertype in &class_type.supertypes {
+                if visited.insert(supertype.as_str()) {
+                    ancestors.push(supertype);
+                }
+            }
+        }
+        ancestors
+    }
+
+    pub(super) fn is_subclass(&self, class: &str, ancestor: &str) -> bool {
+        self.ancestors(class).contains(&ancestor)
+    }
+
+    pub(super) fn find_method(&self, receiver: &ExprType, name: &str) -> Option<&FunType> {
+        let ExprType::Class(class) = receiver else {
+            return None;
+        };
+        self.ancestors(class)
+            .into_iter()
+            .find_map(|class| self.classes.get(class)?.methods.get(name))
+    }
+
+    pub(super) fn find_property(&self, receiver: &ExprType, name: &str) -> Option<&Variable> {
+        let ExprType::Class(class) = receiver else {
+            return None;
+        };
+        self.ancestors(class)
+            .into_iter()
+            .find_map(|class| self.classes.get(class)?.properties.get(name))
+    }
+
+    pub(super) fn find_data_class(&self, receiver: &ExprType) -> Option<&ClassType> {
+        match receiver {
+            ExprType::Class(class) => self
+                .classes
+                .get(class.as_str())
+                .filter(|c| c.is(Modifier::Data)),
+            _ => None,
+        }
+    }
+
+    pub(super) fn find_this_property(&self, name: &str) -> Option<&Variable> {
+        self.find_property(&self.find_var("this")?.ty, name)
+    }
+
+    pub(super) fn find_this_entry(&self, name: &str) -> Option<ExprType> {
+        let this = &self.find_var("this")?.ty;
+        let ExprType::Class(class) = this else {
+            return None;
+        };
+        let class_type = &self.classes[class.as_str()];
+        class_type
+            .entries
+            .iter()
+            .any(|entry| entry == name)
+            .then(|| this.clone())
+    }
+
+    pub(super) fn find_enum(&self, name: &str) -> Option<&ClassType> {
+        if self.find_var(name).is_some() || self.find_this_property(name).is_some() {
+            return None;
+        }
+        self.classes.get(name).filter(|c| c.is(Modifier::Enum))
+    }
+
+    pub(super) fn has_cases(&self, ty: &ExprType) -> bool {
+        match ty.clone().non_null() {
+            ExprType::Class(class) => self
+                .classes
+                .get(class.as_str())
+                .is_some_and(|c| c.is(Modifier::Sealed) || c.is(Modifier::Enum)),
+            _ => false,
+        }
+    }
+
+    pub(super) fn missing_cases(&self, ty: &ExprType, covered: &CoveredCases) -> Vec<String> {
+        let mut missing = vec![];
+        if let ExprType::Class(class) = ty.clone().non_null() {
+            self.collect_missing_cases(&class, covered, &mut missing);
+        }
+        if matches!(ty, ExprType::Nullable(_)) && !covered.has_null {
+            missing.push("'null'".to_string());
+        }
+        missing
+    }
+
+    fn collect_missing_cases(
+        &self,
+        class: &str,
+        covered: &CoveredCases,
+        missing: &mut Vec<String>,
+    ) {
+        if covered.types.iter().any(|ty| self.is_subclass(class, ty)) {
+            return;
+        }
+
+        let class_type = &self.classes[class];
+        if class_type.is(Modifier::Enum) {
+            for entry in &class_type.entries {
+                if !covered.entries.contains(entry) {
+                    missing.push(format!("'{entry}'"));
+                }
+            }
+        } else if class_type.is(Modifier::Sealed) {
+            for subtype in &class_type.subtypes {
+                self.collect_missing_cases(subtype, covered, missing);
+            }
+        } else {
+            let case = format!("'is {class}'");
+            // Subtypes of several sealed parents are reachable more than once
+            if !missing.contains(&case) {
+                missing.push(case);
+            }
+        }
+    }
+}
+
+fn is_any_member(name: &str, ty: &FunType) -> bool {
+    let string = ExprType::Primitive(Primitive::String);
+    let int = ExprType::Primitive(Primitive::Int);
+    ty.args.is_empty()
+        && (name == "toString" && ty.ret_type == string || name == "hashCode" && ty.ret_type == int)
+}
@@ -1,60 +1,118 @@
+mod classes;
+mod stdlib;
 mod validation;
-use std::collections::HashMap;
+use std::collections::{hash_map::Entry, HashMap, HashSet};
 
+use classes::*;
+use stdlib::*;
 use validation::*;
 
 use crate::lexer::Span;
 use crate::parser::expr::Ident;
-use crate::parser::{Program, Spanned};
+use crate::parser::{Fun, Program, Spanned, Type};
+
+#[derive(Debug, Clone)]
+struct Variable {
+    ty: ExprType,
+    is_mut: bool,
+    /// Declared type of a variable narrowed by a smart cast
+    cast_from: Option<ExprType>,
+}
 
 #[derive(Debug, Default)]
 pub struct Context<'a> {
     current_ret_type: Option<ExprType>,
+    loop_depth: usize,
     last_span: Option<Span>,
     functions: HashMap<&'a str, FunType>,
-    scopes: Vec<HashMap<String, ExprType>>,
+    classes: HashMap<&'a str, ClassType>,
+    scopes: Vec<HashMap<String, Variable>>,
+    broken_bindings: HashSet<String>,
     errors: Vec<(Span, String)>,
 }
 
 impl<'a> Context<'a> {
     fn get_functions(&'_ mut self, prog: &'a Program) {
         for fun in &prog.functions {
+            self.resolve_signature(fun);
+
             let name = fun.name.0.as_str();
-            let ty = FunType::from(fun);
+            if fun.body.is_none() {
+                self.error_with_span(format!("function {name} must have a body"), fun.name.span);
+            }
+            if fun.modifier.is_some() {
+                self.error_with_span(
+                    "modifiers are not applicable to top level functions".to_string(),
+                    fun.name.span,
+                );
+            }
+
+            let ty = self.fun_type(fun);
 
-            if self.functions.get(&name).is_some() {
-                self.error(format!("function {name} already defined"));
+            if let Entry::Vacant(entry) = self.functions.entry(name) {
+                entry.insert(ty);
             } else {
-                self.functions.insert(name, ty);
+                self.error_with_span(format!("function {name} already defined"), fun.name.span);
             }
         }
     }
 
     fn validate_functions(&'_ mut self, prog: &'a Program) {
         for fun in &prog.functions {
-            self.current_ret_type = if let Some(ref ty) = fun.ret_type {
-                Some(ExprType::from(ty))
-            } else {
-                Some(ExprType::Unit)
-            };
+            self.validate_fun(fun, None);
+        }
 
-            let args = fun
-                .args
-                .iter()
-                .map(|arg| {
-                    let name = (arg.0).0.clone();
-                    let ty = ExprType::from(&arg.1);
-                    (name, ty)
-                })
-                .collect();
+        for class in &prog.classes {
+            let this = ExprType::Class(class.name.0.clone());
+            for method in &class.methods {
+                self.validate_fun(method, Some(this.clone()));
+            }
+        }
+    }
 
-            self.scopes.push(args);
+    fn validate_fun(&mut self, fun: &Fun, this: Option<ExprType>) {
+        let Some(ref body) = fun.body else {
+            return;
+        };
+        let fun_type = self.fun_type(fun);
+        self.current_ret_type = Some(fun_type.ret_type);
+        self.broken_bindings.clear();
 
-            for expr in &fun.body {
-                expr.validate(self);
-            }
-            self.pop_scope();
+        let mut args: HashMap<_, _> = fun
+            .args
+            .iter()
+            .zip(fun_type.args)
+            .map(|(arg, ty)| {
+                (
+                    (arg.0).0.clone(),
+                    Variable {
+                        ty,
+                        is_mut: false,
+                        cast_from: None,
+                    },
+                )
+            })
+            .collect();
+
+        // `this` is a keyword, so the receiver can't clash with user variables
+        if let Some(ty) = this {
+            args.insert(
+                "this".to_owned(),
+                Variable {
+                    ty,
+                    is_mut: false,
+                    cast_from: None,
+                },
+            );
         }
+
+        self.scopes.push(args);
+
+        for expr in body {
+            expr.validate_statement(self);
+            smart_cast_after(expr, self);
+        }
+        self.pop_scope();
     }
 
     fn check_main(&mut self) {
@@ -80,10 +138,13 @@ impl<'a> Context<'a> {
             "arrayOf" => {
                 if let Some(args) = args {
                     if let Some(first) = args.first() {
-                        if args.iter().any(|t| t != first) {
+                        let ty = args
+                            .iter()
+                            .try_fold(first.clone(), |ty, arg| self.common_supertype(&ty, arg));
+                        if ty.is_none() {
                             self.error("arrayOf arguments must have the same type".to_string());
                         }
-                        Some(ExprType::Array(first.to_owned().into()))
+                        Some(ExprType::Array(ty.unwrap_or_else(|| first.clone()).into()))
                     } else {
                         self.error("arrayOf must have at least one argument".to_string());
                         None
@@ -92,10 +153,19 @@ impl<'a> Context<'a> {
                     None
                 }
             }
+            name if primitive_array_item(name).is_some() => {
+                let item = primitive_array_item(name).map(ExprType::Primitive)?;
+                let fun = FunType {
+                    args: vec![ExprType::Primitive(Primitive::Int)],
+                    ret_type: ExprType::Array(Box::new(item)),
+                };
+                self.check_call(name, &fun, args);
+                Some(fun.ret_type)
+            }
             other => {
                 let arg_count = args.map(|a| a.len()).unwrap_or(1);
                 match other {
-                    "readln" | "readlnInt" | "readlnBoolean" => {
+                    "readln" | "readLine" | "readlnInt" | "readlnBoolean" => {
                         if arg_count != 0 {
                             self.error_with_span(
                                 format!("{other} accepts no arguments"),
@@ -104,6 +174,7 @@ impl<'a> Context<'a> {
                         }
                         match other {
                             "readln" => Some(ExprType::Primitive(Primitive::String)),
+                            "readLine" => Some(ExprType::Primitive(Primitive::String).nullable()),
                             "readlnInt" => Some(ExprType::Primitive(Primitive::Int)),
                             "readlnBoolean" => Some(ExprType::Primitive(Primitive::Boolean)),
                             _ => unreachable!(),
@@ -123,37 +194,180 @@ impl<'a> Context<'a> {
         let name = ident.0.as_str();
 
         if let Some(ty) = self.find_predefined_fun_ret_type(ident, args) {
-            Some(ty)
+            return Some(ty);
+        }
+
+        let fun = if let Some(ty) = self.find_fun_value(name) {
+            ty
         } else if let Some(ty) = self.functions.get(name) {
-            if ty.args == args? {
-                Some(ty.ret_type.clone())
-            } else {
-                self.error(format!(
-                    "function with name {name} found but it's arguments wrong"
-                ));
-                None
-            }
+            ty.clone()
+        } else if self.classes.contains_key(name) {
+            self.find_constructor(name)?
+        } else if let Some(ty) = self
+            .find_var("this")
+            .and_then(|this| self.find_method(&this.ty, name))
+        {
+            ty.clone()
         } else {
             self.error_with_span(format!("function with name {name} not found"), ident.span);
+            return None;
+        };
+
+        self.check_call(name, &fun, args)
+    }
+
+    fn find_fun_params(&self, name: &str) -> Vec<ExprType> {
+        let fun = if let Some(ty) = self.find_fun_value(name) {
+            ty
+        } else if let Some(ty) = self.functions.get(name) {
+            ty.clone()
+        } else if self.classes.contains_key(name) {
+            self.constructor_type(name)
+        } else if let Some(ty) = self
+            .find_var("this")
+            .and_then(|this| self.find_method(&this.ty, name))
+        {
+            ty.clone()
+        } else {
+            return vec![];
+        };
+        fun.args
+    }
+
+    fn find_fun_value(&self, name: &str) -> Option<FunType> {
+        let var = self
+            .find_var(name)
+            .or_else(|| self.find_this_property(name))?;
+        fun_value_type(&var.ty)
+    }
+
+    fn check_call(
+        &mut self,
+        name: &str,
+        fun: &FunType,
+        args: Option<&[ExprType]>,
+    ) -> Option<ExprType> {
+        let args = args?;
+        let is_matching = fun.args.len() == args.len()
+            && fun
+                .args
+                .iter()
+                .zip(args)
+                .all(|(ty, arg)| self.is_assignable(ty, arg));
+        if is_matching {
+            fun.ret_type.clone().known()
+        } else {
+            self.error(format!(
+                "function with name {name} found but it's arguments wrong"
+            ));
             None
         }
     }
 
+    fn resolve_type(&mut self, ty: &Spanned<Type>) -> Option<ExprType> {
+        match self.convert_type(&ty.expr) {
+            Ok(ty) => Some(ty),
+            Err(err) => {
+                self.error_with_span(err, ty.span);
+                None
+            }
+        }
+    }
+
+    fn resolve_signature(&mut self, fun: &Fun) {
+        for (_, ty) in &fun.args {
+            self.resolve_type(ty);
+        }
+        if let Some(ref ty) = fun.ret_type {
+            self.resolve_type(ty);
+        }
+    }
+
+    fn convert_type(&self, ty: &Type) -> Result<ExprType, String> {
+        match ty {
+            Type::Simple(name) if self.classes.contains_key(name.0.as_str()) => {
+                Ok(ExprType::Class(name.0.clone()))
+            }
+            Type::Simple(_) => ExprType::try_from(ty),
+            Type::Generic(name, params) if name.0 == "Array" => match &params[..] {
+                [param] => Ok(ExprType::Array(Box::new(self.convert_type(param)?))),
+                _ => ExprType::try_from(ty),
+            },
+            Type::Generic(..) => ExprType::try_from(ty),
+            Type::Nullable(ty) => Ok(self.convert_type(ty)?.nullable()),
+            Type::Function(params, ret_type) => Ok(ExprType::Function(
+                params
+                    .iter()
+                    .map(|param| self.convert_type(param))
+                    .collect::<Result<_, _>>()?,
+                Box::new(self.convert_type(ret_type)?),
+            )),
+        }
+    }
+
+    fn fun_type(&self, fun: &Fun) -> FunType {
+        // Unknown types are reported by `Context::resolve_signature`
+        let convert = |ty: &Type| self.convert_type(ty).unwrap_or(ExprType::Unresolved);
+
+        let ret_type = if let Some(ref ty) = fun.ret_type {
+            convert(ty)
+        } else {
+            ExprType::Unit
+        };
+        FunType {
+            args: fun.args.iter().map(|(_, ty)| convert(ty)).collect(),
+            ret_type,
+        }
+    }
+
+    fn find_var(&self, ident: &str) -> Option<&Variable> {
+        self.scopes.iter().rev().find_map(|scope| scope.get(ident))
+    }
+
     fn find_var_type(&self, ident: &str) -> Option<ExprType> {
-        for scope in self.scopes.iter().rev() {
-            if let Some(ty) = scope.get(ident) {
-                return Some(ty.clone());
+        self.find_var(ident).map(|var| var.ty.clone())
+    }
+
+    fn add_var(&mut self, ident: String, ty: ExprType, is_mut: bool) {
+        let scope = self.scopes.last_mut().expect("scope exists");
+        match scope.entry(ident) {
+            Entry::Vacant(entry) => {
+                entry.insert(Variable {
+                    ty,
+                    is_mut,
+                    cast_from: None,
+                });
+            }
+            Entry::Occupied(entry) => {
+                let ident = entry.key().clone();
+                self.error(format!("binding {ident} already defined"));
             }
         }
-        None
     }
 
-    fn add_var_type(&mut self, ident: String, ty: ExprType) {
+    fn smart_cast(&mut self, ident: String, ty: ExprType) {
+        let Some(var) = self.find_var(&ident) else {
+            return;
+        };
+        let variable = Variable {
+            ty,
+            is_mut: var.is_mut,
+            cast_from: Some(var.cast_from.clone().unwrap_or_else(|| var.ty.clone())),
+        };
         let scope = self.scopes.last_mut().expect("scope exists");
-        if scope.get(&ident).is_some() {
-            self.error(format!("binding {ident} already defined"));
-        } else {
-            scope.insert(ident, ty);
+        scope.insert(ident, variable);
+    }
+
+    /// An assignment invalidates the null checks a `var` was narrowed by
+    fn drop_smart_casts(&mut self, ident: &str) {
+        for scope in self.scopes.iter_mut().rev() {
+            let Some(var) = scope.get_mut(ident) else {
+                continue;
+            };
+            match var.cast_from.take() {
+                Some(ty) => var.ty = ty,
+                None => return,
+            }
         }
     }
 
@@ -175,13 +389,31 @@ impl<'a> Context<'a> {
     }
 }
 
+fn fun_value_type(ty: &ExprType) -> Option<FunType> {
+    match ty {
+        ExprType::Function(args, ret_type) => Some(FunType {
+            args: args.clone(),
+            ret_type: ret_type.as_ref().clone(),
+        }),
+        _ => None,
+    }
+}
+
 pub fn check_program(prog: &Program) -> Vec<(Span, String)> {
     let mut context = Context::default();
 
+    context.declare_classes(prog);
+
     context.get_functions(prog);
 
+    context.get_classes(prog);
+
+    context.check_overrides(prog);
+
     context.check_main();
 
+    context.validate_properties(prog);
+
     context.validate_functions(prog);
 
     context.errors
@@ -202,7 +434,7 @@ pub fn pretty_print_error(source: &str, span: Span, message: &str) {
     let (error, after) = error.split_at(hi - lo);
 
     let line_num = before.chars().filter(|&c| c == '\n').count() + 1;
-    let before = before.split('\n').last().unwrap_or_default();
+    let before = before.split('\n').next_back().unwrap_or_default();
     let after = after.split('\n').next().unwrap_or_default();
 
     let line_count = source.chars().filter(|&c| c == '\n').count() + 1;
@@ -227,11 +459,22 @@ mod tests {
     use crate::lexer::*;
     use crate::parser::*;
 
+    fn errors(source: &str) -> Vec<String> {
+        let pairs: Vec<_> = Lexer::new(source).collect();
+        let (prog, _) = Program::try_parse(&pairs).unwrap();
+        assert_eq!(prog.syntax_errors(), Vec::<&SyntaxError>::new());
+        check_program(&prog)
+            .into_iter()
+            .map(|(_, message)| message)
+            .collect()
+    }
+
     #[test]
     fn validation() {
         fn validate(source: &str) {
             let pairs: Vec<_> = Lexer::new(source).collect();
             let (prog, _) = Program::try_parse(&pairs).unwrap();
+            assert!(prog.syntax_errors().is_empty());
             assert!(check_program(&prog).is_empty());
         }
 
@@ -239,5 +482,813 @@ mod tests {
         validate(include_str!("../samples/hello.kt"));
         validate(include_str!("../samples/factorial.kt"));
         validate(include_str!("../samples/sort.kt"));
+        validate(include_str!("../samples/loops.kt"));
+        validate(include_str!("../samples/when.kt"));
+        validate(include_str!("../samples/numbers.kt"));
+        validate(include_str!("../samples/nullable.kt"));
+        validate(include_str!("../samples/classes.kt"));
+        validate(include_str!("../samples/data.kt"));
+        validate(include_str!("../samples/enums.kt"));
+        validate(include_str!("../samples/sealed.kt"));
+        validate(include_str!("../samples/inheritance.kt"));
+        validate(include_str!("../samples/lambdas.kt"));
+        validate(include_str!("../samples/collections.kt"));
+        validate(include_str!("../samples/strings.kt"));
+        validate(include_str!("../samples/matrix.kt"));
+    }
+
+    #[test]
+    fn break_outside_loop() {
+        let source = r#"
+        fun main() {
+            if (true) {
+                break
+            }
+            continue
+        }
+        "#;
+
+        assert_eq!(
+            errors(source),
+            [
+                "break is only allowed inside a loop",
+                "continue is only allowed inside a loop"
+            ]
+        );
+    }
+
+    #[test]
+    fn val_reassignment() {
+        let source = r#"
+        fun inc(number: Int): Int {
+            number = number + 1
+            return number
+        }
+
+        fun main() {
+            val a = 1
+            var b = 2
+            b = a
+            a = b
+            for (i in 0..b) {
+                i = a
+            }
+        }
+        "#;
+
+        assert_eq!(errors(source), ["val cannot be reassigned"; 3]);
+    }
+
+    #[test]
+    fn when_value() {
+        let source = r#"
+        fun main() {
+            val a = 1
+            when (a) {
+                1 -> println("one")
+            }
+            val b = when (a) {
+                1 -> "one"
+            }
+            val c = when {
+                a > 1 -> "big"
+                else -> 1
+            }
+            when (a) {
+                "one" -> println("one")
+                in "one" -> println("one")
+            }
+        }
+        "#;
+
+        assert_eq!(
+            errors(source),
+            [
+                "when used as a value must have an else branch",
+                "branches have different types",
+                "condition type doesn't match subject type",
+                "subject can't be contained in the value",
+            ]
+        );
+    }
+
+    #[test]
+    fn if_value() {
+        let source = r#"
+        fun max(a: Int, b: Int): Int {
+            return if (a > b) a else b
+        }
+
+        fun abs(a: Int): Int {
+            val b = if (a < 0) {
+                -a
+            } else {
+                return a
+            }
+            return b
+        }
+
+        fun main() {
+            val a = if (true) 1
+            val b = if (true) 1 else "one"
+        }
+        "#;
+
+        assert_eq!(
+            errors(source),
+            [
+                "if used as a value must have an else branch",
+                "branches have different types",
+                "branches have different types",
+            ]
+        );
+    }
+
+    #[test]
+    fn types() {
+        let source = r#"
+        fun next(c: Char): Char {
+            return c + 1
+        }
+
+        fun codes(chars: Array<Char>, unknown: Byte): Array<Int> {
+            return arrayOf(chars[0].code, chars[1] - 'a')
+        }
+
+        fun main() {
+            val c = 'a' + 'b'
+            val i = 1 - 'a'
+            val s = "a".code
+        }
+        "#;
+
+        assert_eq!(
+            errors(source),
+            [
+                "unknown type Byte",
+                "wrong operands",
+                "wrong operands",
+                "unresolved reference: code",
+            ]
+        );
+    }
+
+    #[test]
+    fn unknown_types() {
+        let source = r#"
+        fun f(c: Foo): Int {
+            return c
+        }
+
+        fun g(): Bar {
+            return 1
+        }
+
+        class Box(val item: Baz) {
+            fun twice(): Int {
+                return item + item
+            }
+        }
+
+        fun main() {
+            val n: Int = f(1) + g()
+            println(Box(2).item + 1)
+        }
+        "#;
+
+        assert_eq!(
+            errors(source),
+            ["unknown type Foo", "unknown type Bar", "unknown type Baz"]
+        );
+    }
+
+    #[test]
+    fn numbers() {
+        let source = r#"
+        fun half(value: Long): Double {
+            return value / 2.0
+        }
+
+        fun main() {
+            val b = 1L == 1
+            val c = 1.5f < 2L
+            val d = true * 2
+            for (x in 0.0..1.0) {
+                println(x)
+            }
+        }
+        "#;
+
+        assert_eq!(
+            errors(source),
+            [
+                "wrong operands",
+                "wrong operands",
+                "only ranges of Int, Long and Char are iterable",
+            ]
+        );
+    }
+
+    #[test]
+    fn nullability() {
+        let source = r#"
+        fun length(word: String?): Int {
+            if (word == null || word.length == 0) {
+                return 0
+            }
+            val first = if (word.length > 1) word[0] else null
+            return word.length + (first?.code ?: 0)
+        }
+
+        fun main() {
+            val a: String = null
+            val b: String? = readLine()
+            println(b.length)
+            var c: Int? = 1
+            if (c != null) {
+                c = c + 1
+                c = null
+                println(c + 1)
+            }
+            if (c != null) {
+                for (i in 1..3) {
+                    println(c * i)
+                    c = null
+                }
+            }
+            val d = b ?: 1
+        }
+        "#;
+
+        assert_eq!(
+            errors(source),
+            [
+                "type mismatch: expected String, found Nothing?",
+                "only safe (?.) or non-null asserted (!!.) calls are allowed on a nullable receiver of type String?",
+                "wrong operands",
+                "wrong operands",
+                "elvis operands have incompatible types String and Int",
+            ]
+        );
+    }
+
+    #[test]
+    fn assignment_nested_in_loop() {
+        let source = r#"
+        fun main() {
+            var c: Int? = 1
+            if (c != null) {
+                for (i in 1..3) {
+                    println(c + i)
+                    val y = if (i == 2) {
+                        c = null
+                        1
+                    } else 2
+                }
+            }
+        }
+        "#;
+
+        assert_eq!(errors(source), ["wrong operands"]);
+    }
+
+    #[test]
+    fn classes() {
+        let source = r#"
+        class Point(val x: Int, var y: Int) {
+            fun len(): Int {
+                return x + this.y
+            }
+
+            fun reset() {
+                y = 0
+                x = 0
+            }
+        }
+
+        fun main() {
+            val p = Point(1, "two")
+            val q = Point(1, 2)
+            q.x = 3
+            q.y = "three"
+            println(q.z)
+            q.area()
+            val n: Int = q.len()
+            val r: Segment = q
+            println(this)
+        }
+        "#;
+
+        assert_eq!(
+            errors(source),
+            [
+                "function with name Point found but it's arguments wrong",
+                "val cannot be reassigned",
+                "type mismatch: expected Int, found String",
+                "unresolved reference: z",
+                "unresolved reference: area",
+                "unknown type Segment",
+                "'this' is not defined in this context",
+                "val cannot be reassigned",
+            ]
+        );
+    }
+
+    #[test]
+    fn data_classes() {
+        let source = r#"
+        data class Empty()
+
+        data class Pair(val first: Int, second: String)
+
+        data class User(val name: String, val age: Int)
+
+        fun main() {
+            val user = User("Alice", 30)
+            val (name, age) = user
+            val n: Int = age
+            val (a, b, c) = user
+            val older = user.copy(age = 31, name = "Bob")
+            user.copy(nick = "A")
+            user.copy("Bob", name = "Bob")
+            user.copy(age = "31")
+            val same: Boolean = user.equals(older)
+            println(user.len(last = 1))
+            val (x) = 1
+        }
+        "#;
+
+        assert_eq!(
+            errors(source),
+            [
+                "data class must have at least one primary constructor parameter",
+                "data class primary constructor must only have property (val / var) parameters",
+                "destructuring declaration initializer of type User must have a component3() function",
+                "cannot find a parameter with name nick",
+                "an argument is already passed for name",
+                "type mismatch: expected Int, found String for age",
+                "named arguments are only supported by copy",
+                "destructuring declaration initializer of type Int must have a component1() function",
+            ]
+        );
+    }
+
+    #[test]
+    fn enums() {
+        let source = r#"
+        enum class Color(val hex: String) {
+            RED("f00"), GREEN(0);
+
+            fun warm(): Boolean {
+                return this == RED
+            }
+        }
+
+        enum class Size { S, M, L }
+
+        fun name(size: Size): String {
+            return when (size) {
+                Size.S -> "small"
+                Size.M -> "medium"
+            }
+        }
+
+        fun main() {
+            val c = Color("000")
+            val n: String = Color.RED.name
+            val i: Int = Color.GREEN.ordinal
+            val all: Array<Color> = Color.values()
+            Size.valueOf(1)
+            val m: Size = Size.valueOf("M")
+            println(Color.BLUE)
+            when (m) {
+                Size.S -> println(0)
+                Color.RED -> println(1)
+                else -> println(2)
+            }
+        }
+        "#;
+
+        assert_eq!(
+            errors(source),
+            [
+                "function with name Color found but it's arguments wrong",
+                "'when' expression must be exhaustive, add necessary 'L' branches or 'else' branch instead",
+                "enum types cannot be instantiated",
+                "function with name valueOf found but it's arguments wrong",
+                "unresolved reference: BLUE",
+                "condition type doesn't match subject type",
+            ]
+        );
+    }
+
+    #[test]
+    fn duplicate_enum_entries() {
+        let source = r#"
+        enum class E { X, Y, X }
+
+        fun main() {
+            println(E.X)
+        }
+        "#;
+
+        assert_eq!(errors(source), ["conflicting declarations: enum entry X"]);
+    }
+
+    #[test]
+    fn sealed_classes() {
+        let source = r#"
+        sealed interface Expr
+
+        class Num(val value: Int) : Expr
+
+        sealed class Op(val left: Expr, val right: Expr) : Expr
+
+        class Add(left: Expr, right: Expr) : Op(left, right)
+
+        class Mul(left: Expr, right: Expr) : Op(left, right)
+
+        class Neg(val expr: Expr) : Num
+
+        class Unit : Op
+
+        fun eval(expr: Expr): Int {
+            return when (expr) {
+                is Num -> expr.value
+                is Add -> eval(expr.left) + eval(expr.right)
+            }
+        }
+
+        fun check(expr: Expr?) {
+            when (expr) {
+                is Num, is Op -> println(expr.value)
+                is String -> println(0)
+            }
+            when {
+                is Num -> println(1)
+            }
+        }
+
+        fun main() {
+            val op = Op(Num(1), Num(2))
+            val sum: Expr = Add(Num(1), Num(2))
+            val product: Op = Num(2)
+        }
+        "#;
+
+        assert_eq!(
+            errors(source),
+            [
+                "this type is final, so it cannot be inherited from",
+                "this type has a constructor, and thus must be initialized here",
+                "'when' expression must be exhaustive, add necessary 'is Mul' branches or 'else' branch instead",
+                "only safe (?.) or non-null asserted (!!.) calls are allowed on a nullable receiver of type Expr?",
+                "incompatible types: String and Expr",
+                "'when' expression must be exhaustive, add necessary 'null' branches or 'else' branch instead",
+                "is condition requires when subject",
+                "sealed types cannot be instantiated",
+                "type mismatch: expected Op, found Num",
+            ]
+        );
+    }
+
+    #[test]
+    fn inheritance() {
+        let source = r#"
+        interface Shape {
+            fun area(): Double
+            fun name(): String {
+                return "shape"
+            }
+        }
+
+        interface Solid : Base
+
+        open class Base(val id: Int) {
+            fun id(): Int {
+                return id
+            }
+
+            open fun scale(factor: Int): Base {
+                return this
+            }
+        }
+
+        abstract class Figure : Base(0), Shape {
+            abstract fun sides(): Int {
+                return 0
+            }
+            fun corners(): Int
+        }
+
+        class Square(val side: Double) : Figure() {
+            override fun area(): Double {
+                return side * side
+            }
+
+            fun name(): String {
+                return "square"
+            }
+
+            override fun id(): Int {
+                return 1
+            }
+
+            override fun scale(factor: String): Base {
+                return this
+            }
+
+            override fun perimeter(): Double {
+                return side * 4.0
+            }
+
+            fun toString(): String {
+                return "square"
+            }
+        }
+
+        class Circle(val r: Double) : Base(1), Shape {
+            abstract fun round(): Boolean
+
+            override fun scale(factor: Int): Circle {
+                return this
+            }
+        }
+
+        class Point : Base(0) {
+            override fun scale(factor: Int): Int {
+                return factor
+            }
+        }
+
+        abstract fun helper()
+
+        fun main() {
+            val figure = Figure()
+            val shape: Shape = Circle(1.0)
+            val base: Base = shape
+            val bases = arrayOf(Point(), Circle(2.0))
+            val first: Base = bases[0]
+        }
+        "#;
+
+        assert_eq!(
+            errors(source),
+            [
+                "an interface cannot inherit from a class",
+                "function helper must have a body",
+                "modifiers are not applicable to top level functions",
+                "abstract function sides cannot have a body",
+                "function corners without a body must be abstract",
+                "abstract function round in non-abstract class Circle",
+                "name hides member of supertype Shape and needs 'override' modifier",
+                "id in Base is final and cannot be overridden",
+                "scale overrides nothing",
+                "perimeter overrides nothing",
+                "toString hides member of supertype Any and needs 'override' modifier",
+                "class Square is not abstract and does not implement abstract member sides",
+                "class Circle is not abstract and does not implement abstract member area",
+                "return type of scale is not a subtype of the return type of the overridden member",
+                "cannot create an instance of an abstract class Figure",
+                "type mismatch: expected Base, found Shape",
+            ]
+        );
+    }
+
+    #[test]
+    fn declaration_errors_are_located() {
+        let source = r#"
+        open class Base {
+            fun id(): Int {
+                return 0
+            }
+        }
+
+        open class Node : Node()
+
+        data class Empty()
+
+        class Square : Base() {
+            override fun id(): Int {
+                return 1
+            }
+
+            override fun perimeter(): Int {
+                return 4
+            }
+        }
+
+        fun main() {}
+        "#;
+
+        let pairs: Vec<_> = Lexer::new(source).collect();
+        let (prog, _) = Program::try_parse(&pairs).unwrap();
+        let located: Vec<_> = check_program(&prog)
+            .into_iter()
+            .map(|(span, message)| (&source[span.lo..span.hi], message))
+            .collect();
+
+        assert_eq!(
+            located,
+            [
+                (
+                    "Node",
+                    "there's a cycle in the inheritance hierarchy for Node".to_string()
+                ),
+                (
+                    "Empty",
+                    "data class must have at least one primary constructor parameter".to_string()
+                ),
+                (
+                    "id",
+                    "id in Base is final and cannot be overridden".to_string()
+                ),
+                ("perimeter", "perimeter overrides nothing".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn lambdas() {
+        let source = r#"
+        fun apply(x: Int, f: (Int) -> Int): Int {
+            return f(x)
+        }
+
+        fun check(f: (Int, Int) -> Boolean): Boolean {
+            return f(1, 2)
+        }
+
+        fun main() {
+            val double: (Int) -> Int = { it * 2 }
+            val wrong: (Int) -> String = { it + 1 }
+            apply(1) { a, b -> a }
+            apply(1) { x: String -> 1 }
+            val untyped = { x -> x }
+            check { a, b -> a < b }
+            check { a, b -> a + b }
+            double("1")
+            val f = { x: Int ->
+                return x
+            }
+            for (i in 0..2) {
+                apply(i) {
+                    break
+                }
+            }
+            val nothing: () -> Unit = { println(it) }
+            val ok: ((Int) -> Int)? = double
+            val compared: (Int, Int) -> Boolean = { a, b -> a < b }
+            val anyInt: (Int) -> Int = compared
+            val fs = arrayOf(double)
+            val two: Int = fs[0](1)
+            val three: String = apply(1, double)(2)
+            fs[0]("1")
+        }
+        "#;
+
+        assert_eq!(
+            errors(source),
+            [
+                "type mismatch: expected String, found Int",
+                "expected 1 parameters, found 2",
+                "type mismatch: expected Int, found String",
+                "cannot infer a type for this parameter, specify it explicitly",
+                "type mismatch: expected Boolean, found Int",
+                "function with name double found but it's arguments wrong",
+                "'return' is not allowed here",
+                "break is only allowed inside a loop",
+                "ident it not found",
+                "type mismatch: expected (Int) -> Int, found (Int, Int) -> Boolean",
+                "expression of type Int cannot be invoked as a function",
+                "function with name invoke found but it's arguments wrong",
+            ]
+        );
+    }
+
+    #[test]
+    fn array_builtins() {
+        let source = r#"
+        data class Point(val x: Int, val y: Int)
+
+        fun main() {
+            val points = arrayOf(Point(1, 2))
+            val words = arrayOf("a", "b")
+            val total: Int = words.size + words.lastIndex
+            points.sum()
+            points.sorted()
+            words.indexOf(1)
+            words.joinToString(", ", "!")
+            words.length
+            val sizes = IntArray("3")
+            val items = Array(3) { x: String -> x }
+            val strings: Array<String> = Array(2) { "$it" }
+            val chars = Array(2) { it * 2 }
+            val first: String = chars[0]
+        }
+        "#;
+
+        assert_eq!(
+            errors(source),
+            [
+                "unresolved reference: sum",
+                "unresolved reference: sorted",
+                "function with name indexOf found but it's arguments wrong",
+                "function with name joinToString found but it's arguments wrong",
+                "unresolved reference: length",
+                "function with name IntArray found but it's arguments wrong",
+                "type mismatch: expected Int, found String",
+                "type mismatch: expected String, found Int",
+            ]
+        );
+    }
+
+    #[test]
+    fn builtin_members() {
+        let source = r#"
+        class Box(val size: Int) {
+            fun double(): Box {
+                return Box(size * 2)
+            }
+        }
+
+        fun main() {
+            val size: Int = Box(1).double().double().size
+            val text: String = "abc".uppercase().trim()
+            val length: String = (text + "d").length
+            "abc".substring("1")
+            "abc".size
+            (1..3).first.code
+            (1.0..2.0).sum()
+            val last: Char = ('a'..'z').last
+            Box(1).double().width
+        }
+        "#;
+
+        assert_eq!(
+            errors(source),
+            [
+                "type mismatch: expected String, found Int",
+                "function with name substring found but it's arguments wrong",
+                "unresolved reference: size",
+                "unresolved reference: code",
+                "unresolved reference: sum",
+                "unresolved reference: width",
+            ]
+        );
+    }
+
+    #[test]
+    fn index_any_expression() {
+        let source = r#"
+        fun grid(): Array<Array<Int>> {
+            return Array(2) { Array(2) { 0 } }
+        }
+
+        fun main() {
+            val m = grid()
+            m[0][1] = 5
+            val first: Int = grid()[0][0]
+            val char: Char = "a b".split(" ")[1][0]
+            m[0]["1"] = 2
+            m[0][0] = "five"
+            "abc"[0] = 'd'
+            first[0]
+        }
+        "#;
+
+        assert_eq!(
+            errors(source),
+            [
+                "only Int index is supported",
+                "wrong operands",
+                "no set method providing array access for String",
+                "index operator is only supported by arrays and strings",
+            ]
+        );
+    }
+
+    #[test]
+    fn skips_invalid_statements() {
+        let source = r#"
+        fun half(a: Int): Int {
+            val b = a +* 2
+            return b / 2
+        }
+
+        fun main() {
+            val x: String = half(4)
+            println(x +)
+        }
+        "#;
+        let pairs: Vec<_> = Lexer::new(source).collect();
+        let (prog, _) = Program::try_parse(&pairs).unwrap();
+        assert_eq!(prog.syntax_errors().len(), 2);
+
+        let errors: Vec<_> = check_program(&prog)
+            .into_iter()
+            .map(|(_, message)| message)
+            .collect();
+        assert_eq!(errors, ["type mismatch: expected String, found Int"]);
     }
 }
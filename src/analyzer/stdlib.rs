@@ -0,0 +1,133 @@
+use super::validation::{ExprType, FunType, Primitive, Validate};
+use super::Context;
+use crate::parser::expr::{Call, Expr, ShortExpr};
+
+pub(super) fn builtin_property(receiver: &ExprType, name: &str) -> Option<ExprType> {
+    use Primitive::*;
+    let int = ExprType::Primitive(Int);
+    let ty = match (receiver, name) {
+        (ExprType::Primitive(Char), "code") => int,
+        (ExprType::Primitive(String), "length") => int,
+        (ExprType::Primitive(String) | ExprType::Array(_), "lastIndex") => int,
+        (ExprType::Primitive(String) | ExprType::Array(_), "indices") => {
+            ExprType::Range(Box::new(int))
+        }
+        (ExprType::Array(_), "size") => int,
+        (ExprType::Range(item), "first" | "last") => item.as_ref().clone(),
+        _ => return None,
+    };
+    Some(ty)
+}
+
+/// Signature of a method of a built-in type,
+/// methods with optional parameters are resolved by the number of arguments
+pub(super) fn builtin_method(receiver: &ExprType, name: &str, arg_count: usize) -> Option<FunType> {
+    let string = ExprType::Primitive(Primitive::String);
+    let (args, ret_type) = match (receiver, name) {
+        (ExprType::Class(_), _) => return None,
+        (_, "toString") => (vec![], string),
+        (ExprType::Primitive(Primitive::String), name) => return string_method(name, arg_count),
+        (ExprType::Array(item), name) => return array_method(item, name, arg_count),
+        (ExprType::Range(item), name) => return range_method(item, name),
+        _ => return None,
+    };
+    Some(FunType { args, ret_type })
+}
+
+fn string_method(name: &str, arg_count: usize) -> Option<FunType> {
+    use Primitive::*;
+    let [int, string, boolean] = [Int, String, Boolean].map(ExprType::Primitive);
+    let (args, ret_type) = match name {
+        "uppercase" | "lowercase" | "trim" | "reversed" => (vec![], string),
+        "isEmpty" | "isNotEmpty" | "isBlank" => (vec![], boolean),
+        "startsWith" | "endsWith" | "contains" => (vec![string], boolean),
+        "indexOf" => (vec![string], int),
+        "substring" if arg_count == 1 => (vec![int], string),
+        "substring" => (vec![int.clone(), int], string),
+        "repeat" => (vec![int], string),
+        "split" => (vec![string.clone()], ExprType::Array(Box::new(string))),
+        "toInt" => (vec![], int),
+        "toIntOrNull" => (vec![], int.nullable()),
+        "toDouble" => (vec![], ExprType::Primitive(Double)),
+        _ => return None,
+    };
+    Some(FunType { args, ret_type })
+}
+
+fn array_method(item: &ExprType, name: &str, arg_count: usize) -> Option<FunType> {
+    let array = ExprType::Array(Box::new(item.clone()));
+    let (args, ret_type) = match name {
+        "sum" if item.is_numeric() => (vec![], item.clone()),
+        "min" | "max" if item.is_comparable() => (vec![], item.clone()),
+        "sorted" if item.is_comparable() => (vec![], array),
+        "reversed" | "copyOf" => (vec![], array),
+        "indexOf" => (vec![item.clone()], ExprType::Primitive(Primitive::Int)),
+        "contains" => (vec![item.clone()], ExprType::Primitive(Primitive::Boolean)),
+        "fill" => (vec![item.clone()], ExprType::Unit),
+        "joinToString" if arg_count == 0 => (vec![], ExprType::Primitive(Primitive::String)),
+        "joinToString" => (
+            vec![ExprType::Primitive(Primitive::String)],
+            ExprType::Primitive(Primitive::String),
+        ),
+        _ => return None,
+    };
+    Some(FunType { args, ret_type })
+}
+
+fn range_method(item: &ExprType, name: &str) -> Option<FunType> {
+    let (args, ret_type) = match name {
+        "isEmpty" => (vec![], ExprType::Primitive(Primitive::Boolean)),
+        "contains" => (vec![item.clone()], ExprType::Primitive(Primitive::Boolean)),
+        // Only ranges of integers can be iterated
+        "sum" if matches!(item, ExprType::Primitive(Primitive::Int | Primitive::Long)) => {
+            (vec![], item.clone())
+        }
+        _ => return None,
+    };
+    Some(FunType { args, ret_type })
+}
+
+pub(super) fn primitive_array_item(name: &str) -> Option<Primitive> {
+    let primitive = match name {
+        "IntArray" => Primitive::Int,
+        "LongArray" => Primitive::Long,
+        "FloatArray" => Primitive::Float,
+        "DoubleArray" => Primitive::Double,
+        "BooleanArray" => Primitive::Boolean,
+        "CharArray" => Primitive::Char,
+        _ => return None,
+    };
+    Some(primitive)
+}
+
+impl Context<'_> {
+    pub(super) fn validate_array_constructor(&mut self, call: &Call) -> Option<ExprType> {
+        let [size, init] = &call.args[..] else {
+            self.error("Array accepts a size and an initializer".to_string());
+            return None;
+        };
+
+        let int = ExprType::Primitive(Primitive::Int);
+        let size = size.validate(self);
+        let init = match &init.expr {
+            Expr::ShortExpr(ShortExpr::Lambda(lambda)) => {
+                let span = self.last_span.replace(init.span);
+                let ty = self.validate_lambda(lambda, Some(std::slice::from_ref(&int)), None);
+                self.last_span = span;
+                ty
+            }
+            _ => init.validate(self),
+        };
+
+        let (size, init) = (size?, init?);
+        match init {
+            ExprType::Function(params, item) if size == int && params == [int] => {
+                Some(ExprType::Array(item))
+            }
+            _ => {
+                self.error("function with name Array found but it's arguments wrong".to_string());
+                None
+            }
+        }
+    }
+}
@@ -2,24 +2,47 @@ use super::*;
 
 impl Validate for expr::ComparisonExpr {
     fn validate(&self, context: &mut Context) -> Option<ExprType> {
-        let ty = match self {
-            ComparisonExpr::LessThan(expr) => {
-                ensure_type_equality(expr.left.as_ref(), expr.right.as_ref(), context)
-            }
-            ComparisonExpr::MoreThan(expr) => {
-                ensure_type_equality(expr.left.as_ref(), expr.right.as_ref(), context)
-            }
-            ComparisonExpr::And(expr) => {
-                ensure_type_equality(expr.left.as_ref(), expr.right.as_ref(), context)
-            }
-            ComparisonExpr::Or(expr) => {
-                ensure_type_equality(expr.left.as_ref(), expr.right.as_ref(), context)
+        use ComparisonExpr::*;
+        let (left, right) = match self {
+            LessThan(expr) => (&expr.left, &expr.right),
+            MoreThan(expr) => (&expr.left, &expr.right),
+            LessOrEqual(expr) => (&expr.left, &expr.right),
+            MoreOrEqual(expr) => (&expr.left, &expr.right),
+            Equal(expr) => (&expr.left, &expr.right),
+            NotEqual(expr) => (&expr.left, &expr.right),
+            And(expr) => (&expr.left, &expr.right),
+            Or(expr) => (&expr.left, &expr.right),
+        };
+
+        let boolean = ExprType::Primitive(Primitive::Boolean);
+
+        // Right operand of `&&` and `||` is only evaluated after the left one has been checked
+        let l = left.validate(context);
+        let r = match self {
+            And(_) => with_smart_casts(left, true, context, |context| right.validate(context)),
+            Or(_) => with_smart_casts(left, false, context, |context| right.validate(context)),
+            _ => right.validate(context),
+        };
+        let (l, r) = (l?, r?);
+
+        // Numbers of different types can be ordered, but not checked for equality
+        let is_ordering = !matches!(self, Equal(_) | NotEqual(_) | And(_) | Or(_));
+        if is_ordering && promote_numeric(&l, &r).is_some() {
+            return Some(boolean);
+        }
+
+        // Nullable values can be compared with `null` and with values of their base type
+        let is_equality = matches!(self, Equal(_) | NotEqual(_));
+        let ty = match context.common_supertype(&l, &r) {
+            Some(ty) if l == r || is_equality => ty,
+            _ => {
+                context.error("wrong operands".to_owned());
+                return None;
             }
         };
 
-        if matches!(self, ComparisonExpr::And(_) | ComparisonExpr::Or(_)) {
-            let boolean = ExprType::Primitive(Primitive::Boolean);
-            return if ty? != boolean {
+        if matches!(self, And(_) | Or(_)) {
+            return if ty != boolean {
                 context.error("boolean operators only applicable to booleans".to_string());
                 None
             } else {
@@ -27,16 +50,28 @@ impl Validate for expr::ComparisonExpr {
             };
         }
 
+        // Equality is defined for every value, including booleans, arrays and ranges
+        if matches!(self, Equal(_) | NotEqual(_)) {
+            return if ty == ExprType::Unit {
+                context.error("can't compare values of unit type".to_string());
+                None
+            } else {
+                Some(boolean)
+            };
+        }
+
         // Arrays and ranges aren't comparable
         match ty {
-            Some(ExprType::Primitive(primitive)) => match primitive {
-                Primitive::Int | Primitive::String | Primitive::Char => {
-                    Some(ExprType::Primitive(Primitive::Boolean))
-                }
+            ExprType::Primitive(primitive) => match primitive {
+                Primitive::String | Primitive::Char => Some(boolean),
                 Primitive::Boolean => {
                     context.error("can't compare booleans".to_string());
                     None
                 }
+                // Handled by numeric promotion above
+                Primitive::Int | Primitive::Long | Primitive::Float | Primitive::Double => {
+                    unreachable!()
+                }
             },
             _ => {
                 context.error("can't compare types".to_string());
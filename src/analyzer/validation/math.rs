@@ -5,8 +5,8 @@ impl Validate for expr::MathExpr {
         match self {
             MathExpr::Neg(expr) => {
                 let ty = expr.0.validate(context)?;
-                if ty != ExprType::Primitive(Primitive::Int) {
-                    context.error("negation only applicable to Int type".to_string());
+                if promote_numeric(&ty, &ty).is_none() {
+                    context.error("negation only applicable to numeric types".to_string());
                     None
                 } else {
                     Some(ty)
@@ -21,22 +21,26 @@ impl Validate for expr::MathExpr {
                     Some(ty)
                 }
             }
-            MathExpr::Parens(expr) => expr.0.validate(context),
             MathExpr::Range(expr) => expr.validate(context),
-            MathExpr::Sub(expr) => {
-                ensure_type_equality(expr.left.as_ref(), expr.right.as_ref(), context)
-            }
-            MathExpr::Mul(expr) => {
-                ensure_type_equality(expr.left.as_ref(), expr.right.as_ref(), context)
-            }
-            MathExpr::Div(expr) => {
-                ensure_type_equality(expr.left.as_ref(), expr.right.as_ref(), context)
-            }
+            MathExpr::Sub(expr) => expr.validate(context),
+            MathExpr::Mul(expr) => validate_arithmetic(&expr.left, &expr.right, context),
+            MathExpr::Div(expr) => validate_arithmetic(&expr.left, &expr.right, context),
+            MathExpr::Rem(expr) => validate_arithmetic(&expr.left, &expr.right, context),
             MathExpr::Add(expr) => expr.validate(context),
         }
     }
 }
 
+fn validate_arithmetic(
+    left: &BoxedExpr,
+    right: &BoxedExpr,
+    context: &mut Context,
+) -> Option<ExprType> {
+    let left = left.validate(context);
+    let right = right.validate(context)?;
+    promote_operands(&left?, &right, context)
+}
+
 impl Validate for expr::Range {
     fn validate(&self, context: &mut Context) -> Option<ExprType> {
         let ty = ensure_type_equality(self.left.as_ref(), self.right.as_ref(), context)?;
@@ -55,7 +59,42 @@ impl Validate for expr::Add {
         } else if [&left, &right].contains(&&ExprType::Primitive(Primitive::String)) {
             Some(ExprType::Primitive(Primitive::String))
         } else {
-            ensure_type_equality(self.left.as_ref(), self.right.as_ref(), context)
+            use Primitive::*;
+            match (left, right) {
+                (ExprType::Primitive(Char), ExprType::Primitive(Int)) => {
+                    Some(ExprType::Primitive(Char))
+                }
+                (ExprType::Array(left), ExprType::Array(right)) if left == right => {
+                    Some(ExprType::Array(left))
+                }
+                (left, right) => promote_operands(&left, &right, context),
+            }
+        }
+    }
+}
+
+impl Validate for expr::Sub {
+    fn validate(&self, context: &mut Context) -> Option<ExprType> {
+        let left = self.left.validate(context);
+        let right = self.right.validate(context)?;
+
+        use Primitive::*;
+        match (left?, right) {
+            (ExprType::Primitive(Char), ExprType::Primitive(Int)) => {
+                Some(ExprType::Primitive(Char))
+            }
+            (ExprType::Primitive(Char), ExprType::Primitive(Char)) => {
+                Some(ExprType::Primitive(Int))
+            }
+            (left, right) => promote_operands(&left, &right, context),
         }
     }
 }
+
+fn promote_operands(left: &ExprType, right: &ExprType, context: &mut Context) -> Option<ExprType> {
+    let ty = promote_numeric(left, right);
+    if ty.is_none() {
+        context.error("wrong operands".to_owned());
+    }
+    ty
+}
@@ -1,54 +1,150 @@
 mod comparison;
 mod math;
+mod null_safety;
 mod short;
 mod top;
 
-use crate::parser::expr::{self, ComparisonExpr, MathExpr};
-use crate::parser::{Fun, Spanned, Type};
+use crate::parser::expr::{self, ComparisonExpr, Expr, MathExpr};
+use crate::parser::{Body, BoxedExpr, Spanned, Type};
+pub use null_safety::{drop_smart_casts_in_loop, smart_cast_after, with_smart_casts};
 
-use super::Context;
+use std::collections::HashMap;
+
+use super::{
+    classes::CoveredCases,
+    fun_value_type,
+    stdlib::{builtin_method, builtin_property},
+    Context, Variable,
+};
 
 #[derive(Debug, PartialEq, Clone)]
 pub enum Primitive {
     Int,
+    Long,
+    Float,
+    Double,
     String,
     Boolean,
     Char,
 }
 
+impl Primitive {
+    fn numeric_rank(&self) -> Option<u8> {
+        match self {
+            Primitive::Int => Some(0),
+            Primitive::Long => Some(1),
+            Primitive::Float => Some(2),
+            Primitive::Double => Some(3),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub enum ExprType {
     Primitive(Primitive),
     Array(Box<ExprType>),
     Range(Box<ExprType>),
     Unit,
+    Nothing,
+    Nullable(Box<ExprType>),
+    Null,
+    Class(String),
+    Function(Vec<ExprType>, Box<ExprType>),
+    /// Declared type that failed to resolve, the error is already reported
+    Unresolved,
 }
 
-impl From<&Type> for ExprType {
-    fn from(value: &Type) -> Self {
+impl ExprType {
+    pub fn nullable(self) -> ExprType {
+        match self {
+            ExprType::Nullable(_) | ExprType::Null => self,
+            ty => ExprType::Nullable(Box::new(ty)),
+        }
+    }
+
+    pub fn is_numeric(&self) -> bool {
+        matches!(self, ExprType::Primitive(primitive) if primitive.numeric_rank().is_some())
+    }
+
+    pub fn is_comparable(&self) -> bool {
+        self.is_numeric()
+            || matches!(
+                self,
+                ExprType::Primitive(Primitive::String | Primitive::Char)
+            )
+    }
+
+    /// Checks depending on an unresolved type are skipped, like for invalid expressions
+    pub fn known(self) -> Option<ExprType> {
+        (self != ExprType::Unresolved).then_some(self)
+    }
+
+    pub fn non_null(self) -> ExprType {
+        match self {
+            ExprType::Nullable(ty) => *ty,
+            ExprType::Null => ExprType::Nothing,
+            ty => ty,
+        }
+    }
+}
+
+impl std::fmt::Display for ExprType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ExprType::Primitive(primitive) => write!(f, "{primitive:?}"),
+            ExprType::Array(ty) => write!(f, "Array<{ty}>"),
+            ExprType::Range(ty) => write!(f, "ClosedRange<{ty}>"),
+            ExprType::Unit => write!(f, "Unit"),
+            ExprType::Nothing => write!(f, "Nothing"),
+            ExprType::Nullable(ty) if matches!(**ty, ExprType::Function(..)) => {
+                write!(f, "({ty})?")
+            }
+            ExprType::Nullable(ty) => write!(f, "{ty}?"),
+            ExprType::Null => write!(f, "Nothing?"),
+            ExprType::Class(name) => write!(f, "{name}"),
+            ExprType::Function(params, ret_type) => {
+                let params: Vec<_> = params.iter().map(ToString::to_string).collect();
+                write!(f, "({}) -> {ret_type}", params.join(", "))
+            }
+            ExprType::Unresolved => write!(f, "<unresolved>"),
+        }
+    }
+}
+
+impl TryFrom<&Type> for ExprType {
+    type Error = String;
+
+    fn try_from(value: &Type) -> Result<Self, Self::Error> {
         match value {
             Type::Simple(value) => {
                 use Primitive::*;
                 let primitive = match value.0.as_str() {
                     "Int" => Int,
+                    "Long" => Long,
+                    "Float" => Float,
+                    "Double" => Double,
                     "String" => String,
                     "Boolean" => Boolean,
-                    _ => return ExprType::Unit,
+                    "Char" => Char,
+                    "Unit" => return Ok(ExprType::Unit),
+                    other => return Err(format!("unknown type {other}")),
                 };
-                Self::Primitive(primitive)
-            }
-            Type::Generic(ty, params) => {
-                if params.len() != 1 || ty.0 != "Array" {
-                    Self::Unit
-                } else {
-                    let param = ExprType::from(&params[0]);
-                    if param != ExprType::Unit {
-                        ExprType::Array(Box::new(param))
-                    } else {
-                        ExprType::Unit
-                    }
-                }
+                Ok(Self::Primitive(primitive))
             }
+            Type::Generic(ty, params) => match (ty.0.as_str(), &params[..]) {
+                ("Array", [param]) => Ok(ExprType::Array(Box::new(param.try_into()?))),
+                ("Array", _) => Err("Array must have exactly one type parameter".to_string()),
+                (other, _) => Err(format!("unknown type {other}")),
+            },
+            Type::Nullable(ty) => Ok(ExprType::try_from(ty.as_ref())?.nullable()),
+            Type::Function(params, ret_type) => Ok(ExprType::Function(
+                params
+                    .iter()
+                    .map(ExprType::try_from)
+                    .collect::<Result<_, _>>()?,
+                Box::new(ret_type.as_ref().try_into()?),
+            )),
         }
     }
 }
@@ -59,22 +155,12 @@ pub struct FunType {
     pub ret_type: ExprType,
 }
 
-impl From<&Fun> for FunType {
-    fn from(value: &Fun) -> Self {
-        let ret_type = if let Some(ref ty) = value.ret_type {
-            ty.into()
-        } else {
-            ExprType::Unit
-        };
-        Self {
-            args: value.args.iter().map(|(_, ty)| ty.into()).collect(),
-            ret_type,
-        }
-    }
-}
-
 pub trait Validate {
     fn validate(&self, _context: &mut Context) -> Option<ExprType>;
+
+    fn validate_statement(&self, context: &mut Context) -> Option<ExprType> {
+        self.validate(context)
+    }
 }
 
 impl<E> Validate for Spanned<E>
@@ -87,6 +173,13 @@ where
         context.last_span = span;
         ty
     }
+
+    fn validate_statement(&self, context: &mut Context) -> Option<ExprType> {
+        let span = context.last_span.replace(self.span);
+        let ty = self.expr.validate_statement(context);
+        context.last_span = span;
+        ty
+    }
 }
 
 impl Validate for expr::Expr {
@@ -95,9 +188,306 @@ impl Validate for expr::Expr {
             expr::Expr::TopExpr(expr) => expr.validate(context),
             expr::Expr::MathExpr(expr) => expr.validate(context),
             expr::Expr::ComparisonExpr(expr) => expr.validate(context),
+            expr::Expr::Elvis(expr) => expr.validate(context),
             expr::Expr::ShortExpr(expr) => expr.validate(context),
+            // Already reported by the parser, its type is unknown
+            expr::Expr::Invalid(invalid) => {
+                if let Some(binding) = &invalid.binding {
+                    context.broken_bindings.insert(binding.0.clone());
+                }
+                None
+            }
+        }
+    }
+
+    fn validate_statement(&self, context: &mut Context) -> Option<ExprType> {
+        match self {
+            expr::Expr::TopExpr(expr) => expr.validate_statement(context),
+            _ => self.validate(context),
+        }
+    }
+}
+
+fn validate_block(body: &Body, is_value: bool, context: &mut Context) -> Option<ExprType> {
+    context.push_scope();
+    let mut ty = Some(ExprType::Unit);
+    for (index, expr) in body.iter().enumerate() {
+        ty = if is_value && index + 1 == body.len() {
+            expr.validate(context)
+        } else {
+            expr.validate_statement(context).map(|_| ExprType::Unit)
+        };
+        if ty.is_none() {
+            break;
+        }
+        smart_cast_after(expr, context);
+    }
+    context.pop_scope();
+    ty
+}
+
+impl Context<'_> {
+    pub fn validate_args(
+        &mut self,
+        args: &[Spanned<Expr>],
+        params: &[ExprType],
+    ) -> Option<Vec<ExprType>> {
+        let types: Vec<_> = args
+            .iter()
+            .enumerate()
+            .filter_map(|(index, arg)| self.validate_expected(arg, params.get(index)))
+            .collect();
+        (types.len() == args.len()).then_some(types)
+    }
+
+    pub fn validate_expected(
+        &mut self,
+        expr: &Spanned<Expr>,
+        expected: Option<&ExprType>,
+    ) -> Option<ExprType> {
+        let expected = match expected {
+            Some(ExprType::Nullable(ty)) => Some(ty.as_ref()),
+            expected => expected,
+        };
+        match (&expr.expr, expected) {
+            (
+                Expr::ShortExpr(expr::ShortExpr::Lambda(lambda)),
+                Some(ExprType::Function(params, ret_type)),
+            ) => {
+                let span = self.last_span.replace(expr.span);
+                let ty = self.validate_lambda(lambda, Some(params), Some(ret_type));
+                self.last_span = span;
+                ty
+            }
+            _ => expr.validate(self),
+        }
+    }
+
+    /// Lambdas without expected parameter types must declare types of all their parameters,
+    /// the return type is inferred from the body unless it's expected
+    pub fn validate_lambda(
+        &mut self,
+        lambda: &expr::Lambda,
+        expected: Option<&[ExprType]>,
+        expected_ret: Option<&ExprType>,
+    ) -> Option<ExprType> {
+        let mut scope = HashMap::new();
+        let mut params = vec![];
+        let mut is_valid = true;
+
+        match expected {
+            Some([param]) if lambda.params.is_empty() => {
+                let variable = Variable {
+                    ty: param.clone(),
+                    is_mut: false,
+                    cast_from: None,
+                };
+                scope.insert("it".to_owned(), variable);
+                params.push(param.clone());
+            }
+            Some(expected) if expected.len() != lambda.params.len() => {
+                self.error(format!(
+                    "expected {} parameters, found {}",
+                    expected.len(),
+                    lambda.params.len()
+                ));
+                return None;
+            }
+            _ => {}
+        }
+
+        for (index, param) in lambda.params.iter().enumerate() {
+            let expected = expected.map(|params| &params[index]);
+            let ty = match (&param.ty, expected) {
+                (Some(ty), expected) => {
+                    let ty = self.resolve_type(ty)?;
+                    if let Some(expected) = expected.filter(|e| !self.is_assignable(&ty, e)) {
+                        is_valid = false;
+                        self.error_with_span(
+                            format!("type mismatch: expected {expected}, found {ty}"),
+                            param.name.span,
+                        );
+                    }
+                    ty
+                }
+                (None, Some(expected)) => expected.clone(),
+                (None, None) => {
+                    self.error_with_span(
+                        "cannot infer a type for this parameter, specify it explicitly".to_string(),
+                        param.name.span,
+                    );
+                    return None;
+                }
+            };
+            let variable = Variable {
+                ty: ty.clone(),
+                is_mut: false,
+                cast_from: None,
+            };
+            if scope.insert(param.name.0.clone(), variable).is_some() {
+                let name = &param.name.0;
+                self.error_with_span(format!("binding {name} already defined"), param.name.span);
+            }
+            params.push(ty);
+        }
+
+        // Lambdas can't return from or break out of the enclosing function
+        let ret_type = self.current_ret_type.take();
+        let loop_depth = std::mem::take(&mut self.loop_depth);
+        self.scopes.push(scope);
+
+        let ty = match expected_ret {
+            Some(ExprType::Unit) => {
+                validate_block(&lambda.body, false, self).map(|_| ExprType::Unit)
+            }
+            Some(expected) => validate_block(&lambda.body, true, self).map(|ty| {
+                if !self.is_assignable(expected, &ty) {
+                    self.error(format!("type mismatch: expected {expected}, found {ty}"));
+                }
+                expected.clone()
+            }),
+            None => validate_block(&lambda.body, true, self),
+        };
+
+        self.pop_scope();
+        self.loop_depth = loop_depth;
+        self.current_ret_type = ret_type;
+
+        let ty = ty.filter(|_| is_valid)?;
+        Some(ExprType::Function(params, Box::new(ty)))
+    }
+
+    pub fn is_assignable(&self, target: &ExprType, value: &ExprType) -> bool {
+        match (target, value) {
+            _ if target == value => true,
+            (_, ExprType::Nothing) | (ExprType::Unresolved, _) | (_, ExprType::Unresolved) => true,
+            (ExprType::Nullable(target), value) => {
+                value == &ExprType::Null || self.is_assignable(target, &value.clone().non_null())
+            }
+            (ExprType::Class(target), ExprType::Class(value)) => self.is_subclass(value, target),
+            // Parameters are contravariant and return types are covariant
+            (
+                ExprType::Function(target_params, target_ret),
+                ExprType::Function(value_params, value_ret),
+            ) => {
+                target_params.len() == value_params.len()
+                    && target_params
+                        .iter()
+                        .zip(value_params)
+                        .all(|(target, value)| self.is_assignable(value, target))
+                    && self.is_assignable(target_ret, value_ret)
+            }
+            _ => false,
+        }
+    }
+
+    pub fn common_supertype(&self, left: &ExprType, right: &ExprType) -> Option<ExprType> {
+        if self.is_assignable(left, right) {
+            Some(left.clone())
+        } else if self.is_assignable(right, left) {
+            Some(right.clone())
+        } else if [left, right].contains(&&ExprType::Null) {
+            let ty = if left == &ExprType::Null { right } else { left };
+            Some(ty.clone().nullable())
+        } else if [left, right]
+            .iter()
+            .any(|ty| matches!(ty, ExprType::Nullable(_)))
+        {
+            let ty = self.common_supertype(&left.clone().non_null(), &right.clone().non_null())?;
+            Some(ty.nullable())
+        } else if let (ExprType::Class(left), ExprType::Class(right)) = (left, right) {
+            let ancestor = self
+                .ancestors(left)
+                .into_iter()
+                .find(|ancestor| self.is_subclass(right, ancestor))?;
+            Some(ExprType::Class(ancestor.to_owned()))
+        } else {
+            None
         }
     }
+
+    fn unify_branch_types(&mut self, types: Vec<ExprType>) -> Option<ExprType> {
+        let ty = types.iter().try_fold(ExprType::Nothing, |ty, branch| {
+            self.common_supertype(&ty, branch)
+        });
+
+        if ty.is_none() {
+            self.error("branches have different types".to_string());
+        }
+        ty
+    }
+}
+
+fn promote_numeric(left: &ExprType, right: &ExprType) -> Option<ExprType> {
+    match (left, right) {
+        (ExprType::Primitive(l), ExprType::Primitive(r)) => {
+            let promoted = if l.numeric_rank()? >= r.numeric_rank()? {
+                l
+            } else {
+                r
+            };
+            Some(ExprType::Primitive(promoted.clone()))
+        }
+        _ => None,
+    }
+}
+
+fn validate_receiver(
+    receiver: &Spanned<expr::ShortExpr>,
+    name: &Spanned<expr::Ident>,
+    is_safe: bool,
+    context: &mut Context,
+) -> Option<(ExprType, bool)> {
+    let receiver = receiver.validate(context)?;
+
+    let is_nullable = matches!(receiver, ExprType::Nullable(_) | ExprType::Null);
+    if is_nullable && !is_safe {
+        context.error_with_span(
+            format!("only safe (?.) or non-null asserted (!!.) calls are allowed on a nullable receiver of type {receiver}"),
+            name.span,
+        );
+        return None;
+    }
+
+    Some((receiver.non_null(), is_nullable))
+}
+
+fn validate_indexing(
+    get_by_index: &expr::GetByIndex,
+    context: &mut Context,
+) -> Option<(ExprType, ExprType)> {
+    let receiver = get_by_index.expr.validate(context);
+    let index = get_by_index.index.validate(context);
+
+    match index {
+        Some(ExprType::Primitive(Primitive::Int)) | None => {}
+        _ => context.error_with_span(
+            "only Int index is supported".to_string(),
+            get_by_index.index.span,
+        ),
+    };
+
+    let receiver = receiver?;
+    let item = match &receiver {
+        ExprType::Array(ty) => ty.as_ref().clone(),
+        ExprType::Primitive(Primitive::String) => ExprType::Primitive(Primitive::Char),
+        _ => {
+            context.error("index operator is only supported by arrays and strings".to_string());
+            return None;
+        }
+    };
+    Some((receiver, item))
+}
+
+fn is_container_of(container: &ExprType, item: &ExprType) -> bool {
+    match container {
+        ExprType::Array(ty) | ExprType::Range(ty) => ty.as_ref() == item,
+        ExprType::Primitive(Primitive::String) => matches!(
+            item,
+            ExprType::Primitive(Primitive::Char | Primitive::String)
+        ),
+        _ => false,
+    }
 }
 
 fn ensure_type_equality<L, R>(left: &L, right: &R, context: &mut Context) -> Option<ExprType>
@@ -108,10 +498,13 @@ where
     let l = left.validate(context);
     let r = right.validate(context)?;
     let l = l?;
-    if l == r {
-        Some(r)
-    } else {
+    let ty = match (&l, &r) {
+        _ if l == r => Some(r),
+        (ExprType::Class(_), ExprType::Class(_)) => context.common_supertype(&l, &r),
+        _ => None,
+    };
+    if ty.is_none() {
         context.error("wrong operands".to_owned());
-        None
     }
+    ty
 }
@@ -0,0 +1,260 @@
+use super::*;
+
+use expr::{Literal, ShortExpr};
+
+impl Validate for expr::Elvis {
+    fn validate(&self, context: &mut Context) -> Option<ExprType> {
+        let left = self.left.validate(context);
+        let right = self.right.validate(context)?;
+        let left = left?.non_null();
+
+        let ty = context.common_supertype(&left, &right);
+        if ty.is_none() {
+            context.error(format!(
+                "elvis operands have incompatible types {left} and {right}"
+            ));
+        }
+        ty
+    }
+}
+
+impl Validate for expr::NotNull {
+    fn validate(&self, context: &mut Context) -> Option<ExprType> {
+        Some(self.0.validate(context)?.non_null())
+    }
+}
+
+pub fn with_smart_casts<T>(
+    condition: &expr::Expr,
+    value: bool,
+    context: &mut Context,
+    f: impl FnOnce(&mut Context) -> T,
+) -> T {
+    let mut casts = vec![];
+    collect_smart_casts(condition, value, context, &mut casts);
+
+    if casts.is_empty() {
+        return f(context);
+    }
+
+    context.push_scope();
+    for (name, ty) in casts {
+        context.smart_cast(name, ty);
+    }
+    let result = f(context);
+    context.pop_scope();
+    result
+}
+
+/// `if (x == null) return` smart casts `x` for the rest of the block
+pub fn smart_cast_after(statement: &expr::Expr, context: &mut Context) {
+    use expr::{ControlExpr, Expr, TopExpr};
+
+    let Expr::TopExpr(TopExpr::ControlExpr(ControlExpr::If(i))) = statement else {
+        return;
+    };
+
+    let always_jumps = matches!(
+        i.body.last().map(|expr| &expr.expr),
+        Some(Expr::TopExpr(TopExpr::ControlExpr(
+            ControlExpr::Return(_) | ControlExpr::Break(_) | ControlExpr::Continue(_)
+        )))
+    );
+
+    if always_jumps && i.else_branch.is_empty() {
+        let mut casts = vec![];
+        collect_smart_casts(&i.expr, false, context, &mut casts);
+        for (name, ty) in casts {
+            context.smart_cast(name, ty);
+        }
+    }
+}
+
+/// Null checks made before a loop don't hold on later iterations
+/// if the loop body reassigns the variable
+pub fn drop_smart_casts_in_loop(body: &[Spanned<expr::Expr>], context: &mut Context) {
+    for statement in body {
+        drop_assigned_casts(&statement.expr, context);
+    }
+}
+
+fn drop_assigned_casts(expr: &expr::Expr, context: &mut Context) {
+    use expr::{ControlExpr, Expr, TopExpr, WhenCondition};
+    use ComparisonExpr as C;
+    use MathExpr as M;
+
+    match expr {
+        Expr::ComparisonExpr(
+            C::And(expr::And { left, right })
+            | C::Or(expr::Or { left, right })
+            | C::Equal(expr::Equal { left, right })
+            | C::NotEqual(expr::NotEqual { left, right })
+            | C::LessThan(expr::LessThan { left, right })
+            | C::MoreThan(expr::MoreThan { left, right })
+            | C::LessOrEqual(expr::LessOrEqual { left, right })
+            | C::MoreOrEqual(expr::MoreOrEqual { left, right }),
+        )
+        | Expr::Elvis(expr::Elvis { left, right })
+        | Expr::MathExpr(
+            M::Range(expr::Range { left, right })
+            | M::Sub(expr::Sub { left, right })
+            | M::Add(expr::Add { left, right })
+            | M::Mul(expr::Mul { left, right })
+            | M::Div(expr::Div { left, right })
+            | M::Rem(expr::Rem { left, right }),
+        ) => {
+            drop_assigned_casts(&left.expr, context);
+            drop_assigned_casts(&right.expr, context);
+        }
+        Expr::MathExpr(M::Neg(expr::Neg(expr)) | M::BoolNeg(expr::BoolNeg(expr))) => {
+            drop_assigned_casts(&expr.expr, context)
+        }
+        Expr::ShortExpr(expr) => drop_assigned_casts_short(expr, context),
+        Expr::TopExpr(TopExpr::Set(set)) => {
+            context.drop_smart_casts(&set.name.0);
+            drop_assigned_casts(&set.expr.expr, context);
+        }
+        Expr::TopExpr(TopExpr::Binding(binding)) => {
+            drop_assigned_casts(&binding.set.expr.expr, context)
+        }
+        Expr::TopExpr(TopExpr::SetByIndex(set)) => {
+            drop_assigned_casts_short(&set.get_by_index.expr.expr, context);
+            drop_assigned_casts(&set.get_by_index.index.expr, context);
+            drop_assigned_casts(&set.expr.expr, context);
+        }
+        Expr::TopExpr(TopExpr::SetMember(set)) => {
+            drop_assigned_casts_short(&set.member.expr.expr, context);
+            drop_assigned_casts(&set.expr.expr, context);
+        }
+        Expr::TopExpr(TopExpr::Destructuring(destructuring)) => {
+            drop_assigned_casts(&destructuring.expr.expr, context)
+        }
+        Expr::TopExpr(TopExpr::ControlExpr(control)) => match control {
+            ControlExpr::If(i) => {
+                drop_assigned_casts(&i.expr.expr, context);
+                drop_smart_casts_in_loop(&i.body, context);
+                drop_smart_casts_in_loop(&i.else_branch, context);
+            }
+            ControlExpr::For(f) => {
+                drop_assigned_casts(&f.iterable.expr, context);
+                drop_smart_casts_in_loop(&f.body, context);
+            }
+            ControlExpr::While(w) => {
+                drop_assigned_casts(&w.expr.expr, context);
+                drop_smart_casts_in_loop(&w.body, context);
+            }
+            ControlExpr::When(w) => {
+                if let Some(subject) = &w.subject {
+                    drop_assigned_casts(&subject.expr, context);
+                }
+                for branch in &w.branches {
+                    for condition in &branch.conditions {
+                        match &condition.expr {
+                            WhenCondition::Expr(expr) => drop_assigned_casts(expr, context),
+                            WhenCondition::In(expr) => drop_assigned_casts(&expr.expr, context),
+                            WhenCondition::Is(_) => {}
+                        }
+                    }
+                    drop_smart_casts_in_loop(&branch.body, context);
+                }
+                if let Some(body) = &w.else_branch {
+                    drop_smart_casts_in_loop(body, context);
+                }
+            }
+            ControlExpr::Return(expr::Return(Some(expr))) => {
+                drop_assigned_casts(&expr.expr, context)
+            }
+            ControlExpr::Return(_) | ControlExpr::Break(_) | ControlExpr::Continue(_) => {}
+        },
+        Expr::Invalid(_) => {}
+    }
+}
+
+fn drop_assigned_casts_short(expr: &ShortExpr, context: &mut Context) {
+    use expr::TemplatePart;
+
+    match expr {
+        ShortExpr::Call(call) => drop_smart_casts_in_loop(&call.args, context),
+        ShortExpr::Parens(expr::Parens(expr)) => drop_assigned_casts(&expr.expr, context),
+        ShortExpr::GetByIndex(get) => {
+            drop_assigned_casts_short(&get.expr.expr, context);
+            drop_assigned_casts(&get.index.expr, context);
+        }
+        ShortExpr::Template(template) => {
+            for part in &template.0 {
+                if let TemplatePart::Expr(expr) = part {
+                    drop_assigned_casts(&expr.expr, context);
+                }
+            }
+        }
+        ShortExpr::Member(member) => drop_assigned_casts_short(&member.expr.expr, context),
+        ShortExpr::MethodCall(call) => {
+            drop_assigned_casts_short(&call.expr.expr, context);
+            drop_smart_casts_in_loop(&call.args, context);
+            for (_, arg) in &call.named_args {
+                drop_assigned_casts(&arg.expr, context);
+            }
+        }
+        ShortExpr::Invoke(invoke) => {
+            drop_assigned_casts_short(&invoke.expr.expr, context);
+            drop_smart_casts_in_loop(&invoke.args, context);
+        }
+        ShortExpr::NotNull(expr::NotNull(expr)) => drop_assigned_casts_short(&expr.expr, context),
+        ShortExpr::Lambda(lambda) => drop_smart_casts_in_loop(&lambda.body, context),
+        ShortExpr::Ident(_) | ShortExpr::Literal(_) | ShortExpr::This => {}
+    }
+}
+
+fn collect_smart_casts(
+    condition: &expr::Expr,
+    value: bool,
+    context: &Context,
+    casts: &mut Vec<(String, ExprType)>,
+) {
+    match condition {
+        expr::Expr::ComparisonExpr(comparison) => match comparison {
+            ComparisonExpr::NotEqual(expr) if value => {
+                casts.extend(null_check(&expr.left, &expr.right, context))
+            }
+            ComparisonExpr::Equal(expr) if !value => {
+                casts.extend(null_check(&expr.left, &expr.right, context))
+            }
+            ComparisonExpr::And(expr) if value => {
+                collect_smart_casts(&expr.left, value, context, casts);
+                collect_smart_casts(&expr.right, value, context, casts);
+            }
+            ComparisonExpr::Or(expr) if !value => {
+                collect_smart_casts(&expr.left, value, context, casts);
+                collect_smart_casts(&expr.right, value, context, casts);
+            }
+            _ => {}
+        },
+        expr::Expr::ShortExpr(ShortExpr::Parens(expr)) => {
+            collect_smart_casts(&expr.0, value, context, casts)
+        }
+        expr::Expr::MathExpr(MathExpr::BoolNeg(expr)) => {
+            collect_smart_casts(&expr.0, !value, context, casts)
+        }
+        _ => {}
+    }
+}
+
+fn null_check(
+    left: &expr::Expr,
+    right: &expr::Expr,
+    context: &Context,
+) -> Option<(String, ExprType)> {
+    use expr::Expr::ShortExpr as Short;
+
+    let name = match (left, right) {
+        (Short(ShortExpr::Ident(ident)), Short(ShortExpr::Literal(Literal::Null)))
+        | (Short(ShortExpr::Literal(Literal::Null)), Short(ShortExpr::Ident(ident))) => &ident.0,
+        _ => return None,
+    };
+
+    let var = context.find_var(name)?;
+    match &var.ty {
+        ExprType::Nullable(ty) => Some((name.clone(), *ty.clone())),
+        _ => None,
+    }
+}
@@ -5,9 +5,13 @@ impl Validate for expr::Literal {
         use expr::Literal::*;
         let primitive = match self {
             Int(_) => Primitive::Int,
+            Long(_) => Primitive::Long,
+            Float(_) => Primitive::Float,
+            Double(_) => Primitive::Double,
             Bool(_) => Primitive::Boolean,
             Char(_) => Primitive::Char,
             String(_) => Primitive::String,
+            Null => return Some(ExprType::Null),
         };
         Some(ExprType::Primitive(primitive))
     }
@@ -17,9 +21,66 @@ impl Validate for expr::ShortExpr {
     fn validate(&self, context: &mut Context) -> Option<ExprType> {
         match self {
             expr::ShortExpr::Ident(ident) => ident.validate(context),
+            expr::ShortExpr::Call(call) => call.validate(context),
+            expr::ShortExpr::Parens(parens) => parens.0.validate(context),
             expr::ShortExpr::GetByIndex(get_by_index) => get_by_index.validate(context),
             expr::ShortExpr::Literal(literal) => literal.validate(context),
+            expr::ShortExpr::Template(template) => template.validate(context),
+            expr::ShortExpr::Member(member) => member.validate(context),
+            expr::ShortExpr::MethodCall(call) => call.validate(context),
+            expr::ShortExpr::Invoke(invoke) => invoke.validate(context),
+            expr::ShortExpr::NotNull(not_null) => not_null.validate(context),
+            expr::ShortExpr::Lambda(lambda) => context.validate_lambda(lambda, None, None),
+            expr::ShortExpr::This => {
+                let this = context.find_var_type("this");
+                if this.is_none() {
+                    context.error("'this' is not defined in this context".to_string());
+                }
+                this
+            }
+        }
+    }
+}
+
+impl Validate for expr::Call {
+    fn validate(&self, context: &mut Context) -> Option<ExprType> {
+        if self.name.0 == "Array" {
+            return context.validate_array_constructor(self);
+        }
+
+        let params = context.find_fun_params(&self.name.0);
+        let args = context.validate_args(&self.args, &params);
+        context.find_fun_ret_type(&self.name, args.as_deref())
+    }
+}
+
+impl Validate for expr::Invoke {
+    fn validate(&self, context: &mut Context) -> Option<ExprType> {
+        let ty = self.expr.validate(context);
+        let fun = ty.as_ref().and_then(fun_value_type);
+        if let (Some(ty), None) = (&ty, &fun) {
+            context.error(format!(
+                "expression of type {ty} cannot be invoked as a function"
+            ));
+        }
+        let params = fun
+            .as_ref()
+            .map(|fun| fun.args.as_slice())
+            .unwrap_or_default();
+        let args = context.validate_args(&self.args, params);
+        context.check_call("invoke", &fun?, args.as_deref())
+    }
+}
+
+impl Validate for expr::Template {
+    fn validate(&self, context: &mut Context) -> Option<ExprType> {
+        let mut valid = true;
+        for part in &self.0 {
+            if let expr::TemplatePart::Expr(expr) = part {
+                valid &= expr.validate(context).is_some();
+            }
         }
+        valid.then_some(ExprType::Primitive(Primitive::String))
     }
 }
 
@@ -27,7 +88,13 @@ impl Validate for expr::Ident {
     fn validate(&self, context: &mut Context) -> Option<ExprType> {
         let name = &self.0;
         if let Some(ty) = context.find_var_type(name) {
-            Some(ty)
+            ty.known()
+        } else if let Some(property) = context.find_this_property(name) {
+            property.ty.clone().known()
+        } else if let Some(entry) = context.find_this_entry(name) {
+            Some(entry)
+        } else if context.broken_bindings.contains(name) {
+            None
         } else {
             context.error(format!("ident {name} not found"));
             None
@@ -37,23 +104,187 @@ impl Validate for expr::Ident {
 
 impl Validate for expr::GetByIndex {
     fn validate(&self, context: &mut Context) -> Option<ExprType> {
-        let ident = self.ident.validate(context);
-        let index = self.index.validate(context);
+        let (_, item) = validate_indexing(self, context)?;
+        Some(item)
+    }
+}
+
+impl Validate for expr::Member {
+    fn validate(&self, context: &mut Context) -> Option<ExprType> {
+        if let Some(class) = enum_receiver(&self.expr, context) {
+            let name = &self.name.0;
+            if context.classes[class.as_str()].entries.contains(name) {
+                return Some(ExprType::Class(class));
+            }
+            context.error_with_span(format!("unresolved reference: {name}"), self.name.span);
+            return None;
+        }
+
+        let (receiver, is_nullable) =
+            validate_receiver(&self.expr, &self.name, self.is_safe, context)?;
+        let name = self.name.0.as_str();
+
+        let ty = builtin_property(&receiver, name).or_else(|| {
+            let property = context.find_property(&receiver, name)?;
+            Some(property.ty.clone())
+        });
+        let Some(ty) = ty else {
+            context.error_with_span(format!("unresolved reference: {name}"), self.name.span);
+            return None;
+        };
+        let ty = ty.known()?;
+
+        Some(if is_nullable { ty.nullable() } else { ty })
+    }
+}
+
+impl Validate for expr::MethodCall {
+    fn validate(&self, context: &mut Context) -> Option<ExprType> {
+        if let Some(class) = enum_receiver(&self.expr, context) {
+            return validate_enum_call(self, class, context);
+        }
 
-        match index {
-            Some(ExprType::Primitive(Primitive::Int)) | None => {}
-            _ => {
-                context.error_with_span("only Int index is supported".to_string(), self.index.span)
+        let receiver = validate_receiver(&self.expr, &self.name, self.is_safe, context);
+        let name = self.name.0.as_str();
+        let params = match receiver {
+            Some((ref receiver, _)) => {
+                find_method_or_value(receiver, name, self.args.len(), context)
+                    .map(|fun| fun.args)
+                    .unwrap_or_default()
             }
+            None => vec![],
+        };
+        let args = context.validate_args(&self.args, &params);
+        let named_args: Option<Vec<_>> = self
+            .named_args
+            .iter()
+            .map(|(_, arg)| arg.validate(context))
+            .collect();
+        let (receiver, is_nullable) = receiver?;
+
+        let is_copy = name == "copy" && context.find_data_class(&receiver).is_some();
+        if !self.named_args.is_empty() && !is_copy {
+            context.error("named arguments are only supported by copy".to_string());
+            return None;
+        }
+
+        let ty = if is_copy {
+            validate_copy(self, receiver, &args?, &named_args?, context)?
+        } else if let Some(method) = find_method_or_value(&receiver, name, self.args.len(), context)
+        {
+            context.check_call(name, &method, args.as_deref())?
+        } else {
+            context.error_with_span(format!("unresolved reference: {name}"), self.name.span);
+            return None;
         };
 
-        match ident? {
-            ExprType::Array(ty) => Some(*ty),
-            ExprType::Primitive(Primitive::String) => Some(ExprType::Primitive(Primitive::Char)),
-            _ => {
-                context.error("index operator is only supported by arrays and strings".to_string());
-                None
+        Some(if is_nullable { ty.nullable() } else { ty })
+    }
+}
+
+fn find_method_or_value(
+    receiver: &ExprType,
+    name: &str,
+    arg_count: usize,
+    context: &Context,
+) -> Option<FunType> {
+    if let Some(method) = builtin_method(receiver, name, arg_count) {
+        return Some(method);
+    }
+    if let Some(method) = context.find_method(receiver, name) {
+        return Some(method.clone());
+    }
+    let property = context.find_property(receiver, name)?;
+    fun_value_type(&property.ty)
+}
+
+fn enum_receiver(receiver: &expr::ShortExpr, context: &Context) -> Option<String> {
+    let expr::ShortExpr::Ident(name) = receiver else {
+        return None;
+    };
+    context.find_enum(&name.0).map(|_| name.0.clone())
+}
+
+fn validate_enum_call(
+    call: &expr::MethodCall,
+    class: String,
+    context: &mut Context,
+) -> Option<ExprType> {
+    let args = context.validate_args(&call.args, &[]);
+    let name = call.name.0.as_str();
+    if !call.named_args.is_empty() {
+        context.error("named arguments are only supported by copy".to_string());
+        return None;
+    }
+
+    let entry = ExprType::Class(class);
+    let fun = match name {
+        "values" => FunType {
+            args: vec![],
+            ret_type: ExprType::Array(Box::new(entry)),
+        },
+        "valueOf" => FunType {
+            args: vec![ExprType::Primitive(Primitive::String)],
+            ret_type: entry,
+        },
+        _ => {
+            context.error_with_span(format!("unresolved reference: {name}"), call.name.span);
+            return None;
+        }
+    };
+    context.check_call(name, &fun, args.as_deref())
+}
+
+fn validate_copy(
+    call: &expr::MethodCall,
+    receiver: ExprType,
+    args: &[ExprType],
+    named_args: &[ExprType],
+    context: &mut Context,
+) -> Option<ExprType> {
+    let params = &context.find_data_class(&receiver)?.constructor;
+
+    let mut is_passed = vec![false; params.len()];
+    let mut errors = vec![];
+
+    if args.len() > params.len() {
+        errors.push("too many arguments for copy".to_string());
+    }
+
+    for (index, (arg, (name, param))) in args.iter().zip(params).enumerate() {
+        is_passed[index] = true;
+        if !context.is_assignable(param, arg) {
+            errors.push(format!(
+                "type mismatch: expected {param}, found {arg} for {name}"
+            ));
+        }
+    }
+
+    for ((name, _), arg) in call.named_args.iter().zip(named_args) {
+        let name = name.0.as_str();
+        match params.iter().position(|(param, _)| param == name) {
+            Some(index) if is_passed[index] => {
+                errors.push(format!("an argument is already passed for {name}"));
             }
+            Some(index) => {
+                is_passed[index] = true;
+                let param = &params[index].1;
+                if !context.is_assignable(param, arg) {
+                    errors.push(format!(
+                        "type mismatch: expected {param}, found {arg} for {name}"
+                    ));
+                }
+            }
+            None => errors.push(format!("cannot find a parameter with name {name}")),
+        }
+    }
+
+    if errors.is_empty() {
+        Some(receiver)
+    } else {
+        for error in errors {
+            context.error(error);
         }
+        None
     }
 }
@@ -7,31 +7,49 @@ impl Validate for expr::ControlExpr {
             If(expr) => expr,
             For(expr) => expr,
             While(expr) => expr,
+            When(expr) => expr,
             Return(expr) => expr,
+            Break(expr) => expr,
+            Continue(expr) => expr,
         };
         expr.validate(context)
     }
+
+    fn validate_statement(&self, context: &mut Context) -> Option<ExprType> {
+        match self {
+            expr::ControlExpr::If(expr) => expr.validate_statement(context),
+            expr::ControlExpr::When(expr) => expr.validate_statement(context),
+            _ => self.validate(context),
+        }
+    }
 }
 
 impl Validate for expr::If {
     fn validate(&self, context: &mut Context) -> Option<ExprType> {
-        let is_bool = self.expr.validate(context)? == ExprType::Primitive(Primitive::Boolean);
-        if is_bool {
-            context.push_scope();
-            for expr in &self.body {
-                expr.validate(context)?;
-            }
-            context.pop_scope();
-            context.push_scope();
-            for expr in &self.else_branch {
-                expr.validate(context)?;
-            }
-            context.pop_scope();
-            Some(ExprType::Unit)
-        } else {
-            context.error("condition must have boolean type".to_owned());
-            None
+        if self.else_branch.is_empty() {
+            context.error("if used as a value must have an else branch".to_string());
         }
+        validate_if(self, true, context)
+    }
+
+    fn validate_statement(&self, context: &mut Context) -> Option<ExprType> {
+        validate_if(self, false, context).map(|_| ExprType::Unit)
+    }
+}
+
+fn validate_if(i: &expr::If, is_value: bool, context: &mut Context) -> Option<ExprType> {
+    let is_bool = i.expr.validate(context)? == ExprType::Primitive(Primitive::Boolean);
+    if is_bool {
+        let body = with_smart_casts(&i.expr, true, context, |context| {
+            validate_block(&i.body, is_value, context)
+        });
+        let else_branch = with_smart_casts(&i.expr, false, context, |context| {
+            validate_block(&i.else_branch, is_value, context)
+        });
+        context.unify_branch_types(vec![body?, else_branch?])
+    } else {
+        context.error("condition must have boolean type".to_owned());
+        None
     }
 }
 
@@ -41,21 +59,33 @@ impl Validate for expr::For {
 
         let ty = match iter_type {
             ExprType::Array(ty) => ty,
-            ExprType::Range(ty) => ty,
+            ExprType::Range(ty)
+                if matches!(
+                    *ty,
+                    ExprType::Primitive(Primitive::Int | Primitive::Long | Primitive::Char)
+                ) =>
+            {
+                ty
+            }
+            ExprType::Range(_) => {
+                context.error("only ranges of Int, Long and Char are iterable".to_owned());
+                return None;
+            }
+            ExprType::Primitive(Primitive::String) => {
+                Box::new(ExprType::Primitive(Primitive::Char))
+            }
             _ => {
-                context.error("only array and range are iterable types".to_owned());
+                context.error("only array, range and string are iterable types".to_owned());
                 return None;
             }
         };
 
         context.push_scope();
 
-        context.add_var_type(self.var.0.to_owned(), *ty);
-        for expr in &self.body {
-            expr.validate(context)?;
-        }
+        context.add_var(self.var.0.to_owned(), *ty, false);
+        let valid = validate_loop_body(&self.body, context);
         context.pop_scope();
-        Some(ExprType::Unit)
+        valid.then_some(ExprType::Unit)
     }
 }
 
@@ -63,12 +93,7 @@ impl Validate for expr::While {
     fn validate(&self, context: &mut Context) -> Option<ExprType> {
         let is_bool = self.expr.validate(context)? == ExprType::Primitive(Primitive::Boolean);
         if is_bool {
-            context.push_scope();
-            for expr in &self.body {
-                expr.validate(context)?;
-            }
-            context.pop_scope();
-            Some(ExprType::Unit)
+            validate_loop_body(&self.body, context).then_some(ExprType::Unit)
         } else {
             context.error("condition must have boolean type".to_owned());
             None
@@ -76,19 +101,234 @@ impl Validate for expr::While {
     }
 }
 
+fn validate_loop_body(body: &Body, context: &mut Context) -> bool {
+    drop_smart_casts_in_loop(body, context);
+    context.loop_depth += 1;
+    let ty = validate_block(body, false, context);
+    context.loop_depth -= 1;
+    ty.is_some()
+}
+
+impl Validate for expr::When {
+    fn validate(&self, context: &mut Context) -> Option<ExprType> {
+        validate_when(self, true, context)
+    }
+
+    fn validate_statement(&self, context: &mut Context) -> Option<ExprType> {
+        validate_when(self, false, context).map(|_| ExprType::Unit)
+    }
+}
+
+fn validate_when(when: &expr::When, is_value: bool, context: &mut Context) -> Option<ExprType> {
+    let subject = match &when.subject {
+        Some(subject) => Some(subject.validate(context)?),
+        None => None,
+    };
+
+    // `when` over a sealed or enum subject must list all cases even as a statement
+    let has_cases = subject.as_ref().is_some_and(|ty| context.has_cases(ty));
+    if is_value && !has_cases && when.else_branch.is_none() {
+        context.error("when used as a value must have an else branch".to_string());
+    }
+
+    let mut valid = true;
+    let mut types = vec![];
+    let mut covered = CoveredCases::default();
+
+    for branch in &when.branches {
+        for condition in &branch.conditions {
+            let span = context.last_span.replace(condition.span);
+            valid &= validate_when_condition(condition, subject.as_ref(), &mut covered, context);
+            context.last_span = span;
+        }
+
+        let ty = match is_cast(when, branch, context) {
+            Some((name, ty)) => {
+                context.push_scope();
+                context.smart_cast(name, ty);
+                let ty = validate_block(&branch.body, is_value, context);
+                context.pop_scope();
+                ty
+            }
+            None => validate_block(&branch.body, is_value, context),
+        };
+        types.push(ty);
+    }
+
+    if let Some(ref body) = when.else_branch {
+        types.push(validate_block(body, is_value, context));
+    } else if let Some(subject) = subject.as_ref().filter(|_| has_cases) {
+        let missing = context.missing_cases(subject, &covered);
+        if !missing.is_empty() {
+            context.error(format!(
+                "'when' expression must be exhaustive, add necessary {} branches or 'else' branch instead",
+                missing.join(", ")
+            ));
+        }
+    }
+
+    let types = types.into_iter().collect::<Option<Vec<_>>>()?;
+
+    if valid {
+        context.unify_branch_types(types)
+    } else {
+        None
+    }
+}
+
+fn validate_when_condition(
+    condition: &expr::WhenCondition,
+    subject: Option<&ExprType>,
+    covered: &mut CoveredCases,
+    context: &mut Context,
+) -> bool {
+    match (condition, subject) {
+        (expr::WhenCondition::Expr(expr), Some(subject)) => match expr.validate(context) {
+            Some(ty) if context.common_supertype(&ty, subject).is_some() => {
+                match ty {
+                    ExprType::Null => covered.has_null = true,
+                    _ => covered.entries.extend(enum_entry(expr, context)),
+                }
+                true
+            }
+            Some(_) => {
+                context.error("condition type doesn't match subject type".to_string());
+                false
+            }
+            None => false,
+        },
+        (expr::WhenCondition::Expr(expr), None) => match expr.validate(context) {
+            Some(ExprType::Primitive(Primitive::Boolean)) => true,
+            Some(_) => {
+                context.error("condition must have boolean type".to_owned());
+                false
+            }
+            None => false,
+        },
+        (expr::WhenCondition::In(expr), Some(subject)) => match expr.validate(context) {
+            Some(ty) if is_container_of(&ty, subject) => true,
+            Some(_) => {
+                context.error("subject can't be contained in the value".to_string());
+                false
+            }
+            None => false,
+        },
+        (expr::WhenCondition::In(_), None) => {
+            context.error("in condition requires when subject".to_string());
+            false
+        }
+        (expr::WhenCondition::Is(ty), Some(subject)) => {
+            let ty = match context.convert_type(ty) {
+                Ok(ty) => ty,
+                Err(err) => {
+                    context.error(err);
+                    return false;
+                }
+            };
+
+            let (value, subject) = (ty.clone().non_null(), subject.clone().non_null());
+            if context.is_assignable(&value, &subject) || context.is_assignable(&subject, &value) {
+                if let ExprType::Class(class) = value {
+                    covered.types.push(class);
+                }
+                covered.has_null |= matches!(ty, ExprType::Nullable(_));
+                true
+            } else {
+                context.error(format!("incompatible types: {ty} and {subject}"));
+                false
+            }
+        }
+        (expr::WhenCondition::Is(_), None) => {
+            context.error("is condition requires when subject".to_string());
+            false
+        }
+    }
+}
+
+fn enum_entry(expr: &expr::Expr, context: &Context) -> Option<String> {
+    match expr {
+        expr::Expr::ShortExpr(expr::ShortExpr::Member(member)) => {
+            let expr::ShortExpr::Ident(ref class) = **member.expr else {
+                return None;
+            };
+            let entry = &member.name.0;
+            let class = context.find_enum(&class.0)?;
+            class.entries.contains(entry).then(|| entry.clone())
+        }
+        expr::Expr::ShortExpr(expr::ShortExpr::Ident(entry)) => {
+            let is_variable = context.find_var(&entry.0).is_some()
+                || context.find_this_property(&entry.0).is_some();
+            let is_entry = !is_variable && context.find_this_entry(&entry.0).is_some();
+            is_entry.then(|| entry.0.clone())
+        }
+        _ => None,
+    }
+}
+
+/// Immutable variable subject of a branch with a single `is` condition,
+/// which is smart cast to the checked type inside the branch
+fn is_cast(
+    when: &expr::When,
+    branch: &expr::WhenBranch,
+    context: &Context,
+) -> Option<(String, ExprType)> {
+    let [condition] = &branch.conditions[..] else {
+        return None;
+    };
+    let expr::WhenCondition::Is(ref ty) = **condition else {
+        return None;
+    };
+    let Some(expr::Expr::ShortExpr(expr::ShortExpr::Ident(name))) =
+        when.subject.as_deref().map(|subject| &subject.expr)
+    else {
+        return None;
+    };
+
+    let var = context.find_var(&name.0).filter(|var| !var.is_mut)?;
+    let ty = context.convert_type(ty).ok()?;
+    context
+        .is_assignable(&var.ty, &ty)
+        .then(|| (name.0.clone(), ty))
+}
+
+impl Validate for expr::Break {
+    fn validate(&self, context: &mut Context) -> Option<ExprType> {
+        if context.loop_depth == 0 {
+            context.error("break is only allowed inside a loop".to_string());
+            None
+        } else {
+            Some(ExprType::Nothing)
+        }
+    }
+}
+
+impl Validate for expr::Continue {
+    fn validate(&self, context: &mut Context) -> Option<ExprType> {
+        if context.loop_depth == 0 {
+            context.error("continue is only allowed inside a loop".to_string());
+            None
+        } else {
+            Some(ExprType::Nothing)
+        }
+    }
+}
+
 impl Validate for expr::Return {
     fn validate(&self, context: &mut Context) -> Option<ExprType> {
-        let expected = context.current_ret_type.clone().unwrap();
+        let Some(expected) = context.current_ret_type.clone() else {
+            context.error("'return' is not allowed here".to_string());
+            return None;
+        };
 
         if let Some(ref expr) = self.0 {
-            let actual = expr.validate(context)?;
+            let actual = context.validate_expected(expr, Some(&expected))?;
 
-            if actual != expected {
+            if !context.is_assignable(&expected, &actual) {
                 context.error("wrong return type".to_string());
             }
-        } else if expected != ExprType::Unit {
+        } else if !matches!(expected, ExprType::Unit | ExprType::Unresolved) {
             context.error("wrong return type".to_string());
         }
-        Some(ExprType::Unit)
+        Some(ExprType::Nothing)
     }
 }
@@ -6,28 +6,20 @@ impl Validate for expr::TopExpr {
     fn validate(&self, context: &mut Context) -> Option<ExprType> {
         use expr::TopExpr::*;
         let expr: &dyn Validate = match self {
-            Call(expr) => expr,
             Binding(expr) => expr,
             Set(expr) => expr,
             ControlExpr(expr) => expr,
             SetByIndex(expr) => expr,
+            SetMember(expr) => expr,
+            Destructuring(expr) => expr,
         };
         expr.validate(context)
     }
-}
-
-impl Validate for expr::Call {
-    fn validate(&self, context: &mut Context) -> Option<ExprType> {
-        let args: Vec<_> = self
-            .args
-            .iter()
-            .filter_map(|arg| arg.validate(context))
-            .collect();
 
-        if args.len() == self.args.len() {
-            context.find_fun_ret_type(&self.name, Some(&args))
-        } else {
-            context.find_fun_ret_type(&self.name, None)
+    fn validate_statement(&self, context: &mut Context) -> Option<ExprType> {
+        match self {
+            expr::TopExpr::ControlExpr(expr) => expr.validate_statement(context),
+            _ => self.validate(context),
         }
     }
 }
@@ -35,8 +27,47 @@ impl Validate for expr::Call {
 impl Validate for expr::Binding {
     fn validate(&self, context: &mut Context) -> Option<ExprType> {
         let ident = self.set.name.0.clone();
-        let ty = self.set.expr.validate(context)?;
-        context.add_var_type(ident, ty.clone());
+        let declared = match self.ty {
+            Some(ref declared) => Some(context.resolve_type(declared)?),
+            None => None,
+        };
+        let ty = context.validate_expected(&self.set.expr, declared.as_ref())?;
+
+        let ty = match declared {
+            Some(declared) => {
+                if !context.is_assignable(&declared, &ty) {
+                    context.error(format!("type mismatch: expected {declared}, found {ty}"));
+                }
+                declared
+            }
+            None => ty,
+        };
+
+        context.add_var(ident, ty.clone(), self.is_mut);
+        Some(ty)
+    }
+}
+
+impl Validate for expr::Destructuring {
+    fn validate(&self, context: &mut Context) -> Option<ExprType> {
+        let ty = self.expr.validate(context)?;
+
+        for (index, name) in self.names.iter().enumerate() {
+            let component = format!("component{}", index + 1);
+            match context.find_method(&ty, &component) {
+                Some(method) if method.args.is_empty() => {
+                    let component_type = method.ret_type.clone();
+                    context.add_var(name.0.clone(), component_type, self.is_mut);
+                }
+                _ => {
+                    context.error(format!(
+                        "destructuring declaration initializer of type {ty} must have a {component}() function"
+                    ));
+                    return None;
+                }
+            }
+        }
+
         Some(ty)
     }
 }
@@ -44,12 +75,26 @@ impl Validate for expr::Binding {
 impl Validate for expr::Set {
     fn validate(&self, context: &mut Context) -> Option<ExprType> {
         let ident = self.name.0.clone();
-        let ty = self.expr.validate(context);
-        if let Some(expected) = context.find_var_type(&ident) {
-            if expected != ty? {
+        let find_var = |context: &Context| {
+            context
+                .find_var(&ident)
+                .or_else(|| context.find_this_property(&ident))
+                .map(|var| {
+                    let declared = var.cast_from.as_ref().unwrap_or(&var.ty);
+                    (declared.clone(), var.is_mut)
+                })
+        };
+        let expected = find_var(context).map(|(ty, _)| ty);
+        let ty = context.validate_expected(&self.expr, expected.as_ref());
+        if let Some((expected, is_mut)) = find_var(context) {
+            if !is_mut {
+                context.error("val cannot be reassigned".to_string());
+                None
+            } else if !context.is_assignable(&expected, &ty?) {
                 context.error(format!("variable {ident} found but it has different type"));
                 None
             } else {
+                context.drop_smart_casts(&ident);
                 Some(ExprType::Unit)
             }
         } else {
@@ -64,6 +109,47 @@ impl Validate for expr::Set {
 
 impl Validate for expr::SetByIndex {
     fn validate(&self, context: &mut Context) -> Option<ExprType> {
-        ensure_type_equality(&self.get_by_index, &*self.expr, context)
+        let indexing = validate_indexing(&self.get_by_index, context);
+        let ty = self.expr.validate(context)?;
+        let (receiver, item) = indexing?;
+
+        if !matches!(receiver, ExprType::Array(_)) {
+            context.error(format!(
+                "no set method providing array access for {receiver}"
+            ));
+            None
+        } else if context.is_assignable(&item, &ty) {
+            Some(ExprType::Unit)
+        } else {
+            context.error("wrong operands".to_owned());
+            None
+        }
+    }
+}
+
+impl Validate for expr::SetMember {
+    fn validate(&self, context: &mut Context) -> Option<ExprType> {
+        let member = &self.member;
+        let receiver = validate_receiver(&member.expr, &member.name, member.is_safe, context);
+        let ty = self.expr.validate(context);
+        let (receiver, _) = receiver?;
+        let name = member.name.0.as_str();
+
+        let Some(property) = context.find_property(&receiver, name).cloned() else {
+            context.error_with_span(format!("unresolved reference: {name}"), member.name.span);
+            return None;
+        };
+
+        let ty = ty?;
+        if !property.is_mut {
+            context.error("val cannot be reassigned".to_string());
+            None
+        } else if !context.is_assignable(&property.ty, &ty) {
+            let expected = property.ty;
+            context.error(format!("type mismatch: expected {expected}, found {ty}"));
+            None
+        } else {
+            Some(ExprType::Unit)
+        }
     }
 }
@@ -1,45 +1,73 @@
-use super::Eval;
+use std::cmp::Ordering;
+
+use super::{Completion, Eval};
 use crate::{
     interpreter::{Context, Object},
-    parser::expr::ComparisonExpr,
+    parser::{
+        expr::{self, ComparisonExpr},
+        BoxedExpr,
+    },
 };
 
 impl Eval for ComparisonExpr {
-    fn eval(&self, context: &Context) -> Object {
+    fn eval(&self, context: &Context) -> Completion {
         use ComparisonExpr::*;
-        use Object::*;
-
-        let (l, r) = match self {
-            LessThan(expr) => (expr.left.eval(context), expr.right.eval(context)),
-            MoreThan(expr) => (expr.left.eval(context), expr.right.eval(context)),
-            And(expr) => {
-                return if let Boolean(true) = expr.left.eval(context) {
-                    return expr.right.eval(context);
-                } else {
-                    Boolean(false)
-                };
-            }
-            Or(expr) => {
-                return if let Boolean(false) = expr.left.eval(context) {
-                    return expr.right.eval(context);
-                } else {
-                    Boolean(true)
-                };
-            }
-        };
-
-        let cmp = match (l, r) {
-            (Int(l), Int(r)) => l.cmp(&r),
-            (String(l), String(r)) => l.cmp(&r),
-            (Boolean(l), Boolean(r)) => l.cmp(&r),
-            (Char(l), Char(r)) => l.cmp(&r),
-            _ => unreachable!(),
-        };
-
-        Boolean(matches!(
-            (cmp, self),
-            (std::cmp::Ordering::Less, ComparisonExpr::LessThan(_))
-                | (std::cmp::Ordering::Greater, ComparisonExpr::MoreThan(_))
-        ))
+
+        match self {
+            LessThan(expr) => compare(&expr.left, &expr.right, context, Ordering::is_lt),
+            MoreThan(expr) => compare(&expr.left, &expr.right, context, Ordering::is_gt),
+            LessOrEqual(expr) => compare(&expr.left, &expr.right, context, Ordering::is_le),
+            MoreOrEqual(expr) => compare(&expr.left, &expr.right, context, Ordering::is_ge),
+            Equal(expr) => expr.eval(context),
+            NotEqual(expr) => expr.eval(context),
+            And(expr) => expr.eval(context),
+            Or(expr) => expr.eval(context),
+        }
+    }
+}
+
+impl Eval for expr::Equal {
+    fn eval(&self, context: &Context) -> Completion {
+        let l = self.left.eval(context)?;
+        Ok(Object::Boolean(l == self.right.eval(context)?))
+    }
+}
+
+impl Eval for expr::NotEqual {
+    fn eval(&self, context: &Context) -> Completion {
+        let l = self.left.eval(context)?;
+        Ok(Object::Boolean(l != self.right.eval(context)?))
     }
 }
+
+impl Eval for expr::And {
+    fn eval(&self, context: &Context) -> Completion {
+        if let Object::Boolean(true) = self.left.eval(context)? {
+            self.right.eval(context)
+        } else {
+            Ok(Object::Boolean(false))
+        }
+    }
+}
+
+impl Eval for expr::Or {
+    fn eval(&self, context: &Context) -> Completion {
+        if let Object::Boolean(false) = self.left.eval(context)? {
+            self.right.eval(context)
+        } else {
+            Ok(Object::Boolean(true))
+        }
+    }
+}
+
+fn compare(
+    left: &BoxedExpr,
+    right: &BoxedExpr,
+    context: &Context,
+    accepts: fn(Ordering) -> bool,
+) -> Completion {
+    let l = left.eval(context)?;
+    let r = right.eval(context)?;
+    // Comparisons with NaN are always false
+    Ok(Object::Boolean(l.compare(&r).is_some_and(accepts)))
+}
@@ -1,89 +1,197 @@
-use std::cell::RefCell;
-
-use super::Eval;
+use super::{Completion, Eval};
 use crate::{
-    interpreter::{Context, Object},
+    interpreter::{object::Numbers, Context, Object},
     parser::expr::{self, MathExpr},
 };
 
 impl Eval for MathExpr {
-    fn eval(&self, context: &Context) -> Object {
-        use Object::*;
-
+    fn eval(&self, context: &Context) -> Completion {
         match self {
-            MathExpr::Neg(expr) => match expr.0.eval(context) {
-                Object::Int(num) => Object::Int(num.wrapping_neg()),
-                _ => unreachable!(),
-            },
-            MathExpr::BoolNeg(expr) => match expr.0.eval(context) {
-                Object::Boolean(val) => Object::Boolean(!val),
-                _ => unreachable!(),
-            },
+            MathExpr::Neg(expr) => expr.eval(context),
+            MathExpr::BoolNeg(expr) => expr.eval(context),
             MathExpr::Range(expr) => expr.eval(context),
-            MathExpr::Sub(expr) => {
-                let l = expr.left.eval(context);
-                let r = expr.right.eval(context);
-
-                match (l, r) {
-                    (Int(l), Int(r)) => Int(l.wrapping_sub(r)),
-                    _ => unreachable!(),
-                }
-            }
-            MathExpr::Mul(expr) => {
-                let l = expr.left.eval(context);
-                let r = expr.right.eval(context);
-
-                match (l, r) {
-                    (Int(l), Int(r)) => Int(l.wrapping_mul(r)),
-                    _ => unreachable!(),
-                }
-            }
-            MathExpr::Div(expr) => {
-                let l = expr.left.eval(context);
-                let r = expr.right.eval(context);
-
-                match (l, r) {
-                    (Int(l), Int(r)) => Int(l.wrapping_div(r)),
-                    _ => unreachable!(),
-                }
-            }
-            MathExpr::Parens(expr) => expr.0.eval(context),
+            MathExpr::Sub(expr) => expr.eval(context),
+            MathExpr::Mul(expr) => expr.eval(context),
+            MathExpr::Div(expr) => expr.eval(context),
+            MathExpr::Rem(expr) => expr.eval(context),
             MathExpr::Add(expr) => expr.eval(context),
         }
     }
 }
 
+impl Eval for expr::Neg {
+    fn eval(&self, context: &Context) -> Completion {
+        use Object::*;
+        Ok(match self.0.eval(context)? {
+            Int(num) => Int(context.overflow_checked(num.overflowing_neg(), "negation")),
+            Long(num) => Long(context.overflow_checked(num.overflowing_neg(), "negation")),
+            Float(num) => Float(-num),
+            Double(num) => Double(-num),
+            _ => unreachable!(),
+        })
+    }
+}
+
+impl Eval for expr::BoolNeg {
+    fn eval(&self, context: &Context) -> Completion {
+        match self.0.eval(context)? {
+            Object::Boolean(val) => Ok(Object::Boolean(!val)),
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl Eval for expr::Sub {
+    fn eval(&self, context: &Context) -> Completion {
+        let l = self.left.eval(context)?;
+        let r = self.right.eval(context)?;
+        Ok(sub(l, r, context))
+    }
+}
+
+impl Eval for expr::Mul {
+    fn eval(&self, context: &Context) -> Completion {
+        let l = self.left.eval(context)?;
+        let r = self.right.eval(context)?;
+        Ok(arithmetic(Operator::Mul, &l, &r, context))
+    }
+}
+
+impl Eval for expr::Div {
+    fn eval(&self, context: &Context) -> Completion {
+        let l = self.left.eval(context)?;
+        let r = self.right.eval(context)?;
+        Ok(arithmetic(Operator::Div, &l, &r, context))
+    }
+}
+
+impl Eval for expr::Rem {
+    fn eval(&self, context: &Context) -> Completion {
+        let l = self.left.eval(context)?;
+        let r = self.right.eval(context)?;
+        Ok(arithmetic(Operator::Rem, &l, &r, context))
+    }
+}
+
 impl Eval for expr::Range {
-    fn eval(&self, context: &Context) -> Object {
-        let l = self.left.eval(context);
-        let r = self.right.eval(context);
-        Object::Range(l.into(), r.into())
+    fn eval(&self, context: &Context) -> Completion {
+        let l = self.left.eval(context)?;
+        let r = self.right.eval(context)?;
+        Ok(Object::Range(l.into(), r.into()))
+    }
+}
+
+impl Eval for expr::Elvis {
+    fn eval(&self, context: &Context) -> Completion {
+        match self.left.eval(context)? {
+            Object::Null => self.right.eval(context),
+            obj => Ok(obj),
+        }
     }
 }
 
 impl Eval for expr::Add {
-    fn eval(&self, context: &Context) -> Object {
-        use Object::*;
-        let l = self.left.eval(context);
-        let r = self.right.eval(context);
-
-        match (l, r) {
-            (Int(l), Int(r)) => Int(l.wrapping_add(r)),
-            (String(mut string), other) => {
-                string.push_str(&other.to_string());
-                String(string)
-            }
-            (other, String(s)) => {
-                let mut string = other.to_string();
-                string.push_str(&s);
-                String(string)
-            }
-            (Array(l), Array(r)) => {
-                let mut sum = l.borrow().clone();
-                sum.append(&mut r.borrow().clone());
-                Array(std::rc::Rc::new(RefCell::new(sum)))
+    fn eval(&self, context: &Context) -> Completion {
+        let l = self.left.eval(context)?;
+        let r = self.right.eval(context)?;
+        Ok(add(l, r, context))
+    }
+}
+
+// Operands are combined outside of `eval`, which stays small for deep recursion
+fn add(l: Object, r: Object, context: &Context) -> Object {
+    use Object::*;
+    match (l, r) {
+        (Char(l), Int(r)) => Char(shift_char(l, r, context)),
+        (String(mut string), other) => {
+            string.push_str(&context.stringify(other));
+            String(string)
+        }
+        (other, String(s)) => {
+            let mut string = context.stringify(other);
+            string.push_str(&s);
+            String(string)
+        }
+        (Array(l, kind), Array(r, _)) => {
+            let mut sum = l.borrow().clone();
+            sum.append(&mut r.borrow().clone());
+            Object::array(sum, kind)
+        }
+        (l, r) => arithmetic(Operator::Add, &l, &r, context),
+    }
+}
+
+fn sub(l: Object, r: Object, context: &Context) -> Object {
+    use Object::*;
+    match (l, r) {
+        (Char(l), Int(r)) => Char(shift_char(l, r.wrapping_neg(), context)),
+        (Char(l), Char(r)) => Int(l as i32 - r as i32),
+        (l, r) => arithmetic(Operator::Sub, &l, &r, context),
+    }
+}
+
+fn shift_char(c: char, offset: i32, context: &Context) -> char {
+    (c as i32)
+        .checked_add(offset)
+        .and_then(|code| char::from_u32(code as u32))
+        .unwrap_or_else(|| context.exception("Char code is out of range".to_owned()))
+}
+
+pub enum Operator {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+}
+
+impl Operator {
+    fn name(&self) -> &'static str {
+        match self {
+            Operator::Add => "addition",
+            Operator::Sub => "subtraction",
+            Operator::Mul => "multiplication",
+            Operator::Div => "division",
+            Operator::Rem => "remainder",
+        }
+    }
+}
+
+macro_rules! integer_arithmetic {
+    ($op:expr, $l:expr, $r:expr, $context:expr) => {{
+        let result = match $op {
+            Operator::Add => $l.overflowing_add($r),
+            Operator::Sub => $l.overflowing_sub($r),
+            Operator::Mul => $l.overflowing_mul($r),
+            Operator::Div | Operator::Rem if $r == 0 => {
+                $context.exception("Division by zero".to_owned())
             }
-            _ => unreachable!(),
+            Operator::Div => $l.overflowing_div($r),
+            // `MIN % -1` is zero, which is the correct result
+            Operator::Rem => ($l.wrapping_rem($r), false),
+        };
+        $context.overflow_checked(result, $op.name())
+    }};
+}
+
+macro_rules! float_arithmetic {
+    ($op:expr, $l:expr, $r:expr) => {
+        match $op {
+            Operator::Add => $l + $r,
+            Operator::Sub => $l - $r,
+            Operator::Mul => $l * $r,
+            Operator::Div => $l / $r,
+            Operator::Rem => $l % $r,
         }
+    };
+}
+
+pub fn arithmetic(op: Operator, l: &Object, r: &Object, context: &Context) -> Object {
+    match Numbers::promote(l, r) {
+        Some(Numbers::Int(l, r)) => Object::Int(integer_arithmetic!(op, l, r, context)),
+        Some(Numbers::Long(l, r)) => Object::Long(integer_arithmetic!(op, l, r, context)),
+        Some(Numbers::Float(l, r)) => Object::Float(float_arithmetic!(op, l, r)),
+        Some(Numbers::Double(l, r)) => Object::Double(float_arithmetic!(op, l, r)),
+        None => unreachable!(),
     }
 }
@@ -4,22 +4,34 @@ mod short;
 mod top;
 
 use crate::parser::expr::Expr;
-use crate::parser::Spanned;
+use crate::parser::{Body, Spanned};
 
 use super::Context;
 use super::Object;
+pub use math::{arithmetic, Operator};
+
+#[derive(Debug)]
+pub enum Jump {
+    Return(Object),
+    Break,
+    Continue,
+}
+
+pub type Completion = Result<Object, Jump>;
 
 pub trait Eval {
-    fn eval(&self, context: &Context) -> Object;
+    fn eval(&self, context: &Context) -> Completion;
 }
 
 impl Eval for Expr {
-    fn eval(&self, context: &Context) -> Object {
+    fn eval(&self, context: &Context) -> Completion {
         match self {
             Expr::TopExpr(top_expr) => top_expr.eval(context),
             Expr::MathExpr(math_expr) => math_expr.eval(context),
             Expr::ComparisonExpr(comparison) => comparison.eval(context),
+            Expr::Elvis(elvis) => elvis.eval(context),
             Expr::ShortExpr(short_expr) => short_expr.eval(context),
+            Expr::Invalid(_) => unreachable!("programs with syntax errors are never run"),
         }
     }
 }
@@ -28,7 +40,25 @@ impl<E> Eval for Spanned<E>
 where
     E: Eval,
 {
-    fn eval(&self, context: &Context) -> Object {
-        self.expr.eval(context)
+    fn eval(&self, context: &Context) -> Completion {
+        let span = context.last_span.replace(Some(self.span));
+        let completion = self.expr.eval(context);
+        context.last_span.set(span);
+        completion
+    }
+}
+
+pub fn eval_body(body: &Body, context: &Context) -> Completion {
+    let mut obj = Object::Unit;
+    for expr in body {
+        obj = expr.eval(context)?;
     }
+    Ok(obj)
+}
+
+pub fn eval_block(body: &Body, context: &Context) -> Completion {
+    context.push();
+    let completion = eval_body(body, context);
+    context.pop();
+    completion
 }
@@ -1,28 +1,166 @@
-use super::Eval;
+use super::{Completion, Eval, Jump};
+
 use crate::{
-    interpreter::{Context, Object},
-    parser::expr::{self, GetByIndex, ShortExpr},
+    interpreter::{funcs, ArrayKind, Context, Object},
+    parser::{
+        expr::{self, Expr, GetByIndex, ShortExpr},
+        Spanned,
+    },
 };
 
 impl Eval for ShortExpr {
-    fn eval(&self, context: &Context) -> Object {
+    fn eval(&self, context: &Context) -> Completion {
         match self {
-            ShortExpr::Ident(ident) => context.get(&ident.0),
+            ShortExpr::Ident(ident) => Ok(context.get(&ident.0)),
+            ShortExpr::Call(call) => call.eval(context),
+            ShortExpr::Parens(parens) => parens.0.eval(context),
             ShortExpr::GetByIndex(get_by_index) => get_by_index.eval(context),
-            ShortExpr::Literal(literal) => match literal {
-                expr::Literal::Int(i) => Object::Int(*i),
-                expr::Literal::Bool(b) => Object::Boolean(*b),
-                expr::Literal::Char(c) => Object::Char(*c),
-                expr::Literal::String(s) => Object::String(s.clone()),
-            },
+            ShortExpr::Literal(literal) => Ok(literal_value(literal)),
+            ShortExpr::Template(template) => template.eval(context),
+            ShortExpr::Member(member) => member.eval(context),
+            ShortExpr::MethodCall(call) => call.eval(context),
+            ShortExpr::Invoke(invoke) => invoke.eval(context),
+            ShortExpr::Lambda(lambda) => Ok(context.closure(lambda)),
+            ShortExpr::This => Ok(context.get("this")),
+            ShortExpr::NotNull(not_null) => not_null.eval(context),
+        }
+    }
+}
+
+fn literal_value(literal: &expr::Literal) -> Object {
+    match literal {
+        expr::Literal::Int(i) => Object::Int(*i),
+        expr::Literal::Long(i) => Object::Long(*i),
+        expr::Literal::Float(f) => Object::Float(*f),
+        expr::Literal::Double(f) => Object::Double(*f),
+        expr::Literal::Bool(b) => Object::Boolean(*b),
+        expr::Literal::Char(c) => Object::Char(*c),
+        expr::Literal::String(s) => Object::String(s.clone()),
+        expr::Literal::Null => Object::Null,
+    }
+}
+
+// A loop keeps iterator adapters out of the stack of recursive calls
+fn eval_args(args: &[Spanned<Expr>], context: &Context) -> Result<Vec<Object>, Jump> {
+    let mut values = Vec::with_capacity(args.len());
+    for arg in args {
+        values.push(arg.eval(context)?);
+    }
+    Ok(values)
+}
+
+impl Eval for expr::Call {
+    fn eval(&self, context: &Context) -> Completion {
+        let args = eval_args(&self.args, context)?;
+        Ok(context.call_function(&self.name.0, args))
+    }
+}
+
+impl Eval for expr::Invoke {
+    fn eval(&self, context: &Context) -> Completion {
+        let Object::Function(closure) = self.expr.eval(context)? else {
+            unreachable!("only function values can be invoked")
+        };
+        let args = eval_args(&self.args, context)?;
+        Ok(context.call_closure(&closure, args))
+    }
+}
+
+impl Eval for expr::NotNull {
+    fn eval(&self, context: &Context) -> Completion {
+        match self.0.eval(context)? {
+            Object::Null => context.exception("NullPointerException".to_owned()),
+            obj => Ok(obj),
+        }
+    }
+}
+
+impl Eval for expr::Template {
+    fn eval(&self, context: &Context) -> Completion {
+        let mut string = String::new();
+        for part in &self.0 {
+            match part {
+                expr::TemplatePart::Str(s) => string.push_str(s),
+                expr::TemplatePart::Expr(expr) => {
+                    string.push_str(&context.stringify(expr.eval(context)?))
+                }
+            }
+        }
+        Ok(Object::String(string))
+    }
+}
+
+impl Eval for expr::Member {
+    fn eval(&self, context: &Context) -> Completion {
+        if let Some(class) = context.find_enum(&self.expr) {
+            let entry = context.enum_entry(class, &self.name.0);
+            return Ok(Object::Instance(entry.expect("entry exists")));
+        }
+
+        let obj = self.expr.eval(context)?;
+
+        match (obj, self.name.0.as_str()) {
+            (Object::Null, _) if self.is_safe => Ok(Object::Null),
+            (Object::Instance(instance), name) => Ok(instance.get(name)),
+            (obj, name) => Ok(funcs::builtin_property(obj, name)),
+        }
+    }
+}
+
+impl Eval for expr::MethodCall {
+    fn eval(&self, context: &Context) -> Completion {
+        if let Some(class) = context.find_enum(&self.expr) {
+            return match self.name.0.as_str() {
+                "values" => {
+                    let entries = context.enum_entries(class).into_iter();
+                    let entries = entries.map(Object::Instance).collect();
+                    Ok(Object::array(entries, ArrayKind::Other))
+                }
+                "valueOf" => {
+                    let Object::String(name) = self.args[0].eval(context)? else {
+                        unreachable!()
+                    };
+                    match context.enum_entry(class, &name) {
+                        Some(entry) => Ok(Object::Instance(entry)),
+                        None => context.exception(format!(
+                            "IllegalArgumentException: No enum constant {}.{name}",
+                            class.name.0
+                        )),
+                    }
+                }
+                _ => unreachable!(),
+            };
+        }
+
+        let obj = self.expr.eval(context)?;
+        if matches!(obj, Object::Null) && self.is_safe {
+            return Ok(Object::Null);
+        }
+
+        let args = eval_args(&self.args, context)?;
+
+        match obj {
+            Object::Instance(instance) => {
+                if context.is_copy(&instance, &self.name.0) {
+                    let named_args = self
+                        .named_args
+                        .iter()
+                        .map(|(name, arg)| Ok((name.0.as_str(), arg.eval(context)?)))
+                        .collect::<Result<_, _>>()?;
+                    return Ok(context.copy(&instance, args, named_args));
+                }
+
+                Ok(context.call_method(instance, &self.name.0, args))
+            }
+            obj => Ok(funcs::builtin_method(obj, &self.name.0, args, context)),
         }
     }
 }
 
 impl Eval for GetByIndex {
-    fn eval(&self, context: &Context) -> Object {
-        let name = &self.ident.0;
-        let Object::Int(index) = self.index.eval(context) else {
+    fn eval(&self, context: &Context) -> Completion {
+        let obj = self.expr.eval(context)?;
+        let Object::Int(index) = self.index.eval(context)? else {
             unreachable!()
         };
 
@@ -30,14 +168,18 @@ impl Eval for GetByIndex {
             context.exception("Index out of range".to_owned());
         }
 
-        let obj = context.get(name);
-
         match obj {
-            Object::Array(arr) => arr
+            Object::Array(arr, _) => Ok(arr
                 .borrow()
                 .get(index as usize)
                 .unwrap_or_else(|| context.exception("Index out of range".to_owned()))
-                .to_owned(),
+                .to_owned()),
+            Object::String(string) => Ok(Object::Char(
+                string
+                    .chars()
+                    .nth(index as usize)
+                    .unwrap_or_else(|| context.exception("Index out of range".to_owned())),
+            )),
             _ => unreachable!(),
         }
     }
@@ -1,105 +1,175 @@
 use super::Eval;
 use crate::{
-    interpreter::{Context, Object},
-    parser::expr::{self, ControlExpr},
+    interpreter::{
+        eval::{eval_block, eval_body, Completion, Jump},
+        Context, Object,
+    },
+    parser::{
+        expr::{self, ControlExpr},
+        Body,
+    },
 };
 
 impl Eval for ControlExpr {
-    fn eval(&self, context: &Context) -> Object {
+    fn eval(&self, context: &Context) -> Completion {
         match self {
             ControlExpr::If(expr) => expr.eval(context),
             ControlExpr::For(expr) => expr.eval(context),
             ControlExpr::While(expr) => expr.eval(context),
+            ControlExpr::When(expr) => expr.eval(context),
             ControlExpr::Return(expr) => {
-                if let Some(e) = &expr.0 {
-                    context.ret_item.replace(Some(e.eval(context)));
+                let obj = if let Some(e) = &expr.0 {
+                    e.eval(context)?
                 } else {
-                    context.ret_item.replace(Some(Object::Unit));
-                }
-                Object::Unit
+                    Object::Unit
+                };
+                Err(Jump::Return(obj))
             }
+            ControlExpr::Break(_) => Err(Jump::Break),
+            ControlExpr::Continue(_) => Err(Jump::Continue),
         }
     }
 }
 
+fn eval_iteration(body: &Body, context: &Context) -> Result<bool, Jump> {
+    match eval_body(body, context) {
+        Ok(_) | Err(Jump::Continue) => Ok(true),
+        Err(Jump::Break) => Ok(false),
+        Err(jump) => Err(jump),
+    }
+}
+
 impl Eval for expr::If {
-    fn eval(&self, context: &Context) -> Object {
-        let bool = match self.expr.eval(context) {
+    fn eval(&self, context: &Context) -> Completion {
+        let bool = match self.expr.eval(context)? {
             Object::Boolean(bool) => bool,
             _ => unreachable!(),
         };
 
-        context.push();
-        if bool {
-            for expr in &self.body {
-                expr.eval(context);
-            }
-        } else {
-            for expr in &self.else_branch {
-                expr.eval(context);
-            }
-        }
-        context.pop();
+        let body = if bool { &self.body } else { &self.else_branch };
 
-        Object::Unit
+        eval_block(body, context)
     }
 }
 
 impl Eval for expr::For {
-    fn eval(&self, context: &Context) -> Object {
-        let name = &self.var.0;
-        let iterable = self.iterable.eval(context);
+    fn eval(&self, context: &Context) -> Completion {
+        let iterable = self.iterable.eval(context)?;
+        iterate(self, iterable, context)?;
+        Ok(Object::Unit)
+    }
+}
 
-        context.push();
+fn iterate(for_loop: &expr::For, iterable: Object, context: &Context) -> Result<(), Jump> {
+    let iteration = |obj| eval_for_iteration(for_loop, obj, context);
 
-        match iterable {
-            Object::String(string) => {
-                for c in string.chars() {
-                    context.var(name, Object::Char(c));
-                    for expr in &self.body {
-                        expr.eval(context);
+    match iterable {
+        Object::String(string) => {
+            for c in string.chars() {
+                if !iteration(Object::Char(c))? {
+                    break;
+                }
+            }
+        }
+        Object::Array(arr, _) => {
+            for obj in arr.borrow().clone() {
+                if !iteration(obj)? {
+                    break;
+                }
+            }
+        }
+        Object::Range(l, r) => match (*l, *r) {
+            (Object::Int(l), Object::Int(r)) => {
+                for i in l..=r {
+                    if !iteration(Object::Int(i))? {
+                        break;
                     }
                 }
             }
-            Object::Array(arr) => {
-                for obj in arr.borrow().clone() {
-                    context.var(name, obj);
-                    for expr in &self.body {
-                        expr.eval(context);
+            (Object::Long(l), Object::Long(r)) => {
+                for i in l..=r {
+                    if !iteration(Object::Long(i))? {
+                        break;
                     }
                 }
             }
-            Object::Range(l, r) => match (*l, *r) {
-                (Object::Int(l), Object::Int(r)) => {
-                    for i in l..=r {
-                        context.var(name, Object::Int(i));
-                        for expr in &self.body {
-                            expr.eval(context);
-                        }
+            (Object::Char(l), Object::Char(r)) => {
+                for c in l..=r {
+                    if !iteration(Object::Char(c))? {
+                        break;
                     }
                 }
-                _ => unreachable!(),
-            },
+            }
             _ => unreachable!(),
-        };
+        },
+        _ => unreachable!(),
+    };
 
-        context.pop();
-        Object::Unit
-    }
+    Ok(())
+}
+
+/// Evaluates one iteration of a `for` loop in its own scope,
+/// so lambdas created in the body capture the value of this iteration
+fn eval_for_iteration(for_loop: &expr::For, obj: Object, context: &Context) -> Result<bool, Jump> {
+    context.push();
+    context.var(&for_loop.var.0, obj);
+    let proceed = eval_iteration(&for_loop.body, context);
+    context.pop();
+    proceed
 }
 
 impl Eval for expr::While {
-    fn eval(&self, context: &Context) -> Object {
-        while match self.expr.eval(context) {
+    fn eval(&self, context: &Context) -> Completion {
+        while match self.expr.eval(context)? {
             Object::Boolean(bool) => bool,
             _ => unreachable!(),
         } {
             context.push();
-            for expr in &self.body {
-                expr.eval(context);
-            }
+            let proceed = eval_iteration(&self.body, context);
             context.pop();
+            if !proceed? {
+                break;
+            }
         }
-        Object::Unit
+        Ok(Object::Unit)
     }
 }
+
+impl Eval for expr::When {
+    fn eval(&self, context: &Context) -> Completion {
+        let subject = match &self.subject {
+            Some(subject) => Some(subject.eval(context)?),
+            None => None,
+        };
+
+        for branch in &self.branches {
+            for condition in &branch.conditions {
+                if matches_condition(condition, subject.as_ref(), context)? {
+                    return eval_block(&branch.body, context);
+                }
+            }
+        }
+
+        match &self.else_branch {
+            Some(body) => eval_block(body, context),
+            None => Ok(Object::Unit),
+        }
+    }
+}
+
+fn matches_condition(
+    condition: &expr::WhenCondition,
+    subject: Option<&Object>,
+    context: &Context,
+) -> Result<bool, Jump> {
+    let matches = match (condition, subject) {
+        (expr::WhenCondition::Expr(expr), Some(subject)) => expr.eval(context)? == *subject,
+        (expr::WhenCondition::Expr(expr), None) => {
+            matches!(expr.eval(context)?, Object::Boolean(true))
+        }
+        (expr::WhenCondition::In(expr), Some(subject)) => expr.eval(context)?.contains(subject),
+        (expr::WhenCondition::Is(ty), Some(subject)) => context.is_instance(subject, ty),
+        (expr::WhenCondition::In(_) | expr::WhenCondition::Is(_), None) => unreachable!(),
+    };
+    Ok(matches)
+}
@@ -1,60 +1,70 @@
 mod control;
 
-use super::Eval;
+use super::{Completion, Eval};
 use crate::{
     interpreter::{Context, Object},
     parser::expr::{self, TopExpr},
 };
 
 impl Eval for TopExpr {
-    fn eval(&self, context: &Context) -> Object {
+    fn eval(&self, context: &Context) -> Completion {
         match self {
             TopExpr::ControlExpr(control_expr) => control_expr.eval(context),
             TopExpr::Binding(binding) => binding.eval(context),
             TopExpr::Set(set) => set.eval(context),
-            TopExpr::Call(expr) => {
-                let name = &expr.name.0;
-                let args = expr.args.iter().map(|e| e.eval(context)).collect();
-                context.call_function(name, args)
-            }
             TopExpr::SetByIndex(set_by_index) => set_by_index.eval(context),
+            TopExpr::SetMember(set_member) => set_member.eval(context),
+            TopExpr::Destructuring(destructuring) => destructuring.eval(context),
         }
     }
 }
 
 impl Eval for expr::Binding {
-    fn eval(&self, context: &Context) -> Object {
+    fn eval(&self, context: &Context) -> Completion {
         let set = &self.set;
-        let obj = set.expr.eval(context);
+        let obj = set.expr.eval(context)?;
         context.var(&set.name.0, obj);
-        Object::Unit
+        Ok(Object::Unit)
+    }
+}
+
+impl Eval for expr::Destructuring {
+    fn eval(&self, context: &Context) -> Completion {
+        let Object::Instance(instance) = self.expr.eval(context)? else {
+            unreachable!()
+        };
+
+        for (index, name) in self.names.iter().enumerate() {
+            let component = format!("component{}", index + 1);
+            let obj = context.call_method(instance.clone(), &component, vec![]);
+            context.var(&name.0, obj);
+        }
+        Ok(Object::Unit)
     }
 }
 
 impl Eval for expr::Set {
-    fn eval(&self, context: &Context) -> Object {
-        let obj = self.expr.eval(context);
+    fn eval(&self, context: &Context) -> Completion {
+        let obj = self.expr.eval(context)?;
         context.set(&self.name.0, obj);
-        Object::Unit
+        Ok(Object::Unit)
     }
 }
 
 impl Eval for expr::SetByIndex {
-    fn eval(&self, context: &Context) -> Object {
-        let name = &self.get_by_index.ident.0;
-        let value = self.expr.eval(context);
-        let Object::Int(index) = self.get_by_index.index.eval(context) else {
+    fn eval(&self, context: &Context) -> Completion {
+        let obj = self.get_by_index.expr.eval(context)?;
+        let Object::Int(index) = self.get_by_index.index.eval(context)? else {
             unreachable!()
         };
+        let value = self.expr.eval(context)?;
 
         if index < 0 {
             context.exception("Index out of range".to_owned());
         }
 
-        let obj = context.get(name);
-
-        match &obj {
-            Object::Array(arr) => {
+        match obj {
+            Object::Array(arr, _) => {
                 let mut arr = arr.borrow_mut();
                 let entry = arr
                     .get_mut(index as usize)
@@ -64,8 +74,23 @@ impl Eval for expr::SetByIndex {
             _ => unreachable!(),
         }
 
-        context.set(name, obj);
+        Ok(Object::Unit)
+    }
+}
+
+impl Eval for expr::SetMember {
+    fn eval(&self, context: &Context) -> Completion {
+        let obj = self.member.expr.eval(context)?;
+
+        match obj {
+            Object::Null if self.member.is_safe => {}
+            Object::Instance(instance) => {
+                let value = self.expr.eval(context)?;
+                instance.set(&self.member.name.0, value);
+            }
+            _ => unreachable!(),
+        }
 
-        Object::Unit
+        Ok(Object::Unit)
     }
 }
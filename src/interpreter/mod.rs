@@ -1,19 +1,42 @@
 mod eval;
 mod object;
 
-use object::Object;
+use object::{ArrayKind, Closure, Instance, InstanceKind, Object};
 
 use crate::{
-    interpreter::eval::Eval,
-    parser::{Fun, Program},
+    analyzer::pretty_print_error,
+    interpreter::eval::{eval_body, Completion, Eval, Jump},
+    lexer::Span,
+    parser::{
+        expr::{Lambda, ShortExpr},
+        Body, Class, Fun, Modifier, Program, Type,
+    },
 };
-use std::{cell::RefCell, collections::HashMap};
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    rc::Rc,
+};
+
+type VTable = HashMap<String, (String, usize)>;
+
+type Scope = Rc<RefCell<HashMap<String, Object>>>;
 
 #[derive(Debug, Default)]
 pub struct Context {
     functions: HashMap<String, Fun>,
-    scopes: RefCell<Vec<HashMap<String, Object>>>,
-    ret_item: RefCell<Option<Object>>,
+    classes: HashMap<String, Class>,
+    vtables: HashMap<String, VTable>,
+    enum_entries: RefCell<HashMap<String, Vec<Rc<Instance>>>>,
+    scopes: RefCell<Vec<Scope>>,
+    /// Index of the first scope of the function being called,
+    /// variables of callers aren't visible to the callee
+    frame: Cell<usize>,
+    checked_arithmetic: bool,
+    source: Option<String>,
+    last_span: Cell<Option<Span>>,
+    /// Called after an exception is reported, without it the exception panics
+    exit: Option<fn(String) -> !>,
 }
 
 impl Context {
@@ -24,41 +47,97 @@ impl Context {
             .map(|f| (f.name.0.clone(), f))
             .collect();
 
+        let classes: HashMap<_, _> = prog
+            .classes
+            .into_iter()
+            .map(|c| (c.name.0.clone(), c))
+            .collect();
+
+        let mut vtables = HashMap::new();
+        for name in classes.keys() {
+            build_vtable(&classes, name, &mut vtables);
+        }
+
         Self {
             functions,
+            classes,
+            vtables,
             ..Default::default()
         }
     }
 
+    /// Integer overflow raises an exception instead of wrapping around
+    pub fn with_checked_arithmetic(self, checked_arithmetic: bool) -> Self {
+        Self {
+            checked_arithmetic,
+            ..self
+        }
+    }
+
+    pub fn with_source(self, source: &str) -> Self {
+        Self {
+            source: Some(source.to_owned()),
+            ..self
+        }
+    }
+
+    pub fn with_exit(self, exit: fn(String) -> !) -> Self {
+        Self {
+            exit: Some(exit),
+            ..self
+        }
+    }
+
     pub fn run(&self) {
         self.call_function("main", vec![]);
     }
 
-    fn get(&self, name: &str) -> Object {
-        for scope in self.scopes.borrow().iter().rev() {
-            if let Some(obj) = scope.get(name) {
-                return obj.clone();
-            }
+    fn lookup(&self, name: &str) -> Option<Object> {
+        let scopes = self.scopes.borrow();
+        scopes[self.frame.get()..]
+            .iter()
+            .rev()
+            .find_map(|scope| scope.borrow().get(name).cloned())
+    }
+
+    fn this(&self) -> Option<Rc<Instance>> {
+        match self.lookup("this")? {
+            Object::Instance(instance) => Some(instance),
+            _ => unreachable!(),
         }
-        unreachable!()
+    }
+
+    /// Variables shadow properties of `this`, which shadow entries of its enum class
+    fn get(&self, name: &str) -> Object {
+        self.lookup(name)
+            .or_else(|| {
+                let this = self.this()?;
+                this.try_get(name).or_else(|| {
+                    let entry = self.enum_entry(&self.classes[&this.class], name)?;
+                    Some(Object::Instance(entry))
+                })
+            })
+            .unwrap_or_else(|| unreachable!())
     }
 
     fn var(&self, name: &str, obj: Object) {
         self.scopes
             .borrow_mut()
-            .last_mut()
+            .last()
             .unwrap()
+            .borrow_mut()
             .insert(name.to_owned(), obj);
     }
 
     fn set(&self, name: &str, obj: Object) {
-        for scope in self.scopes.borrow_mut().iter_mut().rev() {
-            if scope.get(name).is_some() {
-                scope.insert(name.to_owned(), obj);
+        let frame = self.frame.get();
+        for scope in self.scopes.borrow()[frame..].iter().rev() {
+            if let Some(value) = scope.borrow_mut().get_mut(name) {
+                *value = obj;
                 return;
             }
         }
-        unreachable!()
+        self.this().expect("variable exists").set(name, obj)
     }
 
     fn push(&self) {
@@ -70,8 +149,26 @@ impl Context {
     }
 
     fn exception(&self, message: String) -> ! {
-        eprintln!("{}", message);
-        std::process::exit(0)
+        match (&self.source, self.last_span.get()) {
+            (Some(source), Some(span)) => {
+                eprintln!("Exception:");
+                pretty_print_error(source, span, &message);
+            }
+            (None, Some(Span { lo, hi })) => eprintln!("{message} at {lo}..{hi}"),
+            (_, None) => eprintln!("{message}"),
+        }
+
+        match self.exit {
+            Some(exit) => exit(message),
+            None => panic!("{message}"),
+        }
+    }
+
+    fn overflow_checked<T>(&self, (value, overflow): (T, bool), operation: &str) -> T {
+        if overflow && self.checked_arithmetic {
+            self.exception(format!("Integer overflow in {operation}"))
+        }
+        value
     }
 
     fn call_function(&self, name: &str, args: Vec<Object>) -> Object {
@@ -79,25 +176,300 @@ impl Context {
             return obj;
         }
 
-        if let Some(fun) = self.functions.get(name).to_owned() {
-            assert_eq!(fun.args.len(), args.len());
-            let names = fun.args.iter().map(|a| (a.0).0.to_owned());
+        let value = self
+            .lookup(name)
+            .or_else(|| self.this().and_then(|this| this.try_get(name)));
+        if let Some(Object::Function(closure)) = value {
+            self.call_closure(&closure, args)
+        } else if let Some(fun) = self.functions.get(name) {
+            self.call(fun, HashMap::new(), args)
+        } else if let Some(class) = self.classes.get(name) {
+            self.construct(class, args)
+        } else if let Some(this) = self.this() {
+            // Methods of `this` can be called without a receiver
+            self.call_method(this, name, args)
+        } else {
+            unimplemented!("function {name} doesn't exist")
+        }
+    }
+
+    fn call_method(&self, this: Rc<Instance>, name: &str, args: Vec<Object>) -> Object {
+        let Some(method) = self.find_method(&this.class, name) else {
+            if let Some(Object::Function(closure)) = this.try_get(name) {
+                return self.call_closure(&closure, args);
+            }
+            return self.call_data_method(this, name, args);
+        };
 
-            self.scopes.borrow_mut().push(names.zip(args).collect());
+        let scope = HashMap::from([("this".to_owned(), Object::Instance(this))]);
+        self.call(method, scope, args)
+    }
 
-            for expr in &fun.body {
-                expr.eval(self);
-                if let Some(obj) = self.ret_item.borrow_mut().take() {
-                    self.pop();
-                    return obj;
-                }
+    fn find_method(&self, class: &str, name: &str) -> Option<&Fun> {
+        let (class, index) = self.vtables[class].get(name)?;
+        Some(&self.classes[class].methods[*index])
+    }
+
+    fn stringify(&self, obj: Object) -> String {
+        match obj {
+            Object::Instance(instance)
+                if self.find_method(&instance.class, "toString").is_some() =>
+            {
+                self.call_method(instance, "toString", vec![]).to_string()
             }
+            obj => obj.display_with(|nested| self.stringify(nested)),
+        }
+    }
+
+    fn is_subclass(&self, class: &str, ancestor: &str) -> bool {
+        class == ancestor
+            || self.classes[class]
+                .supertypes
+                .iter()
+                .any(|supertype| self.is_subclass(&supertype.name.0, ancestor))
+    }
 
-            self.pop();
-            Object::Unit
+    fn is_instance(&self, obj: &Object, ty: &Type) -> bool {
+        match (ty, obj) {
+            (Type::Nullable(_), Object::Null) => true,
+            (Type::Nullable(ty), obj) => self.is_instance(obj, ty),
+            (Type::Simple(name), Object::Instance(instance)) => {
+                self.is_subclass(&instance.class, &name.0)
+            }
+            (Type::Simple(name), obj) => matches!(
+                (name.0.as_str(), obj),
+                ("Int", Object::Int(_))
+                    | ("Long", Object::Long(_))
+                    | ("Float", Object::Float(_))
+                    | ("Double", Object::Double(_))
+                    | ("String", Object::String(_))
+                    | ("Boolean", Object::Boolean(_))
+                    | ("Char", Object::Char(_))
+            ),
+            (Type::Generic(name, _), Object::Array(..)) => name.0 == "Array",
+            (Type::Function(params, _), Object::Function(closure)) => {
+                let is_implicit_it = closure.params.is_empty() && params.len() == 1;
+                params.len() == closure.params.len() || is_implicit_it
+            }
+            _ => false,
+        }
+    }
+
+    fn find_enum(&self, receiver: &ShortExpr) -> Option<&Class> {
+        let ShortExpr::Ident(name) = receiver else {
+            return None;
+        };
+        let is_property = || {
+            self.this()
+                .is_some_and(|this| this.try_get(&name.0).is_some())
+        };
+        if self.lookup(&name.0).is_some() || is_property() {
+            return None;
+        }
+        self.classes
+            .get(&name.0)
+            .filter(|class| class.is(Modifier::Enum))
+    }
+
+    fn enum_entries(&self, class: &Class) -> Vec<Rc<Instance>> {
+        if let Some(entries) = self.enum_entries.borrow().get(&class.name.0) {
+            return entries.clone();
+        }
+
+        // Arguments of entries don't see variables of the caller
+        let entries: Vec<_> = self.in_frame(HashMap::new(), || {
+            let entries = class.entries.iter().enumerate();
+            entries
+                .map(|(ordinal, entry)| {
+                    let args = entry.args.iter().map(|arg| self.eval_initializer(arg));
+                    let name = entry.name.0.clone();
+                    let kind = InstanceKind::Entry { name, ordinal };
+                    self.instantiate(class, kind, args.collect())
+                })
+                .collect()
+        });
+
+        let mut enum_entries = self.enum_entries.borrow_mut();
+        let entries = enum_entries.entry(class.name.0.clone()).or_insert(entries);
+        entries.clone()
+    }
+
+    fn enum_entry(&self, class: &Class, name: &str) -> Option<Rc<Instance>> {
+        let entries = self.enum_entries(class).into_iter();
+        entries
+            .zip(&class.entries)
+            .find_map(|(instance, entry)| (entry.name.0 == name).then_some(instance))
+    }
+
+    fn call_data_method(&self, this: Rc<Instance>, name: &str, args: Vec<Object>) -> Object {
+        match name {
+            "equals" => Object::Boolean(Object::Instance(this) == args[0]),
+            "hashCode" => Object::Int(Object::Instance(this).hash_code()),
+            "toString" => {
+                let this = Object::Instance(this);
+                Object::String(this.display_with(|nested| self.stringify(nested)))
+            }
+            component => {
+                let index: usize = component
+                    .strip_prefix("component")
+                    .and_then(|index| index.parse().ok())
+                    .expect("generated method exists");
+                this.component(index - 1)
+            }
+        }
+    }
+
+    fn is_copy(&self, this: &Instance, name: &str) -> bool {
+        name == "copy" && self.classes[&this.class].is(Modifier::Data)
+    }
+
+    fn copy(&self, this: &Instance, args: Vec<Object>, named_args: Vec<(&str, Object)>) -> Object {
+        let class = &self.classes[&this.class];
+        let mut values: Vec<_> = (0..class.params.len())
+            .map(|index| this.component(index))
+            .collect();
+
+        for (value, arg) in values.iter_mut().zip(args) {
+            *value = arg;
+        }
+        for (name, arg) in named_args {
+            let index = class
+                .params
+                .iter()
+                .position(|param| param.name.0 == name)
+                .expect("parameter exists");
+            values[index] = arg;
+        }
+
+        self.construct(class, values)
+    }
+
+    fn call(&self, fun: &Fun, mut scope: HashMap<String, Object>, args: Vec<Object>) -> Object {
+        assert_eq!(fun.args.len(), args.len());
+        let names = fun.args.iter().map(|a| (a.0).0.to_owned());
+        scope.extend(names.zip(args));
+
+        let body = fun
+            .body
+            .as_ref()
+            .expect("abstract methods are never called");
+        let completion = self.eval_in_frame(&[], scope, body);
+
+        match completion {
+            Ok(_) => Object::Unit,
+            Err(Jump::Return(obj)) => obj,
+            Err(Jump::Break | Jump::Continue) => unreachable!(),
+        }
+    }
+
+    fn closure(&self, lambda: &Lambda) -> Object {
+        let scopes = self.scopes.borrow()[self.frame.get()..].to_vec();
+        Object::Function(Rc::new(Closure {
+            params: lambda.params.iter().map(|p| p.name.0.clone()).collect(),
+            body: lambda.body.clone(),
+            scopes,
+        }))
+    }
+
+    /// Calls a closure in a frame which starts with its captured scopes,
+    /// a lambda without parameters receives a single argument as `it`
+    fn call_closure(&self, closure: &Closure, args: Vec<Object>) -> Object {
+        let scope = if closure.params.is_empty() && args.len() == 1 {
+            HashMap::from([("it".to_owned(), args.into_iter().next().unwrap())])
         } else {
-            unimplemented!("function {name} doesn't exist")
+            assert_eq!(closure.params.len(), args.len());
+            closure.params.iter().cloned().zip(args).collect()
+        };
+
+        let completion = self.eval_in_frame(&closure.scopes, scope, &closure.body);
+        match completion {
+            Ok(obj) => obj,
+            Err(_) => unreachable!(),
+        }
+    }
+
+    fn construct(&self, class: &Class, args: Vec<Object>) -> Object {
+        let kind = match class.is(Modifier::Data) {
+            true => InstanceKind::Data {
+                components: class.params.len(),
+            },
+            false => InstanceKind::Object,
+        };
+        Object::Instance(self.instantiate(class, kind, args))
+    }
+
+    fn instantiate(&self, class: &Class, kind: InstanceKind, args: Vec<Object>) -> Rc<Instance> {
+        let instance = Rc::new(Instance::new(&class.name.0, kind));
+        self.init(class, &instance, args);
+        instance
+    }
+
+    fn init(&self, class: &Class, instance: &Rc<Instance>, args: Vec<Object>) {
+        assert_eq!(class.params.len(), args.len());
+
+        let mut scope = HashMap::from([("this".to_owned(), Object::Instance(instance.clone()))]);
+        for (param, arg) in class.params.iter().zip(args) {
+            if param.is_property {
+                instance.set(&param.name.0, arg.clone());
+            }
+            scope.insert(param.name.0.clone(), arg);
         }
+
+        // Initializers see constructor parameters, which shadow properties
+        self.in_frame(scope, || {
+            for supertype in &class.supertypes {
+                if let Some(ref args) = supertype.args {
+                    let args = args.iter().map(|arg| self.eval_initializer(arg));
+                    self.init(&self.classes[&supertype.name.0], instance, args.collect());
+                }
+            }
+
+            for property in &class.properties {
+                let obj = self.eval_initializer(&*property.set.expr);
+                instance.set(&property.set.name.0, obj);
+            }
+        });
+    }
+
+    fn eval_initializer(&self, expr: &impl Eval) -> Object {
+        match expr.eval(self) {
+            Ok(obj) => obj,
+            Err(_) => unreachable!(),
+        }
+    }
+
+    fn in_frame<T>(&self, scope: HashMap<String, Object>, f: impl FnOnce() -> T) -> T {
+        let (len, frame) = self.enter_frame(&[], scope);
+        let result = f();
+        self.leave_frame(len, frame);
+        result
+    }
+
+    // Bodies of calls are evaluated without a closure in between, so recursion takes less stack
+    fn eval_in_frame(
+        &self,
+        captured: &[Scope],
+        scope: HashMap<String, Object>,
+        body: &Body,
+    ) -> Completion {
+        let (len, frame) = self.enter_frame(captured, scope);
+        let completion = eval_body(body, self);
+        self.leave_frame(len, frame);
+        completion
+    }
+
+    fn enter_frame(&self, captured: &[Scope], scope: HashMap<String, Object>) -> (usize, usize) {
+        let mut scopes = self.scopes.borrow_mut();
+        let len = scopes.len();
+        let frame = self.frame.replace(len);
+        scopes.extend(captured.iter().cloned());
+        scopes.push(Rc::new(RefCell::new(scope)));
+        (len, frame)
+    }
+
+    fn leave_frame(&self, len: usize, frame: usize) {
+        self.scopes.borrow_mut().truncate(len);
+        self.frame.set(frame);
     }
 
     fn call_predefined_function(&self, name: &str, args: &[Object]) -> Option<Object> {
@@ -106,23 +478,61 @@ impl Context {
             "println" => println,
             "print" => print,
             "arrayOf" => arrayOf,
+            "Array" => Array,
             "readln" => readln,
+            "readLine" => readLine,
             "readlnInt" => readlnInt,
             "readlnBoolean" => readlnBoolean,
-            _ => return None,
+            _ => return primitive_array(name, args, self),
         };
         Some(fun(args.to_vec(), self))
     }
 }
 
+fn build_vtable(
+    classes: &HashMap<String, Class>,
+    name: &str,
+    vtables: &mut HashMap<String, VTable>,
+) {
+    if vtables.contains_key(name) {
+        return;
+    }
+
+    let class = &classes[name];
+    let mut vtable = VTable::new();
+
+    // Implementations of the superclass take precedence over default methods of interfaces
+    let mut supertypes: Vec<_> = class.supertypes.iter().collect();
+    supertypes.sort_by_key(|supertype| supertype.args.is_none());
+    for supertype in supertypes {
+        let supertype = &supertype.name.0;
+        build_vtable(classes, supertype, vtables);
+        for (method, implementation) in &vtables[supertype] {
+            vtable
+                .entry(method.clone())
+                .or_insert_with(|| implementation.clone());
+        }
+    }
+
+    for (index, method) in class.methods.iter().enumerate() {
+        if method.body.is_some() {
+            vtable.insert(method.name.0.clone(), (name.to_owned(), index));
+        }
+    }
+
+    vtables.insert(name.to_owned(), vtable);
+}
+
 mod funcs {
     #![allow(non_snake_case)]
 
     use super::*;
+    use crate::interpreter::eval::{arithmetic, Operator};
+    use std::cmp::Ordering;
 
-    pub fn print(args: Vec<Object>, _: &Context) -> Object {
+    pub fn print(args: Vec<Object>, c: &Context) -> Object {
         for arg in args {
-            print!("{}", arg.to_string())
+            print!("{}", c.stringify(arg))
         }
         Object::Unit
     }
@@ -134,7 +544,214 @@ mod funcs {
     }
 
     pub fn arrayOf(args: Vec<Object>, _: &Context) -> Object {
-        Object::Array(std::rc::Rc::new(RefCell::new(args)))
+        let kind = ArrayKind::of(args.first());
+        Object::array(args, kind)
+    }
+
+    fn array_size(args: &[Object], context: &Context) -> usize {
+        match args[0] {
+            Object::Int(size) if size < 0 => {
+                context.exception(format!("NegativeArraySizeException: {size}"))
+            }
+            Object::Int(size) => size as usize,
+            _ => unreachable!(),
+        }
+    }
+
+    pub fn Array(args: Vec<Object>, context: &Context) -> Object {
+        let size = array_size(&args, context);
+        let Object::Function(ref init) = args[1] else {
+            unreachable!()
+        };
+        let items = (0..size)
+            .map(|index| context.call_closure(init, vec![Object::Int(index as i32)]))
+            .collect();
+        arrayOf(items, context)
+    }
+
+    pub fn primitive_array(name: &str, args: &[Object], context: &Context) -> Option<Object> {
+        let zero = match name {
+            "IntArray" => Object::Int(0),
+            "LongArray" => Object::Long(0),
+            "FloatArray" => Object::Float(0.0),
+            "DoubleArray" => Object::Double(0.0),
+            "BooleanArray" => Object::Boolean(false),
+            "CharArray" => Object::Char('\0'),
+            _ => return None,
+        };
+        let size = array_size(args, context);
+        let kind = ArrayKind::of(Some(&zero));
+        Some(Object::array(vec![zero; size], kind))
+    }
+
+    pub fn builtin_property(obj: Object, name: &str) -> Object {
+        let len = match (&obj, name) {
+            (Object::Char(c), "code") => return Object::Int(*c as i32),
+            (Object::Range(lo, _), "first") => return lo.as_ref().clone(),
+            (Object::Range(_, hi), "last") => return hi.as_ref().clone(),
+            (Object::String(string), _) => string.chars().count() as i32,
+            (Object::Array(array, _), _) => array.borrow().len() as i32,
+            _ => unreachable!(),
+        };
+        match name {
+            "length" | "size" => Object::Int(len),
+            "lastIndex" => Object::Int(len - 1),
+            "indices" => Object::Range(Box::new(Object::Int(0)), Box::new(Object::Int(len - 1))),
+            _ => unreachable!(),
+        }
+    }
+
+    pub fn builtin_method(obj: Object, name: &str, args: Vec<Object>, context: &Context) -> Object {
+        match (obj, name) {
+            (obj, "toString") => Object::String(context.stringify(obj)),
+            (Object::String(string), name) => string_method(&string, name, args, context),
+            (Object::Array(array, kind), name) => array_method(&array, kind, name, args, context),
+            (Object::Range(lo, hi), name) => range_method(*lo, *hi, name, args, context),
+            _ => unreachable!(),
+        }
+    }
+
+    fn string_method(string: &str, name: &str, args: Vec<Object>, context: &Context) -> Object {
+        let string_arg = |index: usize| match &args[index] {
+            Object::String(arg) => arg.as_str(),
+            _ => unreachable!(),
+        };
+        let int_arg = |index: usize| match args[index] {
+            Object::Int(arg) => arg,
+            _ => unreachable!(),
+        };
+
+        match name {
+            "uppercase" => Object::String(string.to_uppercase()),
+            "lowercase" => Object::String(string.to_lowercase()),
+            "trim" => Object::String(string.trim().to_owned()),
+            "reversed" => Object::String(string.chars().rev().collect()),
+            "isEmpty" => Object::Boolean(string.is_empty()),
+            "isNotEmpty" => Object::Boolean(!string.is_empty()),
+            "isBlank" => Object::Boolean(string.trim().is_empty()),
+            "startsWith" => Object::Boolean(string.starts_with(string_arg(0))),
+            "endsWith" => Object::Boolean(string.ends_with(string_arg(0))),
+            "contains" => Object::Boolean(string.contains(string_arg(0))),
+            "indexOf" => {
+                let index = string.find(string_arg(0));
+                Object::Int(index.map_or(-1, |byte| string[..byte].chars().count() as i32))
+            }
+            "substring" => {
+                let chars: Vec<_> = string.chars().collect();
+                let length = chars.len() as i32;
+                let begin = int_arg(0);
+                let end = if args.len() == 2 { int_arg(1) } else { length };
+                if begin < 0 || end > length || begin > end {
+                    context.exception(format!(
+                        "StringIndexOutOfBoundsException: begin {begin}, end {end}, length {length}"
+                    ));
+                }
+                Object::String(chars[begin as usize..end as usize].iter().collect())
+            }
+            "repeat" => match int_arg(0) {
+                count if count < 0 => context.exception(format!(
+                    "IllegalArgumentException: Count 'n' must be non-negative, but was {count}."
+                )),
+                count => Object::String(string.repeat(count as usize)),
+            },
+            "split" => {
+                let parts = string.split(string_arg(0));
+                let parts = parts.map(|part| Object::String(part.to_owned())).collect();
+                Object::array(parts, ArrayKind::Other)
+            }
+            "toInt" => match string.parse() {
+                Ok(int) => Object::Int(int),
+                Err(_) => context.exception(format!(
+                    "NumberFormatException: For input string: \"{string}\""
+                )),
+            },
+            "toIntOrNull" => string.parse().map_or(Object::Null, Object::Int),
+            "toDouble" => match string.trim().parse() {
+                Ok(double) => Object::Double(double),
+                Err(_) => context.exception(format!(
+                    "NumberFormatException: For input string: \"{string}\""
+                )),
+            },
+            _ => unreachable!(),
+        }
+    }
+
+    fn range_method(lo: Object, hi: Object, name: &str, args: Vec<Object>, c: &Context) -> Object {
+        let sum = |items: &mut dyn Iterator<Item = Object>, zero| {
+            items.fold(zero, |sum, item| arithmetic(Operator::Add, &sum, &item, c))
+        };
+
+        match (name, lo, hi) {
+            ("isEmpty", lo, hi) => Object::Boolean(lo.compare(&hi) == Some(Ordering::Greater)),
+            ("contains", lo, hi) => {
+                Object::Boolean(Object::Range(Box::new(lo), Box::new(hi)).contains(&args[0]))
+            }
+            ("sum", Object::Int(lo), Object::Int(hi)) => {
+                sum(&mut (lo..=hi).map(Object::Int), Object::Int(0))
+            }
+            ("sum", Object::Long(lo), Object::Long(hi)) => {
+                sum(&mut (lo..=hi).map(Object::Long), Object::Long(0))
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    fn array_method(
+        array: &RefCell<Vec<Object>>,
+        kind: ArrayKind,
+        name: &str,
+        args: Vec<Object>,
+        context: &Context,
+    ) -> Object {
+        let items = array.borrow();
+        let extremum = |ordering: Ordering| {
+            let item = items.iter().reduce(|extremum, item| {
+                match item.compare(extremum) == Some(ordering) {
+                    true => item,
+                    false => extremum,
+                }
+            });
+            item.cloned().unwrap_or_else(|| {
+                context.exception("NoSuchElementException: Array is empty.".to_owned())
+            })
+        };
+
+        match name {
+            "sum" => items.iter().fold(kind.zero(), |sum, item| {
+                arithmetic(Operator::Add, &sum, item, context)
+            }),
+            "min" => extremum(Ordering::Less),
+            "max" => extremum(Ordering::Greater),
+            "sorted" => {
+                let mut sorted = items.clone();
+                sorted.sort_by(|l, r| l.compare(r).unwrap_or(Ordering::Equal));
+                Object::array(sorted, kind)
+            }
+            "reversed" => Object::array(items.iter().rev().cloned().collect(), kind),
+            "copyOf" => Object::array(items.clone(), kind),
+            "indexOf" => {
+                let index = items.iter().position(|item| *item == args[0]);
+                Object::Int(index.map_or(-1, |index| index as i32))
+            }
+            "contains" => Object::Boolean(items.contains(&args[0])),
+            "joinToString" => {
+                let separator = match args.first() {
+                    Some(Object::String(separator)) => separator.as_str(),
+                    _ => ", ",
+                };
+                let items: Vec<_> = items
+                    .iter()
+                    .map(|item| context.stringify(item.clone()))
+                    .collect();
+                Object::String(items.join(separator))
+            }
+            "fill" => {
+                drop(items);
+                array.borrow_mut().fill(args[0].clone());
+                Object::Unit
+            }
+            _ => unreachable!(),
+        }
     }
 
     // "readln" => Some(ExprType::Primitive(Primitive::String)),
@@ -153,6 +770,13 @@ mod funcs {
         Object::String(read_string(context))
     }
 
+    pub fn readLine(_: Vec<Object>, _: &Context) -> Object {
+        match std::io::stdin().lines().next() {
+            Some(Ok(string)) => Object::String(string),
+            _ => Object::Null,
+        }
+    }
+
     pub fn readlnInt(_: Vec<Object>, context: &Context) -> Object {
         if let Ok(int) = read_string(context).parse() {
             Object::Int(int)
@@ -181,6 +805,7 @@ mod tests {
         fn interpret(source: &str) {
             let pairs: Vec<_> = Lexer::new(source).collect();
             let (prog, _) = Program::try_parse(&pairs).unwrap();
+            assert!(prog.syntax_errors().is_empty());
             Context::new(prog).run()
         }
 
@@ -188,5 +813,651 @@ mod tests {
         interpret(include_str!("../samples/hello.kt"));
         interpret(include_str!("../samples/factorial.kt"));
         interpret(include_str!("../samples/sort.kt"));
+        interpret(include_str!("../samples/loops.kt"));
+        interpret(include_str!("../samples/when.kt"));
+        interpret(include_str!("../samples/numbers.kt"));
+        interpret(include_str!("../samples/nullable.kt"));
+        interpret(include_str!("../samples/classes.kt"));
+        interpret(include_str!("../samples/data.kt"));
+        interpret(include_str!("../samples/enums.kt"));
+        interpret(include_str!("../samples/sealed.kt"));
+        interpret(include_str!("../samples/inheritance.kt"));
+        interpret(include_str!("../samples/lambdas.kt"));
+        interpret(include_str!("../samples/collections.kt"));
+        interpret(include_str!("../samples/strings.kt"));
+        interpret(include_str!("../samples/matrix.kt"));
+    }
+
+    fn call(source: &str, name: &str, args: Vec<Object>) -> Object {
+        let pairs: Vec<_> = Lexer::new(source).collect();
+        let (prog, _) = Program::try_parse(&pairs).unwrap();
+        assert!(prog.syntax_errors().is_empty());
+        Context::new(prog).call_function(name, args)
+    }
+
+    fn call_checked(source: &str, name: &str, args: Vec<Object>) -> Object {
+        let pairs: Vec<_> = Lexer::new(source).collect();
+        let (prog, _) = Program::try_parse(&pairs).unwrap();
+        Context::new(prog)
+            .with_checked_arithmetic(true)
+            .call_function(name, args)
+    }
+
+    #[test]
+    fn nested_return() {
+        let source = r#"
+        fun indexOf(arr: Array<Int>, item: Int): Int {
+            var index = 0
+            for (el in arr) {
+                while (true) {
+                    if (el == item) {
+                        return index
+                    }
+                    break
+                }
+                index = index + 1
+            }
+            return -1
+        }
+
+        fun find(): Int {
+            return indexOf(arrayOf(4, 8, 15, 16), 15)
+        }
+
+        fun missing(): Int {
+            return indexOf(arrayOf(4, 8), 15)
+        }
+        "#;
+
+        assert_eq!(call(source, "find", vec![]), Object::Int(2));
+        assert_eq!(call(source, "missing", vec![]), Object::Int(-1));
+    }
+
+    #[test]
+    fn if_value() {
+        let source = r#"
+        fun max(a: Int, b: Int): Int {
+            val max = if (a > b) a else b
+            return max
+        }
+
+        fun clamp(a: Int): Int {
+            return if (a < 0) {
+                0
+            } else if (a > 10) {
+                val max = 10
+                max
+            } else {
+                a
+            }
+        }
+        "#;
+
+        assert_eq!(
+            call(source, "max", vec![Object::Int(3), Object::Int(7)]),
+            Object::Int(7)
+        );
+        assert_eq!(call(source, "clamp", vec![Object::Int(-3)]), Object::Int(0));
+        assert_eq!(
+            call(source, "clamp", vec![Object::Int(42)]),
+            Object::Int(10)
+        );
+        assert_eq!(call(source, "clamp", vec![Object::Int(5)]), Object::Int(5));
+    }
+
+    #[test]
+    fn char_arithmetic() {
+        let source = r#"
+        fun shift(c: Char, n: Int): Char {
+            return c + n - 1
+        }
+
+        fun digit(c: Char): Int {
+            return c - '0'
+        }
+
+        fun code(c: Char): Int {
+            return c.code
+        }
+        "#;
+
+        assert_eq!(
+            call(source, "shift", vec![Object::Char('a'), Object::Int(3)]),
+            Object::Char('c')
+        );
+        assert_eq!(
+            call(source, "digit", vec![Object::Char('7')]),
+            Object::Int(7)
+        );
+        assert_eq!(
+            call(source, "code", vec![Object::Char('A')]),
+            Object::Int(65)
+        );
+    }
+
+    #[test]
+    fn numbers() {
+        let source = r#"
+        fun mean(a: Int, b: Long): Double {
+            return (a + b) / 2.0
+        }
+
+        fun scale(a: Float, b: Int): Float {
+            return a * b
+        }
+
+        fun wrap(a: Long): Long {
+            return a * 2
+        }
+        "#;
+
+        assert_eq!(
+            call(source, "mean", vec![Object::Int(1), Object::Long(2)]),
+            Object::Double(1.5)
+        );
+        assert_eq!(
+            call(source, "scale", vec![Object::Float(0.5), Object::Int(3)]),
+            Object::Float(1.5)
+        );
+        assert_eq!(
+            call(source, "wrap", vec![Object::Long(i64::MAX)]),
+            Object::Long(-2)
+        );
+    }
+
+    const FACTORIAL: &str = r#"
+        fun factorial(n: Int): Int {
+            var result = 1
+            for (i in 1..n) {
+                result = result * i
+            }
+            return result
+        }
+        "#;
+
+    #[test]
+    fn wrapping_overflow() {
+        assert_eq!(
+            call(FACTORIAL, "factorial", vec![Object::Int(13)]),
+            Object::Int(1932053504)
+        );
+    }
+
+    #[test]
+    fn checked_arithmetic() {
+        assert_eq!(
+            call_checked(FACTORIAL, "factorial", vec![Object::Int(12)]),
+            Object::Int(479001600)
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Integer overflow in multiplication")]
+    fn checked_overflow() {
+        call_checked(FACTORIAL, "factorial", vec![Object::Int(13)]);
+    }
+
+    const NULLABLE: &str = r#"
+        fun length(word: String?): Int {
+            return word?.length ?: -1
+        }
+
+        fun forceLength(word: String?): Int {
+            return word!!.length
+        }
+        "#;
+
+    #[test]
+    fn null_safety() {
+        assert_eq!(
+            call(NULLABLE, "length", vec![Object::String("four".into())]),
+            Object::Int(4)
+        );
+        assert_eq!(
+            call(NULLABLE, "length", vec![Object::Null]),
+            Object::Int(-1)
+        );
+        assert_eq!(
+            call(NULLABLE, "forceLength", vec![Object::String("two".into())]),
+            Object::Int(3)
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "NullPointerException")]
+    fn not_null_assertion() {
+        call(NULLABLE, "forceLength", vec![Object::Null]);
+    }
+
+    #[test]
+    fn classes() {
+        let source = r#"
+        class Counter(var count: Int) {
+            val start = count
+
+            fun inc(): Int {
+                count = count + 1
+                return count - start
+            }
+        }
+
+        fun bump(counter: Counter) {
+            counter.inc()
+        }
+
+        fun shared(): Int {
+            val counter = Counter(10)
+            bump(counter)
+            bump(counter)
+            return counter.inc()
+        }
+
+        fun shadowed(): Int {
+            val count = 100
+            val counter = Counter(0)
+            return counter.inc()
+        }
+        "#;
+
+        assert_eq!(call(source, "shared", vec![]), Object::Int(3));
+        assert_eq!(call(source, "shadowed", vec![]), Object::Int(1));
+    }
+
+    #[test]
+    fn data_classes() {
+        let source = r#"
+        data class User(val name: String, var age: Int)
+
+        fun hash(): Int {
+            val user = User("a", 1)
+            return user.hashCode()
+        }
+
+        fun copy(): Boolean {
+            val user = User("a", 1)
+            val copy = user.copy()
+            copy.age = 2
+            return user.age == 1 && copy == User("a", 2)
+        }
+
+        fun destructure(): String {
+            val user = User("a", 1)
+            val (name, age) = user.copy(age = 5)
+            return name + age
+        }
+        "#;
+
+        assert_eq!(call(source, "hash", vec![]), Object::Int(97 * 31 + 1));
+        assert_eq!(call(source, "copy", vec![]), Object::Boolean(true));
+        assert_eq!(
+            call(source, "destructure", vec![]),
+            Object::String("a5".into())
+        );
+    }
+
+    const ENUMS: &str = r#"
+        enum class Level(val weight: Int) {
+            LOW(1), HIGH(10);
+
+            fun next(): Level {
+                return when (this) {
+                    LOW -> HIGH
+                    HIGH -> LOW
+                }
+            }
+        }
+
+        sealed class Node(val size: Int)
+
+        class Leaf(val value: Int) : Node(1)
+
+        class Pair(val left: Node, val right: Node) : Node(left.size + right.size)
+
+        fun same(): Boolean {
+            val low = Level.LOW
+            return low.next() == Level.HIGH && Level.valueOf("LOW") == low
+        }
+
+        fun weights(): Int {
+            var sum = 0
+            for (level in Level.values()) {
+                sum = sum + level.weight * level.ordinal
+            }
+            return sum
+        }
+
+        fun count(node: Node): Int {
+            return when (node) {
+                is Leaf -> node.value
+                is Pair -> count(node.left) + count(node.right)
+            }
+        }
+
+        fun tree(): String {
+            val tree = Pair(Leaf(2), Pair(Leaf(3), Leaf(4)))
+            return "${tree.size}${count(tree)}"
+        }
+
+        fun parse(name: String): Level {
+            return Level.valueOf(name)
+        }
+    "#;
+
+    #[test]
+    fn enums() {
+        assert_eq!(call(ENUMS, "same", vec![]), Object::Boolean(true));
+        assert_eq!(call(ENUMS, "weights", vec![]), Object::Int(10));
+        assert_eq!(call(ENUMS, "tree", vec![]), Object::String("39".into()));
+    }
+
+    #[test]
+    #[should_panic(expected = "IllegalArgumentException: No enum constant Level.MEDIUM")]
+    fn enum_value_of() {
+        call(ENUMS, "parse", vec![Object::String("MEDIUM".into())]);
+    }
+
+    #[test]
+    fn virtual_dispatch() {
+        let source = r#"
+        interface Greeter {
+            fun name(): String
+
+            fun greet(): String {
+                return "hi " + name()
+            }
+        }
+
+        open class Base : Greeter {
+            override fun name(): String {
+                return "base"
+            }
+
+            override fun greet(): String {
+                return "hello " + name()
+            }
+        }
+
+        class Derived : Base() {
+            override fun name(): String {
+                return "derived"
+            }
+
+            override fun toString(): String {
+                return "Derived!"
+            }
+        }
+
+        fun greet(): String {
+            val greeter: Greeter = Derived()
+            return greeter.greet()
+        }
+
+        fun show(): String {
+            val derived = Derived()
+            return "$derived"
+        }
+        "#;
+
+        assert_eq!(
+            call(source, "greet", vec![]),
+            Object::String("hello derived".into())
+        );
+        assert_eq!(
+            call(source, "show", vec![]),
+            Object::String("Derived!".into())
+        );
+    }
+
+    #[test]
+    fn nested_to_string() {
+        let source = r#"
+        class Name(val value: String) {
+            override fun toString(): String {
+                return "<" + value + ">"
+            }
+        }
+
+        data class User(val name: Name, val tags: Array<Name>)
+
+        fun show(): String {
+            val user = User(Name("ann"), arrayOf(Name("a"), Name("b")))
+            return "$user " + user.toString()
+        }
+        "#;
+
+        assert_eq!(
+            call_checked(source, "show", vec![]),
+            Object::String(
+                "User(name=<ann>, tags=[<a>, <b>]) User(name=<ann>, tags=[<a>, <b>])".into()
+            )
+        );
+    }
+
+    #[test]
+    fn closures() {
+        let source = r#"
+        class Counter(val start: Int) {
+            var count = start
+
+            fun incrementer(): () -> Int {
+                return {
+                    count = count + 1
+                    count
+                }
+            }
+        }
+
+        fun makeAdder(n: Int): (Int) -> Int {
+            return { it + n }
+        }
+
+        fun captured(): String {
+            val add = makeAdder(10)
+            var total = 0
+            val accumulate = { x: Int -> total = total + x }
+            accumulate(add(1))
+            accumulate(add(2))
+            return "$total"
+        }
+
+        fun perIteration(): String {
+            val fs = arrayOf({ -> 0 }, { -> 0 }, { -> 0 })
+            var result = ""
+            for (i in 0..2) {
+                fs[i] = { -> i * i }
+            }
+            for (f in fs) {
+                result = result + f()
+            }
+            return result
+        }
+
+        fun method(): Int {
+            val counter = Counter(5)
+            val next = counter.incrementer()
+            next()
+            next()
+            return counter.count
+        }
+        "#;
+
+        assert_eq!(
+            call(source, "captured", vec![]),
+            Object::String("23".into())
+        );
+        assert_eq!(
+            call(source, "perIteration", vec![]),
+            Object::String("014".into())
+        );
+        assert_eq!(call(source, "method", vec![]), Object::Int(7));
+    }
+
+    #[test]
+    fn deep_recursion() {
+        let source = r#"
+        fun rec(n: Int): Int {
+            return if (n == 0) 0 else rec(n - 1) + 1
+        }
+        "#;
+
+        // The default stack size of the main thread
+        let thread = std::thread::Builder::new()
+            .stack_size(8 << 20)
+            .spawn(move || {
+                assert_eq!(
+                    call(source, "rec", vec![Object::Int(1000)]),
+                    Object::Int(1000)
+                )
+            })
+            .unwrap();
+        thread.join().unwrap();
+    }
+
+    #[test]
+    fn invoke_any_expression() {
+        let source = r#"
+        fun makeAdder(n: Int): (Int) -> Int {
+            return { x: Int -> x + n }
+        }
+
+        fun invoke(flag: Boolean): String {
+            val fs = arrayOf({ x: Int -> x + 1 })
+            val length = when {
+                flag -> "x"
+                else -> "yz"
+            }.length
+            return "${fs[0](1)} ${makeAdder(1)(2)} $length"
+        }
+        "#;
+
+        assert_eq!(
+            call(source, "invoke", vec![Object::Boolean(false)]),
+            Object::String("2 3 2".into())
+        );
+    }
+
+    #[test]
+    fn array_builtins() {
+        let source = r#"
+        fun stats(): String {
+            val numbers = arrayOf(4, -2, 7)
+            return "${numbers.sum()} ${numbers.min()} ${numbers.max()} ${numbers.indexOf(7)}"
+        }
+
+        fun sorted(): String {
+            val numbers = arrayOf(3, 1, 2)
+            val sorted = numbers.sorted()
+            return numbers.joinToString() + " / " + sorted.joinToString("-")
+        }
+
+        fun filled(): String {
+            val array = Array(3) { it + 1 }
+            array.fill(7)
+            val doubles = DoubleArray(2)
+            return array.joinToString("") + doubles.joinToString()
+        }
+        "#;
+
+        assert_eq!(
+            call(source, "stats", vec![]),
+            Object::String("9 -2 7 2".into())
+        );
+        assert_eq!(
+            call(source, "sorted", vec![]),
+            Object::String("3, 1, 2 / 1-2-3".into())
+        );
+        assert_eq!(
+            call(source, "filled", vec![]),
+            Object::String("7770.0, 0.0".into())
+        );
+    }
+
+    #[test]
+    fn index_any_expression() {
+        let source = r#"
+        fun grid(): Array<Array<Int>> {
+            val m = Array(3) { i -> Array(3) { j -> i * 3 + j } }
+            m[2][1] = m[1][2] * 10
+            return m
+        }
+
+        fun corner(): Int {
+            return grid()[2][1] + grid()[0].size
+        }
+
+        fun word(): Char {
+            return "ab cd".split(" ")[1][0]
+        }
+        "#;
+
+        assert_eq!(call(source, "corner", vec![]), Object::Int(53));
+        assert_eq!(call(source, "word", vec![]), Object::Char('c'));
+    }
+
+    #[test]
+    fn array_sum_of_empty() {
+        let source = r#"
+        fun doubles(): Boolean {
+            return DoubleArray(0).sum() == 0.0
+        }
+
+        fun longs(): Boolean {
+            return LongArray(0).sum() == 0L && LongArray(2).reversed().sum() == 0L
+        }
+
+        fun doubleSum(): Double {
+            return DoubleArray(0).sum()
+        }
+        "#;
+
+        assert_eq!(call(source, "doubles", vec![]), Object::Boolean(true));
+        assert_eq!(call(source, "longs", vec![]), Object::Boolean(true));
+        assert_eq!(call(source, "doubleSum", vec![]).to_string(), "0.0");
+    }
+
+    #[test]
+    #[should_panic(expected = "NoSuchElementException: Array is empty.")]
+    fn array_max_of_empty() {
+        let source = r#"
+        fun emptyMax(): Int {
+            val empty = IntArray(0)
+            return empty.max()
+        }
+        "#;
+
+        call(source, "emptyMax", vec![]);
+    }
+
+    #[test]
+    fn builtin_members() {
+        let source = r#"
+        fun strings(): String {
+            val parts = " a-b-c ".trim().split("-")
+            return parts.reversed().joinToString("") + "xyz".substring(1).uppercase()
+        }
+
+        fun ranges(): Int {
+            val range = 1..4
+            return range.sum() * range.last + (4..1).sum()
+        }
+        "#;
+
+        assert_eq!(
+            call(source, "strings", vec![]),
+            Object::String("cbaYZ".into())
+        );
+        assert_eq!(call(source, "ranges", vec![]), Object::Int(40));
+    }
+
+    #[test]
+    #[should_panic(expected = "NumberFormatException: For input string: \"4x\"")]
+    fn string_to_int() {
+        let source = r#"
+        fun parse(): Int {
+            return "4x".toInt()
+        }
+        "#;
+
+        call(source, "parse", vec![]);
     }
 }
@@ -1,24 +1,383 @@
-use std::{cell::RefCell, rc::Rc};
+use std::{cell::RefCell, cmp::Ordering, fmt::Display, rc::Rc};
+
+use super::Scope;
+use crate::parser::Body;
 
 #[derive(Debug, Clone)]
 pub enum Object {
     Unit,
+    Null,
     Int(i32),
+    Long(i64),
+    Float(f32),
+    Double(f64),
     String(String),
     Boolean(bool),
     Char(char),
-    Array(Rc<RefCell<Vec<Object>>>),
+    Array(Rc<RefCell<Vec<Object>>>, ArrayKind),
     Range(Box<Object>, Box<Object>),
+    Instance(Rc<Instance>),
+    Function(Rc<Closure>),
+}
+
+/// Element type of an array, an empty array of numbers sums to the zero of its type
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ArrayKind {
+    Int,
+    Long,
+    Float,
+    Double,
+    Other,
+}
+
+impl ArrayKind {
+    pub fn of(item: Option<&Object>) -> Self {
+        match item {
+            Some(Object::Int(_)) => ArrayKind::Int,
+            Some(Object::Long(_)) => ArrayKind::Long,
+            Some(Object::Float(_)) => ArrayKind::Float,
+            Some(Object::Double(_)) => ArrayKind::Double,
+            _ => ArrayKind::Other,
+        }
+    }
+
+    pub fn zero(self) -> Object {
+        match self {
+            ArrayKind::Int | ArrayKind::Other => Object::Int(0),
+            ArrayKind::Long => Object::Long(0),
+            ArrayKind::Float => Object::Float(0.0),
+            ArrayKind::Double => Object::Double(0.0),
+        }
+    }
+}
+
+pub struct Closure {
+    pub params: Vec<String>,
+    pub body: Rc<Body>,
+    pub scopes: Vec<Scope>,
+}
+
+// Captured scopes may contain the closure itself
+impl std::fmt::Debug for Closure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Closure")
+            .field("params", &self.params)
+            .finish_non_exhaustive()
+    }
+}
+
+#[derive(Debug)]
+pub struct Instance {
+    pub class: String,
+    properties: RefCell<Vec<(String, Object)>>,
+    pub kind: InstanceKind,
+}
+
+#[derive(Debug)]
+pub enum InstanceKind {
+    Object,
+    /// Number of primary constructor properties of a data class,
+    /// they come first and define equality, hash code and string representation
+    Data {
+        components: usize,
+    },
+    Entry {
+        name: String,
+        ordinal: usize,
+    },
+}
+
+impl Instance {
+    pub fn new(class: &str, kind: InstanceKind) -> Self {
+        Self {
+            class: class.to_owned(),
+            properties: Default::default(),
+            kind,
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Object {
+        self.try_get(name).expect("property exists")
+    }
+
+    pub fn try_get(&self, name: &str) -> Option<Object> {
+        if let InstanceKind::Entry {
+            name: entry,
+            ordinal,
+        } = &self.kind
+        {
+            match name {
+                "name" => return Some(Object::String(entry.clone())),
+                "ordinal" => return Some(Object::Int(*ordinal as i32)),
+                _ => {}
+            }
+        }
+
+        let properties = self.properties.borrow();
+        let (_, obj) = properties.iter().find(|(property, _)| property == name)?;
+        Some(obj.clone())
+    }
+
+    pub fn set(&self, name: &str, obj: Object) {
+        let mut properties = self.properties.borrow_mut();
+        match properties.iter_mut().find(|(property, _)| property == name) {
+            Some((_, value)) => *value = obj,
+            None => properties.push((name.to_owned(), obj)),
+        }
+    }
+
+    pub fn component(&self, index: usize) -> Object {
+        self.properties.borrow()[index].1.clone()
+    }
+
+    fn components(&self) -> Option<Vec<Object>> {
+        let properties = self.properties.borrow();
+        let InstanceKind::Data { components } = self.kind else {
+            return None;
+        };
+        let components = properties[..components].iter();
+        Some(components.map(|(_, obj)| obj.clone()).collect())
+    }
+}
+
+impl Object {
+    pub fn array(items: Vec<Object>, kind: ArrayKind) -> Self {
+        Object::Array(Rc::new(RefCell::new(items)), kind)
+    }
+
+    /// Formats arrays and data class instances with their items and components
+    /// converted to strings by `nested`
+    pub fn display_with(&self, nested: impl Fn(Object) -> String) -> String {
+        match self {
+            Object::Array(array, _) => {
+                // Items are copied out, as a `toString` may modify the array
+                let items = array.borrow().clone();
+                let items: Vec<_> = items.into_iter().map(nested).collect();
+                format!("[{}]", items.join(", "))
+            }
+            Object::Instance(instance) => {
+                let Some(components) = instance.components() else {
+                    return self.to_string();
+                };
+                let names: Vec<_> = instance.properties.borrow()[..components.len()]
+                    .iter()
+                    .map(|(name, _)| name.clone())
+                    .collect();
+                let components: Vec<_> = names
+                    .into_iter()
+                    .zip(components)
+                    .map(|(name, obj)| format!("{name}={}", nested(obj)))
+                    .collect();
+                format!("{}({})", instance.class, components.join(", "))
+            }
+            _ => self.to_string(),
+        }
+    }
+
+    pub fn contains(&self, item: &Object) -> bool {
+        use Object::*;
+        match (self, item) {
+            (Array(array, _), item) => array.borrow().contains(item),
+            (Range(lo, hi), item) => {
+                let after_lo = lo.compare(item).is_some_and(Ordering::is_le);
+                let before_hi = item.compare(hi).is_some_and(Ordering::is_le);
+                after_lo && before_hi
+            }
+            (String(string), Char(c)) => string.contains(*c),
+            (String(string), String(substring)) => string.contains(substring.as_str()),
+            _ => false,
+        }
+    }
+
+    pub fn compare(&self, other: &Object) -> Option<Ordering> {
+        use Object::*;
+        match (self, other) {
+            (String(l), String(r)) => Some(l.cmp(r)),
+            (Boolean(l), Boolean(r)) => Some(l.cmp(r)),
+            (Char(l), Char(r)) => Some(l.cmp(r)),
+            (l, r) => match Numbers::promote(l, r)? {
+                Numbers::Int(l, r) => Some(l.cmp(&r)),
+                Numbers::Long(l, r) => Some(l.cmp(&r)),
+                Numbers::Float(l, r) => l.partial_cmp(&r),
+                Numbers::Double(l, r) => l.partial_cmp(&r),
+            },
+        }
+    }
+
+    pub fn hash_code(&self) -> i32 {
+        use Object::*;
+        match self {
+            Unit | Null => 0,
+            Int(int) => *int,
+            Long(long) => (long ^ (long >> 32)) as i32,
+            Float(float) => float.to_bits() as i32,
+            Double(double) => Long(double.to_bits() as i64).hash_code(),
+            String(string) => string.encode_utf16().fold(0, |hash: i32, c| {
+                hash.wrapping_mul(31).wrapping_add(c as i32)
+            }),
+            Boolean(true) => 1231,
+            Boolean(false) => 1237,
+            Char(char) => *char as i32,
+            Array(array, _) => Rc::as_ptr(array) as usize as i32,
+            Range(lo, hi) => lo.hash_code().wrapping_mul(31).wrapping_add(hi.hash_code()),
+            Instance(instance) => match instance.components() {
+                Some(components) => components.iter().fold(0, |hash: i32, obj| {
+                    hash.wrapping_mul(31).wrapping_add(obj.hash_code())
+                }),
+                None => Rc::as_ptr(instance) as usize as i32,
+            },
+            Function(closure) => Rc::as_ptr(closure) as usize as i32,
+        }
+    }
+
+    fn as_i64(&self) -> Option<i64> {
+        match *self {
+            Object::Int(int) => Some(int as i64),
+            Object::Long(long) => Some(long),
+            _ => None,
+        }
+    }
+
+    fn as_f32(&self) -> Option<f32> {
+        match *self {
+            Object::Float(float) => Some(float),
+            _ => self.as_i64().map(|int| int as f32),
+        }
+    }
+
+    fn as_f64(&self) -> Option<f64> {
+        match *self {
+            Object::Double(double) => Some(double),
+            Object::Float(float) => Some(float as f64),
+            _ => self.as_i64().map(|int| int as f64),
+        }
+    }
 }
 
-impl ToString for Object {
-    fn to_string(&self) -> String {
+pub enum Numbers {
+    Int(i32, i32),
+    Long(i64, i64),
+    Float(f32, f32),
+    Double(f64, f64),
+}
+
+impl Numbers {
+    pub fn promote(l: &Object, r: &Object) -> Option<Self> {
+        use Object::*;
+        let numbers = match (l, r) {
+            (Int(l), Int(r)) => Numbers::Int(*l, *r),
+            (Double(_), _) | (_, Double(_)) => Numbers::Double(l.as_f64()?, r.as_f64()?),
+            (Float(_), _) | (_, Float(_)) => Numbers::Float(l.as_f32()?, r.as_f32()?),
+            _ => Numbers::Long(l.as_i64()?, r.as_i64()?),
+        };
+        Some(numbers)
+    }
+}
+
+/// Kotlin `==` semantics: values are compared structurally, arrays and functions by reference
+impl PartialEq for Object {
+    fn eq(&self, other: &Self) -> bool {
+        use Object::*;
+        match (self, other) {
+            (Unit, Unit) => true,
+            (Null, Null) => true,
+            (Int(l), Int(r)) => l == r,
+            (Long(l), Long(r)) => l == r,
+            (Float(l), Float(r)) => l == r,
+            (Double(l), Double(r)) => l == r,
+            (String(l), String(r)) => l == r,
+            (Boolean(l), Boolean(r)) => l == r,
+            (Char(l), Char(r)) => l == r,
+            (Array(l, _), Array(r, _)) => Rc::ptr_eq(l, r),
+            (Function(l), Function(r)) => Rc::ptr_eq(l, r),
+            (Instance(l), Instance(r)) => {
+                Rc::ptr_eq(l, r)
+                    || l.class == r.class
+                        && l.components().is_some_and(|c| Some(c) == r.components())
+            }
+            (Range(l_lo, l_hi), Range(r_lo, r_hi)) => l_lo == r_lo && l_hi == r_hi,
+            _ => false,
+        }
+    }
+}
+
+impl Display for Object {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Object::Int(int) => int.to_string(),
-            Object::String(string) => string.to_owned(),
-            Object::Boolean(bool) => bool.to_string(),
-            Object::Char(char) => char.to_string(),
-            _ => unreachable!(),
+            Object::Unit => write!(f, "kotlin.Unit"),
+            Object::Null => write!(f, "null"),
+            Object::Int(int) => int.fmt(f),
+            Object::Long(long) => long.fmt(f),
+            Object::Float(float) => f.write_str(&format_float(*float, *float as f64)),
+            Object::Double(double) => f.write_str(&format_float(*double, *double)),
+            Object::String(string) => string.fmt(f),
+            Object::Boolean(bool) => bool.fmt(f),
+            Object::Char(char) => char.fmt(f),
+            Object::Array(..) => f.write_str(&self.display_with(|nested| nested.to_string())),
+            Object::Range(lo, hi) => write!(f, "{lo}..{hi}"),
+            Object::Instance(instance) => {
+                if let InstanceKind::Entry { name, .. } = &instance.kind {
+                    return f.write_str(name);
+                }
+                if let InstanceKind::Data { .. } = instance.kind {
+                    return f.write_str(&self.display_with(|nested| nested.to_string()));
+                }
+                let address = Rc::as_ptr(instance) as usize;
+                write!(f, "{}@{address:x}", instance.class)
+            }
+            Object::Function(closure) => {
+                let address = Rc::as_ptr(closure) as usize;
+                write!(f, "Function{}@{address:x}", closure.params.len())
+            }
         }
     }
 }
+
+/// Kotlin formatting of floating point numbers:
+/// plain notation for magnitudes in `[1e-3, 1e7)` and scientific otherwise
+fn format_float<F>(value: F, as_f64: f64) -> String
+where
+    F: Display + std::fmt::LowerExp,
+{
+    if as_f64.is_nan() {
+        return "NaN".to_string();
+    }
+    if as_f64.is_infinite() {
+        let sign = if as_f64 < 0.0 { "-" } else { "" };
+        return format!("{sign}Infinity");
+    }
+
+    if as_f64 == 0.0 || (1e-3..1e7).contains(&as_f64.abs()) {
+        let plain = value.to_string();
+        if plain.contains('.') {
+            plain
+        } else {
+            plain + ".0"
+        }
+    } else {
+        let scientific = format!("{value:e}");
+        let (mantissa, exponent) = scientific.split_once('e').expect("exponent exists");
+        if mantissa.contains('.') {
+            format!("{mantissa}E{exponent}")
+        } else {
+            format!("{mantissa}.0E{exponent}")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn float_format() {
+        assert_eq!(Object::Double(1.0).to_string(), "1.0");
+        assert_eq!(Object::Double(-0.0).to_string(), "-0.0");
+        assert_eq!(Object::Double(0.1 + 0.2).to_string(), "0.30000000000000004");
+        assert_eq!(Object::Double(1e7).to_string(), "1.0E7");
+        assert_eq!(Object::Double(1.5e-4).to_string(), "1.5E-4");
+        assert_eq!(Object::Double(f64::NEG_INFINITY).to_string(), "-Infinity");
+        assert_eq!(Object::Float(1.1).to_string(), "1.1");
+        assert_eq!(Object::Float(3e10).to_string(), "3.0E10");
+    }
+}
@@ -2,7 +2,7 @@ mod token;
 
 pub use token::Token;
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Span {
     pub lo: usize,
     pub hi: usize,
@@ -20,6 +20,10 @@ impl<'a> Pair<'a> {
         let Span { lo, hi } = self.span;
         &self.source[lo..hi]
     }
+
+    pub fn source(&self) -> &'a str {
+        self.source
+    }
 }
 
 pub struct Lexer<'a> {
@@ -34,6 +38,14 @@ impl<'a> Lexer<'a> {
             remaining: s,
         }
     }
+
+    /// Lexer over a part of the source, produced spans are still relative to the whole source
+    pub fn with_span(s: &'a str, span: Span) -> Lexer<'a> {
+        Lexer {
+            original: &s[..span.hi],
+            remaining: &s[span.lo..span.hi],
+        }
+    }
 }
 
 impl<'a> Iterator for Lexer<'a> {
@@ -106,14 +118,27 @@ mod tests {
     #[test]
     fn samples() {
         fn assert_expected(source: &str) {
-            Lexer::new(source)
-                .filter(|pair| pair.token == Token::Unexpected)
-                .any(|pair| panic!("unexpected: {}", pair.str()));
+            if let Some(pair) = Lexer::new(source).find(|pair| pair.token == Token::Unexpected) {
+                panic!("unexpected: {}", pair.str())
+            }
         }
 
         assert_expected(include_str!("../samples/hello.kt"));
         assert_expected(include_str!("../samples/arrays.kt"));
         assert_expected(include_str!("../samples/factorial.kt"));
         assert_expected(include_str!("../samples/sort.kt"));
+        assert_expected(include_str!("../samples/loops.kt"));
+        assert_expected(include_str!("../samples/when.kt"));
+        assert_expected(include_str!("../samples/numbers.kt"));
+        assert_expected(include_str!("../samples/nullable.kt"));
+        assert_expected(include_str!("../samples/classes.kt"));
+        assert_expected(include_str!("../samples/data.kt"));
+        assert_expected(include_str!("../samples/enums.kt"));
+        assert_expected(include_str!("../samples/sealed.kt"));
+        assert_expected(include_str!("../samples/inheritance.kt"));
+        assert_expected(include_str!("../samples/lambdas.kt"));
+        assert_expected(include_str!("../samples/collections.kt"));
+        assert_expected(include_str!("../samples/strings.kt"));
+        assert_expected(include_str!("../samples/matrix.kt"));
     }
 }
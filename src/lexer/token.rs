@@ -1,4 +1,4 @@
-#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[derive(Debug, PartialEq, Clone, Copy)]
 pub enum Token {
     WhiteSpace,
     End,
@@ -6,10 +6,21 @@ pub enum Token {
     Char,
     Str,
     Int(i32),
+    Long(i64),
+    Float(f32),
+    Double(f64),
     Bool(bool),
     AndOp,
     OrOp,
+    EqOp,
+    NotEqOp,
+    LessEqOp,
+    MoreEqOp,
     RangeOp,
+    Arrow,
+    SafeCall,
+    Elvis,
+    NotNullOp,
     NewLine,
     Symbol(char),
 
@@ -20,10 +31,17 @@ pub enum Token {
     For,
     In,
     While,
+    When,
     Var,
     Val,
     Return,
     Break,
+    Continue,
+    Null,
+    Class,
+    Interface,
+    This,
+    Is,
 
     Unexpected,
 }
@@ -38,15 +56,18 @@ impl Token {
 
         let parsing_functions = [
             parse_comment,
+            parse_cmp_op,
+            parse_arrow,
+            parse_null_op,
+            parse_range_op,
             parse_symbol,
             parse_kw,
             parse_bool,
             parse_char,
             parse_ident,
-            parse_int,
+            parse_number,
             parse_new_line,
             parse_bool_op,
-            parse_range_op,
             parse_str,
             parse_white_space,
         ];
@@ -94,7 +115,7 @@ fn parse_comment(string: &str) -> Option<(Token, &str)> {
 fn parse_symbol(string: &str) -> Option<(Token, &str)> {
     let c = string.chars().next()?;
 
-    if "(){}[],:+-*/%<>=!".contains(c) {
+    if "(){}[],.:;?+-*/%<>=!".contains(c) {
         Some((Symbol(c), &string[1..]))
     } else {
         None
@@ -131,6 +152,27 @@ fn parse_bool_op(string: &str) -> Option<(Token, &str)> {
     }
 }
 
+fn parse_cmp_op(string: &str) -> Option<(Token, &str)> {
+    [
+        ("==", EqOp),
+        ("!=", NotEqOp),
+        ("<=", LessEqOp),
+        (">=", MoreEqOp),
+    ]
+    .into_iter()
+    .find_map(|(op, token)| string.strip_prefix(op).map(|string| (token, string)))
+}
+
+fn parse_null_op(string: &str) -> Option<(Token, &str)> {
+    [("?.", SafeCall), ("?:", Elvis), ("!!", NotNullOp)]
+        .into_iter()
+        .find_map(|(op, token)| string.strip_prefix(op).map(|string| (token, string)))
+}
+
+fn parse_arrow(string: &str) -> Option<(Token, &str)> {
+    string.strip_prefix("->").map(|string| (Arrow, string))
+}
+
 fn parse_range_op(string: &str) -> Option<(Token, &str)> {
     string.strip_prefix("..").map(|string| (RangeOp, string))
 }
@@ -149,10 +191,17 @@ fn parse_kw(string: &str) -> Option<(Token, &str)> {
         "for" => For,
         "in" => In,
         "while" => While,
+        "when" => When,
         "var" => Var,
         "val" => Val,
         "return" => Return,
         "break" => Break,
+        "continue" => Continue,
+        "null" => Null,
+        "class" => Class,
+        "interface" => Interface,
+        "this" => This,
+        "is" => Is,
         _ => return None,
     };
 
@@ -191,35 +240,53 @@ fn parse_str(string: &str) -> Option<(Token, &str)> {
     }
 
     let string = &string[1..];
+    let bytes = string.as_bytes();
 
-    let mut string_size = 0;
+    let mut index = 0;
     let mut escape = false;
+    // Nesting level of braces inside of a `${...}` template entry
+    let mut depth = 0;
 
     let mut fail = false;
-
-    for c in string.bytes() {
-        string_size += 1;
-        if c.is_ascii() {
-            if escape {
-                escape = false;
-                if !ESCAPE_SEQ_PARTS.contains(&c) {
-                    fail = true;
-                }
-            } else if c == b'\\' {
-                escape = true;
-            } else if c == b'"' {
-                break;
+    let mut closed = false;
+
+    while index < bytes.len() {
+        let c = bytes[index];
+        index += 1;
+        if depth > 0 {
+            match c {
+                b'{' => depth += 1,
+                b'}' => depth -= 1,
+                // Quotes inside of an embedded expression start a nested literal
+                b'"' => match parse_str(&string[index - 1..]) {
+                    Some((Str, rest)) => index = string.len() - rest.len(),
+                    _ => {
+                        fail = true;
+                        index = string.len();
+                    }
+                },
+                _ => {}
             }
+        } else if escape {
+            escape = false;
+            if !ESCAPE_SEQ_PARTS.contains(&c) {
+                fail = true;
+            }
+        } else if c == b'\\' {
+            escape = true;
+        } else if c == b'$' && bytes.get(index) == Some(&b'{') {
+            depth = 1;
+            index += 1;
+        } else if c == b'"' {
+            closed = true;
+            break;
         }
     }
 
-    if fail {
-        Some((Unexpected, &string[string_size..]))
+    if closed && !fail {
+        Some((Str, &string[index..]))
     } else {
-        match string.chars().nth(string_size - 1) {
-            Some(last) if last == '"' => Some((Str, &string[string_size..])),
-            _ => Some((Unexpected, &string[string_size..])),
-        }
+        Some((Unexpected, &string[index..]))
     }
 }
 
@@ -254,24 +321,43 @@ fn parse_char(string: &str) -> Option<(Token, &str)> {
     Some((Char, &string[string_size..]))
 }
 
-fn parse_int(string: &str) -> Option<(Token, &str)> {
-    let digit_count = string
-        .bytes()
-        .take_while(|b| b.is_ascii_hexdigit() || *b == b'x')
-        .count();
-
-    if digit_count == 0 {
+/// Integer literals become `Long` when they don't fit into `Int`, as in Kotlin
+fn parse_number(string: &str) -> Option<(Token, &str)> {
+    if !string.starts_with(|c: char| c.is_ascii_digit()) {
         return None;
     }
 
-    let (digits, string) = string.split_at(digit_count);
+    let radix = match string.get(..2) {
+        Some("0x" | "0X") => 16,
+        Some("0b" | "0B") => 2,
+        _ => 10,
+    };
 
-    let parse_res = if let Some(hex) = digits.strip_prefix("0x") {
-        i32::from_str_radix(hex, 16)
-    } else if let Some(binary) = digits.strip_prefix("0b") {
-        i32::from_str_radix(binary, 2)
+    let (digits, string) = if radix == 10 {
+        string.split_at(decimal_len(string))
     } else {
-        digits.parse()
+        let digit_count = string[2..]
+            .bytes()
+            .take_while(|b| b.is_ascii_hexdigit())
+            .count();
+        let (digits, string) = string.split_at(2 + digit_count);
+        (&digits[2..], string)
+    };
+
+    let (suffix, string) = match string.chars().next() {
+        Some(c @ ('L' | 'f' | 'F')) => (Some(c), &string[1..]),
+        _ => (None, string),
+    };
+
+    let is_integer = radix != 10 || !digits.contains(['.', 'e', 'E']);
+    let integer = || i64::from_str_radix(digits, radix).ok();
+
+    let token = match suffix {
+        Some('L') if is_integer => integer().map(Long),
+        Some('f' | 'F') if radix == 10 => digits.parse().ok().map(Float),
+        None if is_integer => integer().map(|n| i32::try_from(n).map_or(Long(n), Int)),
+        None => digits.parse().ok().map(Double),
+        _ => None,
     };
 
     let chars_after_num = string
@@ -279,19 +365,40 @@ fn parse_int(string: &str) -> Option<(Token, &str)> {
         .take_while(|c| c.is_ascii_alphanumeric())
         .count();
 
-    let token = if let Ok(number) = parse_res {
-        Int(number)
-    } else {
-        Unexpected
-    };
-
     if chars_after_num == 0 {
-        Some((token, string))
+        Some((token.unwrap_or(Unexpected), string))
     } else {
         Some((Unexpected, &string[chars_after_num..]))
     }
 }
 
+fn decimal_len(string: &str) -> usize {
+    let bytes = string.as_bytes();
+    let digits_from = |from: usize| {
+        bytes[from.min(bytes.len())..]
+            .iter()
+            .take_while(|b| b.is_ascii_digit())
+            .count()
+    };
+
+    let mut len = digits_from(0);
+
+    // `1..2` is a range, not a fraction
+    if bytes.get(len) == Some(&b'.') && digits_from(len + 1) != 0 {
+        len += 1 + digits_from(len + 1);
+    }
+
+    if let Some(b'e' | b'E') = bytes.get(len) {
+        let sign = matches!(bytes.get(len + 1), Some(b'+' | b'-')) as usize;
+        let exponent = digits_from(len + 1 + sign);
+        if exponent != 0 {
+            len += 1 + sign + exponent;
+        }
+    }
+
+    len
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -302,6 +409,7 @@ mod tests {
         assert_eq!(parse_str(r#""Hello, world"+=8"#), Some((Str, "+=8")));
         assert_eq!(parse_str(r#""Hello, world\n""#), Some((Str, "")));
         assert_eq!(parse_str(r#""\lol""#), Some((Unexpected, "")));
+        assert_eq!(parse_str(r#""${"}" + "a"}"+1"#), Some((Str, "+1")));
     }
 
     #[test]
@@ -311,11 +419,67 @@ mod tests {
         assert_eq!(parse_char("'\\l'"), None);
     }
 
+    #[test]
+    fn cmp_op() {
+        assert_eq!(parse_cmp_op("== b"), Some((EqOp, " b")));
+        assert_eq!(parse_cmp_op("!=b"), Some((NotEqOp, "b")));
+        assert_eq!(parse_cmp_op("<= b"), Some((LessEqOp, " b")));
+        assert_eq!(parse_cmp_op(">=b"), Some((MoreEqOp, "b")));
+        assert_eq!(parse_cmp_op("= b"), None);
+        assert_eq!(parse_cmp_op("< =b"), None);
+        assert_eq!(Token::parse("!(a)"), (Symbol('!'), "(a)"));
+    }
+
+    #[test]
+    fn null_op() {
+        assert_eq!(Token::parse("?.length"), (SafeCall, "length"));
+        assert_eq!(Token::parse("?: 0"), (Elvis, " 0"));
+        assert_eq!(Token::parse("!!.code"), (NotNullOp, ".code"));
+        assert_eq!(Token::parse("? = null"), (Symbol('?'), " = null"));
+        assert_eq!(Token::parse("null"), (Null, ""));
+    }
+
+    #[test]
+    fn class_kw() {
+        assert_eq!(Token::parse("class Point"), (Class, " Point"));
+        assert_eq!(Token::parse("this.x"), (This, ".x"));
+        assert_eq!(Token::parse("classes"), (Ident, ""));
+        assert_eq!(Token::parse("interface Shape"), (Interface, " Shape"));
+        assert_eq!(Token::parse("is Circle"), (Is, " Circle"));
+        assert_eq!(Token::parse("isEmpty"), (Ident, ""));
+    }
+
+    #[test]
+    fn dot() {
+        assert_eq!(Token::parse("..b"), (RangeOp, "b"));
+        assert_eq!(Token::parse(".code"), (Symbol('.'), "code"));
+    }
+
     #[test]
     fn int() {
-        assert_eq!(parse_int("42"), Some((Int(42), "")));
-        assert_eq!(parse_int("0xfd2"), Some((Int(0xfd2), "")));
-        assert_eq!(parse_int("0b10011"), Some((Int(0b10011), "")));
-        assert_eq!(parse_int("hello"), None);
+        assert_eq!(parse_number("42"), Some((Int(42), "")));
+        assert_eq!(parse_number("0xfd2"), Some((Int(0xfd2), "")));
+        assert_eq!(parse_number("0b10011"), Some((Int(0b10011), "")));
+        assert_eq!(parse_number("1..2"), Some((Int(1), "..2")));
+        assert_eq!(parse_number("hello"), None);
+    }
+
+    #[test]
+    fn long() {
+        assert_eq!(parse_number("123L"), Some((Long(123), "")));
+        assert_eq!(parse_number("0xffL"), Some((Long(0xff), "")));
+        assert_eq!(parse_number("3000000000"), Some((Long(3000000000), "")));
+        assert_eq!(parse_number("1.5L"), Some((Unexpected, "")));
+    }
+
+    #[test]
+    fn floating() {
+        assert_eq!(parse_number("1.5"), Some((Double(1.5), "")));
+        assert_eq!(parse_number("1e3"), Some((Double(1e3), "")));
+        assert_eq!(parse_number("2.5E-2)"), Some((Double(2.5e-2), ")")));
+        assert_eq!(parse_number("1.5f"), Some((Float(1.5), "")));
+        assert_eq!(parse_number("1F"), Some((Float(1.0), "")));
+        assert_eq!(parse_number("1.code"), Some((Int(1), ".code")));
+        assert_eq!(parse_number("1e"), Some((Unexpected, "")));
     }
 }
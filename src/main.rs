@@ -6,17 +6,37 @@ mod parser;
 use analyzer::{check_program, pretty_print_error};
 use interpreter::Context;
 use lexer::{Lexer, Span};
-use parser::{Program, TryParse};
+use parser::{Program, SyntaxError, TryParse};
+
+const STACK_SIZE: usize = 64 << 20;
 
 fn main() {
-    let args: Vec<_> = std::env::args().collect();
+    let mut checked_arithmetic = false;
+    let mut files = vec![];
 
-    match args.len() {
-        1 => println!("Pass path to kotlin file as argument"),
-        2 => {
-            let file_name = &args[1];
+    for arg in std::env::args().skip(1) {
+        match arg.as_str() {
+            "--checked" => checked_arithmetic = true,
+            flag if flag.starts_with("--") => {
+                println!("Unknown option {flag}");
+                return;
+            }
+            _ => files.push(arg),
+        }
+    }
+
+    match &files[..] {
+        [] => println!("Pass path to kotlin file as argument"),
+        [file_name] => {
             if let Ok(source) = std::fs::read_to_string(file_name) {
-                interpret(&source);
+                // Interpreted calls recurse on the native stack, so deep recursion needs a big one
+                let interpreter = std::thread::Builder::new()
+                    .stack_size(STACK_SIZE)
+                    .spawn(move || interpret(&source, checked_arithmetic))
+                    .expect("interpreter thread is spawned");
+                if interpreter.join().is_err() {
+                    std::process::exit(101);
+                }
             } else {
                 println!("File {file_name} not found")
             };
@@ -25,54 +45,54 @@ fn main() {
     };
 }
 
-fn interpret(source: &str) {
+fn interpret(source: &str, checked_arithmetic: bool) {
     let pairs: Vec<_> = Lexer::new(source).collect();
+    let (prog, _) = Program::try_parse(&pairs).expect("program parser recovers from errors");
 
-    match Program::try_parse(&pairs) {
-        Ok((prog, pairs)) if pairs.is_empty() => {
-            let errors = check_program(&prog);
+    let syntax_errors = prog.syntax_errors();
+    if !syntax_errors.is_empty() {
+        match syntax_errors.len() {
+            1 => eprintln!("Syntax Error:"),
+            num => eprintln!("Found {num} syntax errors:"),
+        };
+        for &error in &syntax_errors {
+            print_syntax_error(source, error)
+        }
+    }
 
-            if errors.is_empty() {
-                Context::new(prog).run();
-            } else {
-                match errors.len() {
-                    1 => eprintln!("Found error:"),
-                    num => eprintln!("Found {num} errors:"),
-                };
-                for (span, ref message) in check_program(&prog) {
-                    pretty_print_error(source, span, message)
-                }
-            }
+    // Declarations are recovered after syntax errors, so the rest is still checked
+    let errors = check_program(&prog);
+    if !errors.is_empty() {
+        match errors.len() {
+            1 => eprintln!("Found error:"),
+            num => eprintln!("Found {num} errors:"),
+        };
+        for (span, message) in &errors {
+            pretty_print_error(source, *span, message)
         }
-        Ok(_) => eprintln!("Source is not fully parsed"),
-        Err(error) => print_parse_error(source, error),
+    }
+
+    if syntax_errors.is_empty() && errors.is_empty() {
+        Context::new(prog)
+            .with_checked_arithmetic(checked_arithmetic)
+            .with_source(source)
+            .with_exit(|_| std::process::exit(0))
+            .run();
     }
 }
 
-fn print_parse_error(source: &str, error: parser::ParseError) {
-    eprintln!("Syntax Error:");
-    match error {
-        parser::ParseError::UnexpectedEndOfInput => pretty_print_error(
-            source,
-            Span {
-                lo: source.len(),
-                hi: source.len(),
-            },
-            "Unexpected end of input",
-        ),
-        parser::ParseError::NotImplementedYet => eprintln!("Use of not implemented feature"),
-        parser::ParseError::WrongExprType(pair, expected) => pretty_print_error(
-            source,
-            pair.span,
-            &format!("Wrong, expression type, expected: {expected}"),
-        ),
-        parser::ParseError::UnexpectedToken(pair, expected) => pretty_print_error(
-            source,
-            pair.span,
-            &format!(
-                "Wrong token, expected {expected:?} but got {unexpected:?}",
-                unexpected = pair.token
-            ),
-        ),
+fn print_syntax_error(source: &str, error: &SyntaxError) {
+    pretty_print_error(source, error.span, &error.message);
+
+    if let Some((span, ref label)) = error.note {
+        // Only the first line of the enclosing construct is underlined
+        let line_end = source[span.lo..]
+            .find('\n')
+            .map_or(source.len(), |i| span.lo + i);
+        let span = Span {
+            lo: span.lo,
+            hi: span.hi.min(line_end),
+        };
+        pretty_print_error(source, span, label)
     }
 }
@@ -1,9 +1,115 @@
 use super::math_expr::binary_operator;
 use super::*;
 
-expr_enum!(ComparisonExpr => And | Or | LessThan | MoreThan);
+binary_operator!(LessThan);
+binary_operator!(MoreThan);
+binary_operator!(LessOrEqual);
+binary_operator!(MoreOrEqual);
+binary_operator!(Equal);
+binary_operator!(NotEqual);
+binary_operator!(And);
+binary_operator!(Or);
 
-binary_operator!(LessThan => '<');
-binary_operator!(MoreThan => '>');
-binary_operator!(And => Token::AndOp);
-binary_operator!(Or => Token::OrOp);
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_helpers::*;
+
+    #[test]
+    fn equal() {
+        assert_eq!(
+            make::<Expr>("a == b"),
+            Expr::ComparisonExpr(ComparisonExpr::Equal(Equal {
+                left: make("a"),
+                right: make("b"),
+            }))
+        );
+        assert_eq!(
+            make::<Expr>("a != b"),
+            Expr::ComparisonExpr(ComparisonExpr::NotEqual(NotEqual {
+                left: make("a"),
+                right: make("b"),
+            }))
+        );
+    }
+
+    #[test]
+    fn equal_math() {
+        assert_eq!(
+            make::<Expr>("a + 1 == b * 2"),
+            Expr::ComparisonExpr(ComparisonExpr::Equal(Equal {
+                left: make("a + 1"),
+                right: make("b * 2"),
+            }))
+        );
+    }
+
+    #[test]
+    fn equal_and() {
+        assert_eq!(
+            make::<Expr>("a == b && c != d"),
+            Expr::ComparisonExpr(ComparisonExpr::And(And {
+                left: make("a == b"),
+                right: make("c != d"),
+            }))
+        );
+    }
+
+    #[test]
+    fn less_or_equal() {
+        assert_eq!(
+            make::<Expr>("a <= b"),
+            Expr::ComparisonExpr(ComparisonExpr::LessOrEqual(LessOrEqual {
+                left: make("a"),
+                right: make("b"),
+            }))
+        );
+        assert_eq!(
+            make::<Expr>("a >= b - 1"),
+            Expr::ComparisonExpr(ComparisonExpr::MoreOrEqual(MoreOrEqual {
+                left: make("a"),
+                right: make("b - 1"),
+            }))
+        );
+    }
+
+    #[test]
+    fn set_less_or_equal() {
+        assert_eq!(
+            make::<TopExpr>("a = b <= c"),
+            TopExpr::Set(Set {
+                name: make("a"),
+                expr: make("b <= c"),
+            })
+        );
+    }
+
+    #[test]
+    fn unary_binds_tighter() {
+        assert_eq!(
+            make::<Expr>("!a && b"),
+            Expr::ComparisonExpr(ComparisonExpr::And(And {
+                left: make("!a"),
+                right: make("b"),
+            }))
+        );
+        assert_eq!(
+            make::<Expr>("-a < b"),
+            Expr::ComparisonExpr(ComparisonExpr::LessThan(LessThan {
+                left: make("-a"),
+                right: make("b"),
+            }))
+        );
+    }
+
+    #[test]
+    fn or_left_associative() {
+        assert_eq!(
+            make::<Expr>("a || b || c"),
+            Expr::ComparisonExpr(ComparisonExpr::Or(Or {
+                left: make("a || b"),
+                right: make("c"),
+            }))
+        );
+    }
+}
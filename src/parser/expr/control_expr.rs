@@ -6,12 +6,15 @@ pub enum ControlExpr {
     If(If),
     For(For),
     While(While),
+    When(When),
     Return(Return),
+    Break(Break),
+    Continue(Continue),
 }
 
 impl TryParse for ControlExpr {
-    fn try_parse<'a>(pairs: &'a [Pair<'a>]) -> ParseResult<Self> {
-        let pair = pairs.get(0).ok_or(ParseError::UnexpectedEndOfInput)?;
+    fn try_parse<'a>(pairs: &'a [Pair<'a>]) -> ParseResult<'a, Self> {
+        let pair = expect_pair(pairs, "control expression")?;
 
         match pair.token {
             Token::If => {
@@ -26,11 +29,26 @@ impl TryParse for ControlExpr {
                 let (r, pairs) = try_parse(pairs)?;
                 Ok((ControlExpr::While(r), pairs))
             }
+            Token::When => {
+                let (r, pairs) = try_parse(pairs)?;
+                Ok((ControlExpr::When(r), pairs))
+            }
             Token::Return => {
                 let (r, pairs) = try_parse(pairs)?;
                 Ok((ControlExpr::Return(r), pairs))
             }
-            _ => Err(ParseError::WrongExprType(*pair, "ControlExpr")),
+            Token::Break => {
+                let (r, pairs) = try_parse(pairs)?;
+                Ok((ControlExpr::Break(r), pairs))
+            }
+            Token::Continue => {
+                let (r, pairs) = try_parse(pairs)?;
+                Ok((ControlExpr::Continue(r), pairs))
+            }
+            _ => Err(ParseError::new(
+                pairs,
+                Expected::Construct("control expression"),
+            )),
         }
     }
 }
@@ -41,7 +59,10 @@ impl std::fmt::Debug for ControlExpr {
             Self::If(child) => child.fmt(f),
             Self::For(child) => child.fmt(f),
             Self::While(child) => child.fmt(f),
+            Self::When(child) => child.fmt(f),
             Self::Return(child) => child.fmt(f),
+            Self::Break(child) => child.fmt(f),
+            Self::Continue(child) => child.fmt(f),
         }
     }
 }
@@ -50,7 +71,7 @@ impl std::fmt::Debug for ControlExpr {
 pub struct Return(pub Option<BoxedExpr>);
 
 impl TryParse for Return {
-    fn try_parse<'a>(pairs: &'a [Pair<'a>]) -> ParseResult<Self> {
+    fn try_parse<'a>(pairs: &'a [Pair<'a>]) -> ParseResult<'a, Self> {
         let (_, pairs) = expect_token(pairs, Token::Return)?;
 
         if let Ok((expr, pairs)) = try_parse(pairs) {
@@ -61,6 +82,26 @@ impl TryParse for Return {
     }
 }
 
+#[derive(Debug, PartialEq)]
+pub struct Break;
+
+impl TryParse for Break {
+    fn try_parse<'a>(pairs: &'a [Pair<'a>]) -> ParseResult<'a, Self> {
+        let (_, pairs) = expect_token(pairs, Token::Break)?;
+        Ok((Break, pairs))
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Continue;
+
+impl TryParse for Continue {
+    fn try_parse<'a>(pairs: &'a [Pair<'a>]) -> ParseResult<'a, Self> {
+        let (_, pairs) = expect_token(pairs, Token::Continue)?;
+        Ok((Continue, pairs))
+    }
+}
+
 #[derive(Debug, PartialEq)]
 pub struct For {
     pub var: Ident,
@@ -69,15 +110,18 @@ pub struct For {
 }
 
 impl TryParse for For {
-    fn try_parse<'a>(pairs: &'a [Pair<'a>]) -> ParseResult<Self> {
+    fn try_parse<'a>(pairs: &'a [Pair<'a>]) -> ParseResult<'a, Self> {
+        let start = pairs;
+        let within = |err: ParseError<'a>| err.within("for loop", start);
+
         let (_, pairs) = expect_token(pairs, Token::For)?;
-        let pairs = expect_symbol(pairs, '(')?;
-        let (var, pairs) = try_parse(pairs)?;
-        let (_, pairs) = expect_token(pairs, Token::In)?;
-        let (iterable, pairs) = try_parse(pairs)?;
-        let pairs = expect_symbol(pairs, ')')?;
+        let pairs = expect_symbol(pairs, '(').map_err(within)?;
+        let (var, pairs) = try_parse(pairs).map_err(within)?;
+        let (_, pairs) = expect_token(pairs, Token::In).map_err(within)?;
+        let (iterable, pairs) = try_parse(pairs).map_err(within)?;
+        let pairs = expect_symbol(pairs, ')').map_err(within)?;
 
-        let (body, pairs) = expect_body(pairs)?;
+        let (body, pairs) = expect_body(pairs).map_err(within)?;
 
         let f = For {
             var,
@@ -96,13 +140,16 @@ pub struct While {
 }
 
 impl TryParse for While {
-    fn try_parse<'a>(pairs: &'a [Pair<'a>]) -> ParseResult<Self> {
+    fn try_parse<'a>(pairs: &'a [Pair<'a>]) -> ParseResult<'a, Self> {
+        let start = pairs;
+        let within = |err: ParseError<'a>| err.within("while loop", start);
+
         let (_, pairs) = expect_token(pairs, Token::While)?;
-        let pairs = expect_symbol(pairs, '(')?;
-        let (expr, pairs) = try_parse(pairs)?;
-        let pairs = expect_symbol(pairs, ')')?;
+        let pairs = expect_symbol(pairs, '(').map_err(within)?;
+        let (expr, pairs) = try_parse(pairs).map_err(within)?;
+        let pairs = expect_symbol(pairs, ')').map_err(within)?;
 
-        let (body, pairs) = expect_body(pairs)?;
+        let (body, pairs) = expect_body(pairs).map_err(within)?;
 
         let w = While { expr, body };
 
@@ -118,22 +165,21 @@ pub struct If {
 }
 
 impl TryParse for If {
-    fn try_parse<'a>(pairs: &'a [Pair<'a>]) -> ParseResult<Self> {
+    fn try_parse<'a>(pairs: &'a [Pair<'a>]) -> ParseResult<'a, Self> {
+        let start = pairs;
+        let within = |err: ParseError<'a>| err.within("if expression", start);
+
         let (_, pairs) = expect_token(pairs, Token::If)?;
-        let pairs = expect_symbol(pairs, '(')?;
+        let pairs = expect_symbol(pairs, '(').map_err(within)?;
 
-        let (expr, pairs) = try_parse(pairs)?;
+        let (expr, pairs) = try_parse(pairs).map_err(within)?;
 
-        let pairs = expect_symbol(pairs, ')')?;
+        let pairs = expect_symbol(pairs, ')').map_err(within)?;
 
-        let (body, pairs) = expect_body(pairs)?;
+        let (body, pairs) = expect_branch(pairs).map_err(within)?;
 
         let (else_branch, pairs) = if let Ok((_, pairs)) = expect_token(pairs, Token::Else) {
-            if let Ok((top_expr, pairs)) = try_parse(pairs) {
-                (vec![top_expr], pairs)
-            } else {
-                expect_body(pairs)?
-            }
+            expect_branch(pairs).map_err(within)?
         } else {
             (vec![], pairs)
         };
@@ -148,6 +194,116 @@ impl TryParse for If {
     }
 }
 
+#[derive(Debug, PartialEq)]
+pub struct When {
+    pub subject: Option<BoxedExpr>,
+    pub branches: Vec<WhenBranch>,
+    pub else_branch: Option<Body>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct WhenBranch {
+    pub conditions: Vec<Spanned<WhenCondition>>,
+    pub body: Body,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum WhenCondition {
+    Expr(Expr),
+    In(BoxedExpr),
+    Is(Spanned<Type>),
+}
+
+impl TryParse for When {
+    fn try_parse<'a>(pairs: &'a [Pair<'a>]) -> ParseResult<'a, Self> {
+        let start = pairs;
+        let within = |err: ParseError<'a>| err.within("when expression", start);
+
+        let (_, pairs) = expect_token(pairs, Token::When)?;
+
+        let (subject, pairs) = match expect_symbol(pairs, '(') {
+            Ok(pairs) => {
+                let (subject, pairs) = try_parse(pairs).map_err(within)?;
+                let pairs = expect_symbol(pairs, ')').map_err(within)?;
+                (Some(subject), pairs)
+            }
+            Err(_) => (None, pairs),
+        };
+
+        let mut pairs = expect_symbol(pairs, '{').map_err(within)?;
+        let mut branches = vec![];
+        let mut else_branch = None;
+
+        loop {
+            pairs = ignore_newlines(pairs);
+
+            if let Ok(pairs) = expect_symbol(pairs, '}') {
+                let w = When {
+                    subject,
+                    branches,
+                    else_branch,
+                };
+                return Ok((w, pairs));
+            }
+
+            if else_branch.is_some() {
+                return Err(within(ParseError::new(pairs, '}')));
+            }
+
+            if let Ok((_, p)) = expect_token(pairs, Token::Else) {
+                let (_, p) = expect_token(p, Token::Arrow).map_err(within)?;
+                let (body, p) = expect_branch(p).map_err(within)?;
+                else_branch = Some(body);
+                pairs = p;
+            } else {
+                let (branch, p) =
+                    try_parse(pairs).map_err(|err| within(err.or(ParseError::new(pairs, '}'))))?;
+                branches.push(branch);
+                pairs = p;
+            }
+        }
+    }
+}
+
+impl TryParse for WhenBranch {
+    fn try_parse<'a>(mut pairs: &'a [Pair<'a>]) -> ParseResult<'a, Self> {
+        let mut conditions = vec![];
+
+        loop {
+            let (condition, p) = try_parse(pairs)?;
+            conditions.push(condition);
+
+            match expect_symbol(p, ',') {
+                Ok(p) => pairs = ignore_newlines(p),
+                Err(_) => {
+                    pairs = p;
+                    break;
+                }
+            }
+        }
+
+        let (_, pairs) = expect_token(pairs, Token::Arrow)?;
+        let (body, pairs) = expect_branch(pairs)?;
+
+        Ok((Self { conditions, body }, pairs))
+    }
+}
+
+impl TryParse for WhenCondition {
+    fn try_parse<'a>(pairs: &'a [Pair<'a>]) -> ParseResult<'a, Self> {
+        if let Ok((_, pairs)) = expect_token(pairs, Token::In) {
+            let (expr, pairs) = try_parse(pairs)?;
+            Ok((Self::In(expr), pairs))
+        } else if let Ok((_, pairs)) = expect_token(pairs, Token::Is) {
+            let (ty, pairs) = try_parse(pairs)?;
+            Ok((Self::Is(ty), pairs))
+        } else {
+            let (expr, pairs) = try_parse(pairs)?;
+            Ok((Self::Expr(expr), pairs))
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -180,6 +336,18 @@ mod tests {
         );
     }
 
+    #[test]
+    fn if_value() {
+        assert_eq!(
+            make::<Expr>("if (a > b) a else b"),
+            Expr::TopExpr(TopExpr::ControlExpr(ControlExpr::If(If {
+                expr: make("a > b"),
+                body: vec![make("a")],
+                else_branch: vec![make("b")],
+            })))
+        );
+    }
+
     #[test]
     fn while_loop() {
         make::<ControlExpr>(
@@ -199,6 +367,23 @@ mod tests {
         );
     }
 
+    #[test]
+    fn break_continue() {
+        assert_eq!(make::<ControlExpr>("break"), ControlExpr::Break(Break));
+        assert_eq!(
+            make::<ControlExpr>("continue"),
+            ControlExpr::Continue(Continue)
+        );
+        make::<ControlExpr>(
+            r#"while (true) {
+                if (a) {
+                    continue
+                }
+                break
+            }"#,
+        );
+    }
+
     #[test]
     fn for_range() {
         make::<ControlExpr>(
@@ -207,4 +392,62 @@ mod tests {
             }"#,
         );
     }
+
+    #[test]
+    fn when_subject() {
+        let when = make::<When>(
+            r#"when (value) {
+                0 -> "zero"
+                1, 2 -> {
+                    println("small")
+                    "small"
+                }
+                in 3..9 -> "digit"
+                else -> "large"
+            }"#,
+        );
+
+        assert_eq!(when.subject, Some(make("value")));
+        assert_eq!(when.branches.len(), 3);
+        assert_eq!(when.branches[1].conditions, [make("1"), make("2")]);
+        assert_eq!(when.branches[1].body.len(), 2);
+        assert_eq!(
+            *when.branches[2].conditions[0],
+            WhenCondition::In(make("3..9"))
+        );
+        assert_eq!(when.else_branch, Some(vec![make("\"large\"")]));
+    }
+
+    #[test]
+    fn when_without_subject() {
+        let when = make::<When>(
+            r#"when {
+                a < b -> println("less")
+                a > b -> println("more")
+            }"#,
+        );
+
+        assert_eq!(when.subject, None);
+        assert_eq!(when.branches[0].conditions, [make("a < b")]);
+        assert_eq!(when.else_branch, None);
+    }
+
+    #[test]
+    fn when_is() {
+        let when = make::<When>(
+            "when (shape) {
+                is Circle, is Square -> 1
+                Color.RED -> 2
+            }",
+        );
+
+        let conditions = &when.branches[0].conditions;
+        let circle = Type::Simple(Ident("Circle".to_owned()));
+        assert!(matches!(&*conditions[0], WhenCondition::Is(ty) if **ty == circle));
+        assert_eq!(conditions.len(), 2);
+        assert!(matches!(
+            *when.branches[1].conditions[0],
+            WhenCondition::Expr(_)
+        ));
+    }
 }
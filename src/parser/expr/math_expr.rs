@@ -1,125 +1,30 @@
 use super::*;
 
-expr_enum!(MathExpr => Neg | BoolNeg | Range | Sub | Add | Mul | Div | Parens);
-
-#[derive(Debug, PartialEq)]
-pub struct Parens(pub BoxedExpr);
-
-impl TryParse for Parens {
-    fn try_parse<'a>(pairs: &'a [Pair<'a>]) -> ParseResult<Self> {
-        let pairs = expect_symbol(pairs, '(')?;
-        let mut matcing_paren_index = pairs.len();
-        let mut nesting_level = 1;
-        for (index, pair) in pairs.iter().enumerate() {
-            if let Token::Symbol(s) = pair.token {
-                if s == '(' {
-                    nesting_level += 1;
-                }
-                if s == ')' {
-                    nesting_level -= 1;
-                    if nesting_level == 0 {
-                        matcing_paren_index = index;
-                        break;
-                    }
-                }
-            }
-        }
-
-        if matcing_paren_index == pairs.len() {
-            return Err(ParseError::UnexpectedEndOfInput);
-        }
-
-        let (enclosed, pairs) = pairs.split_at(matcing_paren_index);
-
-        let (expr, _) = try_parse(enclosed)?;
-        let pairs = expect_symbol(pairs, ')')?;
-        Ok((Parens(expr), pairs))
-    }
-}
-
 #[derive(Debug, PartialEq)]
 pub struct Neg(pub BoxedExpr);
 
-impl TryParse for Neg {
-    fn try_parse<'a>(pairs: &'a [Pair<'a>]) -> ParseResult<Self> {
-        let pairs = expect_symbol(pairs, '-')?;
-        let (expr, pairs) = try_parse(pairs)?;
-        Ok((Self(expr), pairs))
-    }
-}
-
 #[derive(Debug, PartialEq)]
 pub struct BoolNeg(pub BoxedExpr);
 
-impl TryParse for BoolNeg {
-    fn try_parse<'a>(pairs: &'a [Pair<'a>]) -> ParseResult<Self> {
-        let pairs = expect_symbol(pairs, '!')?;
-        let (expr, pairs) = try_parse(pairs)?;
-        Ok((Self(expr), pairs))
-    }
-}
-
 macro_rules! binary_operator {
-    ($name:ident => $tok:expr) => {
+    ($name:ident) => {
         #[derive(Debug, PartialEq)]
         pub struct $name {
             pub left: BoxedExpr,
             pub right: BoxedExpr,
         }
-
-        impl TryParse for $name {
-            fn try_parse<'a>(pairs: &'a [Pair<'a>]) -> ParseResult<Self> {
-                let operator: Token = $tok.into();
-
-                let sym_pos = get_toplevel_index_of(pairs, operator);
-
-                let &pair = pairs.get(sym_pos).ok_or(ParseError::UnexpectedEndOfInput)?;
-
-                if pair.token == Token::NewLine {
-                    Err(ParseError::UnexpectedToken(pair, operator))
-                } else {
-                    let (left, right) = pairs.split_at(sym_pos);
-
-                    let (left, empty_pairs) = try_parse(left)?;
-                    if !empty_pairs.is_empty() {
-                        Err(ParseError::WrongExprType(pair, stringify!($name)))
-                    } else {
-                        let (right, pairs) = try_parse(&right[1..])?;
-                        Ok((Self { left, right }, pairs))
-                    }
-                }
-            }
-        }
     };
 }
 
 pub(crate) use binary_operator;
 
-pub(crate) fn get_toplevel_index_of<'a>(pairs: &'a [Pair<'a>], token: Token) -> usize {
-    let mut target_index = usize::MAX;
-    let mut nesting_level = 0;
-    for (index, pair) in pairs.iter().enumerate() {
-        if let Token::Symbol(s) = pair.token {
-            match s {
-                '(' => nesting_level += 1,
-                ')' => nesting_level -= 1,
-                _ => {}
-            }
-        }
-        if nesting_level == 0 && pair.token == token {
-            target_index = index;
-            break;
-        }
-    }
-
-    target_index
-}
-
-binary_operator!(Mul => '*');
-binary_operator!(Div => '/');
-binary_operator!(Add => '+');
-binary_operator!(Sub => '-');
-binary_operator!(Range => Token::RangeOp);
+binary_operator!(Mul);
+binary_operator!(Div);
+binary_operator!(Rem);
+binary_operator!(Add);
+binary_operator!(Sub);
+binary_operator!(Range);
+binary_operator!(Elvis);
 
 #[cfg(test)]
 mod tests {
@@ -130,13 +35,15 @@ mod tests {
         match expr {
             Expr::TopExpr(_) => panic!(),
             Expr::MathExpr(math_expr) => match math_expr {
-                MathExpr::Parens(Parens(expr)) => eval_expr(&expr),
-                MathExpr::Mul(Mul { left, right }) => eval_expr(&left) * eval_expr(&right),
-                MathExpr::Div(Div { left, right }) => eval_expr(&left) / eval_expr(&right),
-                MathExpr::Add(Add { left, right }) => eval_expr(&left) + eval_expr(&right),
-                MathExpr::Sub(Sub { left, right }) => eval_expr(&left) - eval_expr(&right),
+                MathExpr::Mul(Mul { left, right }) => eval_expr(left) * eval_expr(right),
+                MathExpr::Div(Div { left, right }) => eval_expr(left) / eval_expr(right),
+                MathExpr::Rem(Rem { left, right }) => eval_expr(left) % eval_expr(right),
+                MathExpr::Add(Add { left, right }) => eval_expr(left) + eval_expr(right),
+                MathExpr::Sub(Sub { left, right }) => eval_expr(left) - eval_expr(right),
+                MathExpr::Neg(Neg(expr)) => -eval_expr(expr),
                 _ => panic!(),
             },
+            Expr::ShortExpr(ShortExpr::Parens(Parens(expr))) => eval_expr(expr),
             Expr::ShortExpr(ShortExpr::Literal(Literal::Int(int))) => *int,
             _ => panic!(),
         }
@@ -182,6 +89,30 @@ mod tests {
         check!(30 * 5 / 5);
     }
 
+    #[test]
+    fn rem() {
+        check!(17 % 5);
+        check!(7 + 10 % 4);
+        check!(10 % 4 - 7);
+    }
+
+    #[test]
+    fn left_associative() {
+        check!(10 - 4 - 3);
+        check!(100 / 10 / 5);
+        check!(20 - 6 + 4 - 2);
+        check!(64 / 4 * 2 / 8);
+        check!(100 % 30 % 7);
+    }
+
+    #[test]
+    fn unary() {
+        check!(-3 + 5);
+        check!(-3 * 5 - 2);
+        check!(2 - -3);
+        check!(-(2 + 3) * -4);
+    }
+
     #[test]
     fn range() {
         assert_eq!(
@@ -12,7 +12,186 @@ pub use top_expr::*;
 
 use super::*;
 
-expr_enum!(Expr => MathExpr | TopExpr | ComparisonExpr | ShortExpr);
+expr_enum!(Expr => ComparisonExpr | Elvis | MathExpr | TopExpr | ShortExpr | Invalid);
+expr_enum!(MathExpr => Neg | BoolNeg | Range | Sub | Add | Mul | Div | Rem);
+expr_enum!(
+    ComparisonExpr => And | Or | Equal | NotEqual | LessThan | MoreThan | LessOrEqual | MoreOrEqual
+);
+
+impl TryParse for Expr {
+    fn try_parse<'a>(pairs: &'a [Pair<'a>]) -> ParseResult<'a, Self> {
+        let pair = expect_pair(pairs, "expression")?;
+
+        match pair.token {
+            Token::Var | Token::Val => {
+                let (r, pairs) = try_parse(pairs)?;
+                Ok((Expr::TopExpr(r), pairs))
+            }
+            _ => parse_binary(pairs, 0),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Invalid {
+    pub error: SyntaxError,
+    /// Name declared by a broken `val` or `var`, so its uses aren't reported too
+    pub binding: Option<Ident>,
+}
+
+/// Binding power of a binary operator, operators with higher powers bind tighter.
+/// Elvis binds tighter than comparisons, but looser than arithmetic
+fn precedence(token: Token) -> Option<u8> {
+    let precedence = match token {
+        Token::OrOp => 0,
+        Token::AndOp => 1,
+        Token::EqOp | Token::NotEqOp => 2,
+        Token::Symbol('<' | '>') | Token::LessEqOp | Token::MoreEqOp => 3,
+        Token::Elvis => 4,
+        Token::RangeOp => 5,
+        Token::Symbol('+' | '-') => 6,
+        Token::Symbol('*' | '/' | '%') => 7,
+        _ => return None,
+    };
+    Some(precedence)
+}
+
+fn binary_expr(operator: Token, left: BoxedExpr, right: BoxedExpr) -> Expr {
+    use ComparisonExpr as C;
+    use MathExpr as M;
+
+    match operator {
+        Token::OrOp => Expr::ComparisonExpr(C::Or(Or { left, right })),
+        Token::AndOp => Expr::ComparisonExpr(C::And(And { left, right })),
+        Token::EqOp => Expr::ComparisonExpr(C::Equal(Equal { left, right })),
+        Token::NotEqOp => Expr::ComparisonExpr(C::NotEqual(NotEqual { left, right })),
+        Token::Symbol('<') => Expr::ComparisonExpr(C::LessThan(LessThan { left, right })),
+        Token::Symbol('>') => Expr::ComparisonExpr(C::MoreThan(MoreThan { left, right })),
+        Token::LessEqOp => Expr::ComparisonExpr(C::LessOrEqual(LessOrEqual { left, right })),
+        Token::MoreEqOp => Expr::ComparisonExpr(C::MoreOrEqual(MoreOrEqual { left, right })),
+        Token::Elvis => Expr::Elvis(Elvis { left, right }),
+        Token::RangeOp => Expr::MathExpr(M::Range(Range { left, right })),
+        Token::Symbol('+') => Expr::MathExpr(M::Add(Add { left, right })),
+        Token::Symbol('-') => Expr::MathExpr(M::Sub(Sub { left, right })),
+        Token::Symbol('*') => Expr::MathExpr(M::Mul(Mul { left, right })),
+        Token::Symbol('/') => Expr::MathExpr(M::Div(Div { left, right })),
+        Token::Symbol('%') => Expr::MathExpr(M::Rem(Rem { left, right })),
+        _ => unreachable!("not a binary operator"),
+    }
+}
+
+fn continues_on_next_line(token: Token) -> bool {
+    matches!(token, Token::AndOp | Token::OrOp | Token::Elvis)
+}
+
+/// Expression or an assignment, if `=` follows an assignable operand.
+/// The operand is parsed only once, so nested lambdas aren't parsed again and again
+pub fn parse_statement<'a>(pairs: &'a [Pair<'a>]) -> ParseResult<'a, Expr> {
+    if matches!(
+        expect_pair(pairs, "statement")?.token,
+        Token::Var | Token::Val
+    ) {
+        let (top, rest) = try_parse(pairs)?;
+        return Ok((Expr::TopExpr(top), rest));
+    }
+
+    let (left, rest) = parse_prefix(pairs)?;
+    match left {
+        Expr::ShortExpr(target) if is_assignable(&target) && expect_symbol(rest, '=').is_ok() => {
+            let (top, rest) = parse_assignment(pairs, target, rest)?;
+            Ok((Expr::TopExpr(top), rest))
+        }
+        left => parse_binary_operators(pairs, left, rest, 0),
+    }
+}
+
+fn parse_binary<'a>(pairs: &'a [Pair<'a>], min_precedence: u8) -> ParseResult<'a, Expr> {
+    let (left, rest) = parse_prefix(pairs)?;
+    parse_binary_operators(pairs, left, rest, min_precedence)
+}
+
+fn parse_binary_operators<'a>(
+    pairs: &'a [Pair<'a>],
+    mut left: Expr,
+    mut rest: &'a [Pair<'a>],
+    min_precedence: u8,
+) -> ParseResult<'a, Expr> {
+    loop {
+        let left_end = rest;
+        let next = ignore_newlines(rest);
+        let operator = match next.first() {
+            Some(pair) if continues_on_next_line(pair.token) => *pair,
+            _ => match rest.first() {
+                Some(pair) => *pair,
+                None => break,
+            },
+        };
+        let Some(precedence) = precedence(operator.token) else {
+            break;
+        };
+        if precedence < min_precedence {
+            break;
+        }
+
+        let operator_pairs = if continues_on_next_line(operator.token) {
+            next
+        } else {
+            rest
+        };
+        let right_pairs = ignore_newlines(&operator_pairs[1..]);
+        let (right, after) = parse_binary(right_pairs, precedence + 1)?;
+
+        let left_operand = Spanned::consumed(left, pairs, left_end);
+        let right_operand = Spanned::consumed(right, right_pairs, after);
+        left = binary_expr(
+            operator.token,
+            Box::new(left_operand),
+            Box::new(right_operand),
+        );
+        rest = after;
+    }
+
+    Ok((left, rest))
+}
+
+fn parse_prefix<'a>(pairs: &'a [Pair<'a>]) -> ParseResult<'a, Expr> {
+    let pair = expect_pair(pairs, "expression")?;
+
+    match pair.token {
+        Token::Symbol('-') => {
+            let (operand, rest) = try_parse_prefix_operand(&pairs[1..])?;
+            Ok((Expr::MathExpr(MathExpr::Neg(Neg(operand))), rest))
+        }
+        Token::Symbol('!') => {
+            let (operand, rest) = try_parse_prefix_operand(&pairs[1..])?;
+            Ok((Expr::MathExpr(MathExpr::BoolNeg(BoolNeg(operand))), rest))
+        }
+        Token::When => {
+            let (r, rest) = try_parse(pairs)?;
+            let expr = Expr::TopExpr(TopExpr::ControlExpr(r));
+            if !starts_postfix(rest) {
+                return Ok((expr, rest));
+            }
+            // `when { … }.length` acts as if the `when` was parenthesized
+            let parens = Parens(Box::new(Spanned::consumed(expr, pairs, rest)));
+            let (r, rest) = parse_postfix(pairs, ShortExpr::Parens(parens), rest)?;
+            Ok((Expr::ShortExpr(r), rest))
+        }
+        Token::If | Token::For | Token::While | Token::Return | Token::Break | Token::Continue => {
+            let (r, pairs) = try_parse(pairs)?;
+            Ok((Expr::TopExpr(TopExpr::ControlExpr(r)), pairs))
+        }
+        _ => {
+            let (r, pairs) = try_parse(pairs)?;
+            Ok((Expr::ShortExpr(r), pairs))
+        }
+    }
+}
+
+fn try_parse_prefix_operand<'a>(pairs: &'a [Pair<'a>]) -> ParseResult<'a, BoxedExpr> {
+    let (operand, rest) = parse_prefix(pairs)?;
+    Ok((Box::new(Spanned::consumed(operand, pairs, rest)), rest))
+}
 
 macro_rules! expr_enum {
     ($name:ident => $($type:ident)|+ ) => {
@@ -22,29 +201,15 @@ macro_rules! expr_enum {
              $($type($type)),+
         }
 
-        impl TryParse for $name {
-            fn try_parse<'a>(pairs: &'a [Pair<'a>]) -> ParseResult<Self> {
-                let pair = pairs.get(0).ok_or(ParseError::UnexpectedEndOfInput)?;
-
-                $(
-                    if let Ok((r, pairs)) = $type::try_parse(pairs) {
-                        return Ok(($name::$type(r), pairs))
-                    }
-                )+
-
-                Err(ParseError::WrongExprType(*pair, stringify!($name)))
-            }
-        }
-
         impl std::fmt::Debug for $name {
-        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-            match self {
-                $(
-                    Self::$type(child) => child.fmt(f)
-                ),+
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                match self {
+                    $(
+                        Self::$type(child) => child.fmt(f)
+                    ),+
+                }
             }
         }
-}
     };
 }
 
@@ -1,32 +1,185 @@
 use super::*;
 
+use std::rc::Rc;
+
 #[allow(clippy::enum_variant_names)]
 #[derive(PartialEq)]
 pub enum ShortExpr {
     Ident(Ident),
+    Call(Call),
+    Parens(Parens),
     GetByIndex(GetByIndex),
     Literal(Literal),
+    Template(Template),
+    Member(Member),
+    MethodCall(MethodCall),
+    Invoke(Invoke),
+    NotNull(NotNull),
+    Lambda(Lambda),
+    This,
 }
 
 impl TryParse for ShortExpr {
-    fn try_parse<'a>(pairs: &'a [Pair<'a>]) -> ParseResult<Self> {
-        let pair = pairs.get(0).ok_or(ParseError::UnexpectedEndOfInput)?;
+    fn try_parse<'a>(pairs: &'a [Pair<'a>]) -> ParseResult<'a, Self> {
+        let (expr, rest) = parse_atom(pairs)?;
+        parse_postfix(pairs, expr, rest)
+    }
+}
 
-        match pair.token {
-            Token::Char | Token::Str | Token::Int(_) | Token::Bool(_) => {
-                let (r, pairs) = try_parse(pairs)?;
-                Ok((ShortExpr::Literal(r), pairs))
+pub fn starts_postfix(pairs: &[Pair]) -> bool {
+    let next = ignore_newlines(pairs);
+    matches!(
+        next.first().map(|pair| pair.token),
+        Some(Token::Symbol('.') | Token::SafeCall)
+    ) || matches!(
+        pairs.first().map(|pair| pair.token),
+        Some(Token::Symbol('[' | '(') | Token::NotNullOp)
+    )
+}
+
+pub fn parse_postfix<'a>(
+    pairs: &'a [Pair<'a>],
+    mut expr: ShortExpr,
+    mut rest: &'a [Pair<'a>],
+) -> ParseResult<'a, ShortExpr> {
+    loop {
+        // Chained calls may continue on the next line
+        let next = ignore_newlines(rest);
+        if let Some(pair) = next.first() {
+            if matches!(pair.token, Token::Symbol('.') | Token::SafeCall) {
+                rest = next;
             }
-            Token::Ident => {
-                if let Ok((r, pairs)) = try_parse(pairs) {
-                    Ok((ShortExpr::GetByIndex(r), pairs))
+        }
+        let Some(&pair) = rest.first() else {
+            break;
+        };
+        let receiver = |expr| Box::new(Spanned::consumed(expr, pairs, rest));
+
+        (expr, rest) = match pair.token {
+            Token::Symbol('.') | Token::SafeCall => {
+                let (name, after_name) = try_parse(&rest[1..])?;
+                let is_safe = pair.token == Token::SafeCall;
+                let expr = receiver(expr);
+
+                let is_call =
+                    expect_symbol(after_name, '(').is_ok() || trailing_lambda(after_name).is_some();
+                if is_call {
+                    let (args, after_args) = if expect_symbol(after_name, '(').is_ok() {
+                        expect_sequence(
+                            after_name,
+                            '('.into(),
+                            ')'.into(),
+                            ','.into(),
+                            try_parse::<Argument>,
+                        )
+                        .map_err(|err| err.within("method call", pairs))?
+                    } else {
+                        (vec![], after_name)
+                    };
+
+                    let (named_args, args): (Vec<_>, Vec<_>) =
+                        args.into_iter().partition(|arg| arg.name.is_some());
+                    let mut args: Vec<_> = args.into_iter().map(|arg| arg.value).collect();
+                    let after_args = match trailing_lambda(after_args) {
+                        Some((lambda, rest)) => {
+                            args.push(lambda);
+                            rest
+                        }
+                        None => after_args,
+                    };
+
+                    let call = MethodCall {
+                        expr,
+                        name,
+                        args,
+                        named_args: named_args
+                            .into_iter()
+                            .map(|arg| (arg.name.unwrap(), arg.value))
+                            .collect(),
+                        is_safe,
+                    };
+                    (ShortExpr::MethodCall(call), after_args)
                 } else {
-                    let (r, pairs) = try_parse(pairs)?;
-                    Ok((ShortExpr::Ident(r), pairs))
+                    let member = Member {
+                        expr,
+                        name,
+                        is_safe,
+                    };
+                    (ShortExpr::Member(member), after_name)
                 }
             }
-            _ => Err(ParseError::WrongExprType(*pair, "ShortExpr")),
+            Token::Symbol('[') => {
+                let within = |err: ParseError<'a>| err.within("indexing", pairs);
+                let (index, after_index) =
+                    try_parse(ignore_newlines(&rest[1..])).map_err(within)?;
+                let after_index =
+                    expect_symbol(ignore_newlines(after_index), ']').map_err(within)?;
+                let get_by_index = GetByIndex {
+                    expr: receiver(expr),
+                    index,
+                };
+                (ShortExpr::GetByIndex(get_by_index), after_index)
+            }
+            Token::Symbol('(') => {
+                let (args, after_args) =
+                    expect_sequence(rest, '('.into(), ')'.into(), ','.into(), try_parse)
+                        .map_err(|err| err.within("function call", pairs))?;
+                let invoke = Invoke {
+                    expr: receiver(expr),
+                    args,
+                };
+                (ShortExpr::Invoke(invoke), after_args)
+            }
+            Token::NotNullOp => (ShortExpr::NotNull(NotNull(receiver(expr))), &rest[1..]),
+            _ => break,
+        };
+    }
+
+    Ok((expr, rest))
+}
+
+fn parse_atom<'a>(pairs: &'a [Pair<'a>]) -> ParseResult<'a, ShortExpr> {
+    let pair = expect_pair(pairs, "expression")?;
+
+    match pair.token {
+        Token::Str if is_template(pair.str()) => {
+            let (r, pairs) = try_parse(pairs)?;
+            Ok((ShortExpr::Template(r), pairs))
         }
+        Token::Char
+        | Token::Str
+        | Token::Int(_)
+        | Token::Long(_)
+        | Token::Float(_)
+        | Token::Double(_)
+        | Token::Bool(_)
+        | Token::Null => {
+            let (r, pairs) = try_parse(pairs)?;
+            Ok((ShortExpr::Literal(r), pairs))
+        }
+        // A name followed by parentheses is always a call, so errors in arguments are reported
+        Token::Ident if expect_symbol(&pairs[1..], '(').is_ok() => {
+            let (r, pairs) = try_parse(pairs)?;
+            Ok((ShortExpr::Call(r), pairs))
+        }
+        Token::Ident => {
+            if let Ok((r, pairs)) = try_parse(pairs) {
+                Ok((ShortExpr::Call(r), pairs))
+            } else {
+                let (r, pairs) = try_parse(pairs)?;
+                Ok((ShortExpr::Ident(r), pairs))
+            }
+        }
+        Token::This => Ok((ShortExpr::This, &pairs[1..])),
+        Token::Symbol('(') => {
+            let (r, pairs) = try_parse(pairs)?;
+            Ok((ShortExpr::Parens(r), pairs))
+        }
+        Token::Symbol('{') => {
+            let (r, pairs) = try_parse(pairs)?;
+            Ok((ShortExpr::Lambda(r), pairs))
+        }
+        _ => Err(ParseError::new(pairs, Expected::Construct("expression"))),
     }
 }
 
@@ -34,17 +187,132 @@ impl std::fmt::Debug for ShortExpr {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Self::Ident(child) => child.fmt(f),
+            Self::Call(child) => child.fmt(f),
+            Self::Parens(child) => child.fmt(f),
             Self::Literal(child) => child.fmt(f),
             Self::GetByIndex(child) => child.fmt(f),
+            Self::Template(child) => child.fmt(f),
+            Self::Member(child) => child.fmt(f),
+            Self::MethodCall(child) => child.fmt(f),
+            Self::Invoke(child) => child.fmt(f),
+            Self::NotNull(child) => child.fmt(f),
+            Self::Lambda(child) => child.fmt(f),
+            Self::This => write!(f, "This"),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Call {
+    pub name: Spanned<Ident>,
+    pub args: Vec<Spanned<Expr>>,
+}
+
+impl TryParse for Call {
+    fn try_parse<'a>(pairs: &'a [Pair<'a>]) -> ParseResult<'a, Self> {
+        let start = pairs;
+        let (name, pairs) = try_parse(pairs)?;
+
+        let (mut args, pairs) =
+            match expect_sequence(pairs, '('.into(), ')'.into(), ','.into(), try_parse) {
+                Ok(result) => result,
+                // Parentheses may be omitted when the only argument is a trailing lambda
+                Err(_) if trailing_lambda(pairs).is_some() => (vec![], pairs),
+                Err(err) => return Err(err.within("function call", start)),
+            };
+        let pairs = match trailing_lambda(pairs) {
+            Some((lambda, rest)) => {
+                args.push(lambda);
+                rest
+            }
+            None => pairs,
+        };
+
+        let call = Call { name, args };
+
+        Ok((call, pairs))
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Parens(pub BoxedExpr);
+
+impl TryParse for Parens {
+    fn try_parse<'a>(pairs: &'a [Pair<'a>]) -> ParseResult<'a, Self> {
+        let start = pairs;
+        let within = |err: ParseError<'a>| err.within("parenthesized expression", start);
+
+        let pairs = expect_symbol(pairs, '(')?;
+        let (expr, pairs) = try_parse(ignore_newlines(pairs)).map_err(within)?;
+        let pairs = expect_symbol(ignore_newlines(pairs), ')').map_err(within)?;
+        Ok((Parens(expr), pairs))
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Lambda {
+    pub params: Vec<LambdaParam>,
+    pub body: Rc<Body>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct LambdaParam {
+    pub name: Spanned<Ident>,
+    pub ty: Option<Spanned<Type>>,
+}
+
+impl TryParse for Lambda {
+    fn try_parse<'a>(pairs: &'a [Pair<'a>]) -> ParseResult<'a, Self> {
+        let start = pairs;
+        let pairs = expect_symbol(pairs, '{')?;
+
+        let (params, pairs) = match parse_lambda_params(ignore_newlines(pairs)) {
+            Ok((params, pairs)) => (params, pairs),
+            Err(_) => (vec![], pairs),
+        };
+        let (body, pairs) = expect_statements(pairs).map_err(|err| err.within("lambda", start))?;
+
+        let body = Rc::new(body);
+        Ok((Self { params, body }, pairs))
+    }
+}
+
+fn parse_lambda_params<'a>(mut pairs: &'a [Pair<'a>]) -> ParseResult<'a, Vec<LambdaParam>> {
+    let mut params = vec![];
+    if let Ok((_, pairs)) = expect_token(pairs, Token::Arrow) {
+        return Ok((params, pairs));
+    }
+
+    loop {
+        let (name, rest) = try_parse(pairs)?;
+        let (ty, rest) = match expect_symbol(rest, ':') {
+            Ok(rest) => {
+                let (ty, rest) = try_parse(rest)?;
+                (Some(ty), rest)
+            }
+            Err(_) => (None, rest),
+        };
+        params.push(LambdaParam { name, ty });
+
+        if let Ok((_, rest)) = expect_token(rest, Token::Arrow) {
+            return Ok((params, rest));
         }
+        pairs = expect_symbol(rest, ',')?;
     }
 }
 
+pub fn trailing_lambda<'a>(pairs: &'a [Pair<'a>]) -> Option<(Spanned<Expr>, &'a [Pair<'a>])> {
+    expect_symbol(pairs, '{').ok()?;
+    let (lambda, rest) = Lambda::try_parse(pairs).ok()?;
+    let expr = Expr::ShortExpr(ShortExpr::Lambda(lambda));
+    Some((Spanned::consumed(expr, pairs, rest), rest))
+}
+
 #[derive(Debug, PartialEq)]
 pub struct Ident(pub String);
 
 impl TryParse for Ident {
-    fn try_parse<'a>(pairs: &'a [Pair<'a>]) -> ParseResult<Self> {
+    fn try_parse<'a>(pairs: &'a [Pair<'a>]) -> ParseResult<'a, Self> {
         let (ident_pair, pairs) = expect_token(pairs, Token::Ident)?;
         Ok((Self(ident_pair.str().to_owned()), pairs))
     }
@@ -52,38 +320,90 @@ impl TryParse for Ident {
 
 #[derive(Debug, PartialEq)]
 pub struct GetByIndex {
-    pub ident: Spanned<Ident>,
+    pub expr: Box<Spanned<ShortExpr>>,
     pub index: BoxedExpr,
 }
 
 impl TryParse for GetByIndex {
-    fn try_parse<'a>(pairs: &'a [Pair<'a>]) -> ParseResult<Self> {
-        let (ident, pairs) = try_parse(pairs)?;
-        let pairs = expect_symbol(pairs, '[')?;
-        let (index, pairs) = try_parse(pairs)?;
-        let pairs = expect_symbol(pairs, ']')?;
+    fn try_parse<'a>(pairs: &'a [Pair<'a>]) -> ParseResult<'a, Self> {
+        let (ShortExpr::GetByIndex(get_by_index), rest) = try_parse(pairs)? else {
+            return Err(ParseError::new(pairs, Expected::Construct("indexing")));
+        };
+
+        Ok((get_by_index, rest))
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Member {
+    pub expr: Box<Spanned<ShortExpr>>,
+    pub name: Spanned<Ident>,
+    pub is_safe: bool,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct MethodCall {
+    pub expr: Box<Spanned<ShortExpr>>,
+    pub name: Spanned<Ident>,
+    pub args: Vec<Spanned<Expr>>,
+    pub named_args: Vec<(Spanned<Ident>, Spanned<Expr>)>,
+    pub is_safe: bool,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Invoke {
+    pub expr: Box<Spanned<ShortExpr>>,
+    pub args: Vec<Spanned<Expr>>,
+}
+
+struct Argument {
+    name: Option<Spanned<Ident>>,
+    value: Spanned<Expr>,
+}
+
+impl TryParse for Argument {
+    fn try_parse<'a>(pairs: &'a [Pair<'a>]) -> ParseResult<'a, Self> {
+        let named =
+            try_parse(pairs).and_then(|(name, pairs)| Ok((name, expect_symbol(pairs, '=')?)));
 
-        Ok((Self { ident, index }, pairs))
+        let (name, pairs) = match named {
+            Ok((name, pairs)) => (Some(name), pairs),
+            Err(_) => (None, pairs),
+        };
+
+        let (value, pairs) = try_parse(pairs)?;
+        Ok((Argument { name, value }, pairs))
     }
 }
 
+#[derive(Debug, PartialEq)]
+pub struct NotNull(pub Box<Spanned<ShortExpr>>);
+
 #[derive(PartialEq)]
 pub enum Literal {
     Int(i32),
+    Long(i64),
+    Float(f32),
+    Double(f64),
     Bool(bool),
     Char(char),
     String(String),
+    Null,
 }
 
 impl TryParse for Literal {
-    fn try_parse<'a>(pairs: &'a [Pair<'a>]) -> ParseResult<Self> {
-        let pair = pairs.first().ok_or(ParseError::UnexpectedEndOfInput)?;
+    fn try_parse<'a>(pairs: &'a [Pair<'a>]) -> ParseResult<'a, Self> {
+        let pair = expect_pair(pairs, "literal")?;
         let literal = match pair.token {
             Token::Char => Self::Char(parse_char(pair.str())),
             Token::Str => Self::String(parse_string(pair.str())),
             Token::Int(val) => Self::Int(val),
+            Token::Long(val) => Self::Long(val),
+            Token::Float(val) => Self::Float(val),
+            Token::Double(val) => Self::Double(val),
             Token::Bool(val) => Self::Bool(val),
-            _ => return Err(ParseError::WrongExprType(*pair, "Literal")),
+            Token::Null => Self::Null,
+            _ => return Err(ParseError::new(pairs, Expected::Construct("literal"))),
         };
 
         Ok((literal, &pairs[1..]))
@@ -94,14 +414,132 @@ impl std::fmt::Debug for Literal {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let s = match self {
             Literal::Int(i) => i.to_string(),
+            Literal::Long(i) => i.to_string(),
+            Literal::Float(f) => f.to_string(),
+            Literal::Double(f) => f.to_string(),
             Literal::Bool(b) => b.to_string(),
             Literal::Char(c) => c.to_string(),
             Literal::String(s) => s.to_owned(),
+            Literal::Null => "null".to_owned(),
         };
         f.debug_tuple("Literal").field(&s).finish()
     }
 }
 
+#[derive(Debug, PartialEq)]
+pub struct Template(pub Vec<TemplatePart>);
+
+#[derive(Debug, PartialEq)]
+pub enum TemplatePart {
+    Str(String),
+    Expr(Spanned<Expr>),
+}
+
+impl TryParse for Template {
+    fn try_parse<'a>(pairs: &'a [Pair<'a>]) -> ParseResult<'a, Self> {
+        let (pair, pairs) = expect_token(pairs, Token::Str)?;
+
+        let mut parts = vec![];
+        let mut literal_lo = '"'.len_utf8();
+
+        for (lo, hi, expr) in template_exprs(pair.str()) {
+            let literal = &pair.str()[literal_lo..lo];
+            if !literal.is_empty() {
+                parts.push(TemplatePart::Str(unescape(literal)));
+            }
+            literal_lo = hi;
+
+            // The closing brace of `${expr}` is lexed too, so leftovers are reported before it
+            let is_braced = hi > expr.hi;
+            let span = Span {
+                lo: pair.span.lo + expr.lo,
+                hi: pair.span.lo + expr.hi + usize::from(is_braced),
+            };
+
+            let expr_pairs: Vec<_> = Lexer::with_span(pair.source(), span).collect();
+            // Pairs of the template expression don't outlive this function,
+            // so the error is moved to the same pairs copied out of them
+            let detach = |err: ParseError| ParseError {
+                found: err
+                    .found
+                    .and_then(|found| expr_pairs.iter().find(|p| p.span.lo == found.span.lo))
+                    .copied()
+                    .or(Some(pair)),
+                expected: err.expected,
+                enclosing: err.enclosing,
+            };
+            let (expr, rest) = try_parse(&expr_pairs).map_err(detach)?;
+            if is_braced {
+                expect_symbol(rest, '}').map_err(detach)?;
+            }
+            parts.push(TemplatePart::Expr(expr));
+        }
+
+        let literal = &pair.str()[literal_lo..pair.str().len() - '"'.len_utf8()];
+        if !literal.is_empty() {
+            parts.push(TemplatePart::Str(unescape(literal)));
+        }
+
+        Ok((Self(parts), pairs))
+    }
+}
+
+fn is_template(string: &str) -> bool {
+    template_exprs(string).next().is_some()
+}
+
+/// Finds `$name` and `${expr}` entries in a string literal.
+/// Yields the range of the entry and the range of the embedded expression.
+fn template_exprs(string: &str) -> impl Iterator<Item = (usize, usize, Span)> + '_ {
+    let bytes = string.as_bytes();
+    let mut index = 0;
+
+    std::iter::from_fn(move || {
+        while index < bytes.len() {
+            let lo = index;
+            index += 1;
+            match bytes[lo] {
+                b'\\' => index += 1,
+                b'$' if bytes.get(index) == Some(&b'{') => {
+                    // Braces are counted by tokens, so the ones in nested literals are skipped
+                    let mut nesting_level = 1;
+                    let close = Lexer::new(&string[index + 1..])
+                        .find(|pair| {
+                            match pair.token {
+                                Token::Symbol('{') => nesting_level += 1,
+                                Token::Symbol('}') => nesting_level -= 1,
+                                _ => {}
+                            }
+                            nesting_level == 0
+                        })?
+                        .span
+                        .lo;
+                    let expr = Span {
+                        lo: index + 1,
+                        hi: index + 1 + close,
+                    };
+                    index += close + 2;
+                    return Some((lo, index, expr));
+                }
+                b'$' if bytes.get(index).is_some_and(u8::is_ascii_alphabetic) => {
+                    let len = bytes[index..]
+                        .iter()
+                        .take_while(|b| b.is_ascii_alphanumeric())
+                        .count();
+                    let expr = Span {
+                        lo: index,
+                        hi: index + len,
+                    };
+                    index += len;
+                    return Some((lo, index, expr));
+                }
+                _ => {}
+            }
+        }
+        None
+    })
+}
+
 fn parse_char(string: &str) -> char {
     let mut chars = string.chars().skip(1).take(2);
     let c = chars.next().unwrap();
@@ -122,8 +560,10 @@ fn parse_char(string: &str) -> char {
 }
 
 fn parse_string(string: &str) -> String {
-    let substring = &string['"'.len_utf8()..string.len() - '"'.len_utf8()];
+    unescape(&string['"'.len_utf8()..string.len() - '"'.len_utf8()])
+}
 
+fn unescape(substring: &str) -> String {
     if !substring.chars().any(|c| c == '\\') {
         substring.to_owned()
     } else {
@@ -172,12 +612,208 @@ mod tests {
         assert_eq!(
             make::<ShortExpr>("a[i]"),
             ShortExpr::GetByIndex(GetByIndex {
-                ident: make("a"),
+                expr: Box::new(make("a")),
                 index: make("i")
             })
         )
     }
 
+    #[test]
+    fn get_by_index_of_any_expression() {
+        assert_eq!(
+            make::<ShortExpr>("m[i][j - 1]"),
+            ShortExpr::GetByIndex(GetByIndex {
+                expr: Box::new(make("m[i]")),
+                index: make("j - 1")
+            })
+        );
+        assert_eq!(
+            make::<ShortExpr>("f(x < y)[0]"),
+            ShortExpr::GetByIndex(GetByIndex {
+                expr: Box::new(make("f(x < y)")),
+                index: make("0")
+            })
+        );
+        make::<ShortExpr>("\"abc\".split(\"b\")[arr[0]].length");
+    }
+
+    #[test]
+    fn invoke() {
+        assert_eq!(
+            make::<ShortExpr>("fs[0]()"),
+            ShortExpr::Invoke(Invoke {
+                expr: Box::new(make("fs[0]")),
+                args: vec![],
+            })
+        );
+        assert_eq!(
+            make::<ShortExpr>("makeAdder(1)(2)"),
+            ShortExpr::Invoke(Invoke {
+                expr: Box::new(make("makeAdder(1)")),
+                args: vec![make("2")],
+            })
+        );
+        assert!(matches!(
+            make::<Expr>("when { a -> \"x\" else -> \"yz\" }.length"),
+            Expr::ShortExpr(ShortExpr::Member(_))
+        ));
+    }
+
+    #[test]
+    fn parens_leftover() {
+        assert_eq!(
+            fail::<Parens>("(a b)"),
+            "expected `)`, found identifier `b`"
+        );
+        assert_eq!(
+            fail::<ShortExpr>("f(a b)"),
+            "expected `,` or `)`, found identifier `b`"
+        );
+    }
+
+    #[test]
+    fn member() {
+        assert_eq!(
+            make::<ShortExpr>("a[i].code"),
+            ShortExpr::Member(Member {
+                expr: Box::new(make("a[i]")),
+                name: make("code"),
+                is_safe: false,
+            })
+        )
+    }
+
+    #[test]
+    fn method_call() {
+        assert_eq!(
+            make::<ShortExpr>("this.point?.len(a, 1)"),
+            ShortExpr::MethodCall(MethodCall {
+                expr: Box::new(make("this.point")),
+                name: make("len"),
+                args: vec![make("a"), make("1")],
+                named_args: vec![],
+                is_safe: true,
+            })
+        );
+        assert_eq!(
+            make::<ShortExpr>("p.copy(1, b = x == y)"),
+            ShortExpr::MethodCall(MethodCall {
+                expr: Box::new(make("p")),
+                name: make("copy"),
+                args: vec![make("1")],
+                named_args: vec![(make("b"), make("x == y"))],
+                is_safe: false,
+            })
+        );
+    }
+
+    #[test]
+    fn postfix_on_any_expression() {
+        assert_eq!(
+            make::<ShortExpr>("Point(1, 2).moved(3).x"),
+            ShortExpr::Member(Member {
+                expr: Box::new(make("Point(1, 2).moved(3)")),
+                name: make("x"),
+                is_safe: false,
+            })
+        );
+        assert_eq!(
+            make::<ShortExpr>("Point(1, 2).moved(3)"),
+            ShortExpr::MethodCall(MethodCall {
+                expr: Box::new(make("Point(1, 2)")),
+                name: make("moved"),
+                args: vec![make("3")],
+                named_args: vec![],
+                is_safe: false,
+            })
+        );
+        assert_eq!(
+            make::<ShortExpr>("(a + b).toString()"),
+            ShortExpr::MethodCall(MethodCall {
+                expr: Box::new(make("(a + b)")),
+                name: make("toString"),
+                args: vec![],
+                named_args: vec![],
+                is_safe: false,
+            })
+        );
+        assert_eq!(
+            make::<ShortExpr>("text\n    .trim()\n    ?.length"),
+            ShortExpr::Member(Member {
+                expr: Box::new(make("text.trim()")),
+                name: make("length"),
+                is_safe: true,
+            })
+        );
+    }
+
+    #[test]
+    fn lambda() {
+        assert_eq!(
+            make::<ShortExpr>("{ a, b: Int -> a < b }"),
+            ShortExpr::Lambda(Lambda {
+                params: vec![
+                    LambdaParam {
+                        name: make("a"),
+                        ty: None,
+                    },
+                    LambdaParam {
+                        name: make("b"),
+                        ty: Some(make("Int")),
+                    },
+                ],
+                body: Rc::new(vec![make("a < b")]),
+            })
+        );
+        assert_eq!(
+            make::<ShortExpr>("{\n    println(it)\n    it\n}"),
+            ShortExpr::Lambda(Lambda {
+                params: vec![],
+                body: Rc::new(vec![make("println(it)"), make("it")]),
+            })
+        );
+    }
+
+    #[test]
+    fn trailing_lambda() {
+        assert_eq!(
+            make::<ShortExpr>("arr.forEach { println(it) }"),
+            ShortExpr::MethodCall(MethodCall {
+                expr: Box::new(make("arr")),
+                name: make("forEach"),
+                args: vec![make("{ println(it) }")],
+                named_args: vec![],
+                is_safe: false,
+            })
+        );
+        assert_eq!(
+            make::<ShortExpr>("a.fold(0) { acc, x -> acc + x }"),
+            ShortExpr::MethodCall(MethodCall {
+                expr: Box::new(make("a")),
+                name: make("fold"),
+                args: vec![make("0"), make("{ acc, x -> acc + x }")],
+                named_args: vec![],
+                is_safe: false,
+            })
+        );
+    }
+
+    #[test]
+    fn null_safety() {
+        assert_eq!(
+            make::<ShortExpr>("a?.length!!"),
+            ShortExpr::NotNull(NotNull(Box::new(make("a?.length"))))
+        );
+        assert_eq!(
+            make::<ShortExpr>("a!!.code"),
+            ShortExpr::Member(Member {
+                expr: Box::new(make("a!!")),
+                name: make("code"),
+                is_safe: false,
+            })
+        );
+    }
+
     #[test]
     fn int_literal() {
         let literal: ShortExpr = make("123");
@@ -211,6 +847,61 @@ mod tests {
         assert_eq!(literal, ShortExpr::Literal(Literal::Char('\\')));
     }
 
+    #[test]
+    fn template() {
+        assert_eq!(
+            make::<ShortExpr>(r#""a = $a, sum = ${a + b}!""#),
+            ShortExpr::Template(Template(vec![
+                TemplatePart::Str("a = ".into()),
+                TemplatePart::Expr(make("a")),
+                TemplatePart::Str(", sum = ".into()),
+                TemplatePart::Expr(make("a + b")),
+                TemplatePart::Str("!".into()),
+            ]))
+        );
+
+        assert_eq!(
+            make::<ShortExpr>(r#""${size(arr)}\n""#),
+            ShortExpr::Template(Template(vec![
+                TemplatePart::Expr(make("size(arr)")),
+                TemplatePart::Str("\n".into()),
+            ]))
+        );
+
+        assert_eq!(
+            make::<ShortExpr>(r#""<${"in" + "}"}>""#),
+            ShortExpr::Template(Template(vec![
+                TemplatePart::Str("<".into()),
+                TemplatePart::Expr(make(r#""in" + "}""#)),
+                TemplatePart::Str(">".into()),
+            ]))
+        );
+    }
+
+    #[test]
+    fn template_leftover() {
+        let source = r#""${1 2}""#;
+        let pairs = pairs(source);
+        let err = ShortExpr::try_parse(&pairs).unwrap_err();
+        assert_eq!(err.to_string(), "expected `}`, found number `2`");
+        let span = err.found.unwrap().span;
+        assert_eq!(&source[span.lo..span.hi], "2");
+
+        assert_eq!(
+            fail::<ShortExpr>(r#""sum: ${a +}""#),
+            "expected expression, found `}`"
+        );
+    }
+
+    #[test]
+    fn not_template() {
+        let literal: ShortExpr = make(r#""\$a costs 5$""#);
+        assert_eq!(
+            literal,
+            ShortExpr::Literal(Literal::String("$a costs 5$".into()))
+        );
+    }
+
     #[test]
     fn string_literal() {
         let literal: ShortExpr = make(r#""Hello""#);
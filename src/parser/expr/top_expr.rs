@@ -6,47 +6,84 @@ pub enum TopExpr {
     ControlExpr(ControlExpr),
     Binding(Binding),
     Set(Set),
-    Call(Call),
     SetByIndex(SetByIndex),
+    SetMember(SetMember),
+    Destructuring(Destructuring),
 }
 
 impl TryParse for TopExpr {
-    fn try_parse<'a>(pairs: &'a [Pair<'a>]) -> ParseResult<Self> {
-        let pair = pairs.get(0).ok_or(ParseError::UnexpectedEndOfInput)?;
+    fn try_parse<'a>(pairs: &'a [Pair<'a>]) -> ParseResult<'a, Self> {
+        let pair = expect_pair(pairs, "statement")?;
 
         match pair.token {
-            Token::Ident => {
-                if let Ok((r, pairs)) = try_parse(pairs) {
-                    Ok((TopExpr::Set(r), pairs))
-                } else if let Ok((r, pairs)) = try_parse(pairs) {
-                    Ok((TopExpr::Call(r), pairs))
-                } else if let Ok((r, pairs)) = try_parse(pairs) {
-                    Ok((TopExpr::SetByIndex(r), pairs))
-                } else {
-                    Err(ParseError::WrongExprType(*pair, "TopExpr"))
-                }
-            }
-            Token::If | Token::For | Token::While | Token::Return => {
+            Token::If
+            | Token::For
+            | Token::While
+            | Token::When
+            | Token::Return
+            | Token::Break
+            | Token::Continue => {
                 let (r, pairs) = try_parse(pairs)?;
                 Ok((Self::ControlExpr(r), pairs))
             }
+            Token::Var | Token::Val if expect_symbol(&pairs[1..], '(').is_ok() => {
+                let (destructuring, pairs) = Destructuring::try_parse(pairs)?;
+                Ok((Self::Destructuring(destructuring), pairs))
+            }
             Token::Var | Token::Val => {
                 let (binding, pairs) = Binding::try_parse(pairs)?;
                 Ok((Self::Binding(binding), pairs))
             }
-            _ => Err(ParseError::WrongExprType(*pair, "TopExpr")),
+            // Assignments to variables, items or members, like `this.x = 1`
+            _ => {
+                let (target, rest) = ShortExpr::try_parse(pairs)?;
+                parse_assignment(pairs, target, rest)
+            }
         }
     }
 }
 
+pub fn parse_assignment<'a>(
+    pairs: &'a [Pair<'a>],
+    target: ShortExpr,
+    rest: &'a [Pair<'a>],
+) -> ParseResult<'a, TopExpr> {
+    if !is_assignable(&target) {
+        return Err(ParseError::new(pairs, Expected::Construct("assignment")));
+    }
+    let after_eq = expect_symbol(rest, '=')?;
+    let (expr, after) = try_parse(after_eq)?;
+
+    let top = match target {
+        ShortExpr::Ident(ident) => TopExpr::Set(Set {
+            name: Spanned::consumed(ident, pairs, rest),
+            expr,
+        }),
+        ShortExpr::GetByIndex(get_by_index) => {
+            TopExpr::SetByIndex(SetByIndex { get_by_index, expr })
+        }
+        ShortExpr::Member(member) => TopExpr::SetMember(SetMember { member, expr }),
+        _ => unreachable!("target is assignable"),
+    };
+    Ok((top, after))
+}
+
+pub fn is_assignable(target: &ShortExpr) -> bool {
+    matches!(
+        target,
+        ShortExpr::Ident(_) | ShortExpr::GetByIndex(_) | ShortExpr::Member(_)
+    )
+}
+
 impl std::fmt::Debug for TopExpr {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Self::ControlExpr(child) => child.fmt(f),
             Self::Binding(child) => child.fmt(f),
             Self::Set(child) => child.fmt(f),
-            Self::Call(child) => child.fmt(f),
             Self::SetByIndex(child) => child.fmt(f),
+            Self::SetMember(child) => child.fmt(f),
+            Self::Destructuring(child) => child.fmt(f),
         }
     }
 }
@@ -54,65 +91,95 @@ impl std::fmt::Debug for TopExpr {
 #[derive(Debug, PartialEq)]
 pub struct Binding {
     pub is_mut: bool,
+    pub ty: Option<Spanned<Type>>,
     pub set: Set,
 }
 
 impl TryParse for Binding {
-    fn try_parse<'a>(pairs: &'a [Pair<'a>]) -> ParseResult<Self> {
+    fn try_parse<'a>(pairs: &'a [Pair<'a>]) -> ParseResult<'a, Self> {
+        let start = pairs;
         let (pair, pairs) = if let Ok(res) = expect_token(pairs, Token::Val) {
             res
         } else if let Ok(res) = expect_token(pairs, Token::Var) {
             res
         } else {
-            let pair = pairs.get(1).ok_or(ParseError::UnexpectedEndOfInput)?;
-            return Err(ParseError::WrongExprType(*pair, "Binding"));
+            return Err(ParseError::new(pairs, Token::Val).or(ParseError::new(pairs, Token::Var)));
         };
 
         let is_mut = pair.token == Token::Var;
+        let within = |err: ParseError<'a>| err.within("variable declaration", start);
+
+        let (name, pairs) = try_parse(pairs).map_err(within)?;
+
+        let (ty, pairs) = match expect_symbol(pairs, ':') {
+            Ok(pairs) => {
+                let (ty, pairs) = try_parse(pairs).map_err(within)?;
+                (Some(ty), pairs)
+            }
+            Err(_) => (None, pairs),
+        };
 
-        let (set, pairs) = Set::try_parse(pairs)?;
+        let pairs = expect_symbol(pairs, '=').map_err(within)?;
+        let (expr, pairs) = try_parse(pairs).map_err(within)?;
 
-        let binding = Binding { is_mut, set };
+        let set = Set { name, expr };
+        let binding = Binding { is_mut, ty, set };
 
         Ok((binding, pairs))
     }
 }
 
 #[derive(Debug, PartialEq)]
-pub struct Set {
-    pub name: Spanned<Ident>,
+pub struct Destructuring {
+    pub is_mut: bool,
+    pub names: Vec<Spanned<Ident>>,
     pub expr: BoxedExpr,
 }
 
-impl TryParse for Set {
-    fn try_parse<'a>(pairs: &'a [Pair<'a>]) -> ParseResult<Self> {
-        let (name, pairs) = try_parse(pairs)?;
+impl TryParse for Destructuring {
+    fn try_parse<'a>(pairs: &'a [Pair<'a>]) -> ParseResult<'a, Self> {
+        let is_mut = match pairs.first().map(|pair| pair.token) {
+            Some(Token::Val) => false,
+            Some(Token::Var) => true,
+            _ => {
+                let err = ParseError::new(pairs, Token::Val);
+                return Err(err.or(ParseError::new(pairs, Token::Var)));
+            }
+        };
 
-        let pairs = expect_symbol(pairs, '=')?;
+        let (names, pairs) =
+            expect_sequence(&pairs[1..], '('.into(), ')'.into(), ','.into(), try_parse)?;
 
+        let pairs = expect_symbol(pairs, '=')?;
         let (expr, pairs) = try_parse(pairs)?;
 
-        let set = Set { name, expr };
+        let destructuring = Destructuring {
+            is_mut,
+            names,
+            expr,
+        };
 
-        Ok((set, pairs))
+        Ok((destructuring, pairs))
     }
 }
 
 #[derive(Debug, PartialEq)]
-pub struct Call {
+pub struct Set {
     pub name: Spanned<Ident>,
-    pub args: Vec<Spanned<Expr>>,
+    pub expr: BoxedExpr,
 }
 
-impl TryParse for Call {
-    fn try_parse<'a>(pairs: &'a [Pair<'a>]) -> ParseResult<Self> {
+impl TryParse for Set {
+    fn try_parse<'a>(pairs: &'a [Pair<'a>]) -> ParseResult<'a, Self> {
         let (name, pairs) = try_parse(pairs)?;
 
-        let (args, pairs) = expect_sequence(pairs, '('.into(), ')'.into(), ','.into(), try_parse)?;
+        let pairs = expect_symbol(pairs, '=')?;
 
-        let call = Call { name, args };
+        let (expr, pairs) = try_parse(pairs)?;
 
-        Ok((call, pairs))
+        let set = Set { name, expr };
+
+        Ok((set, pairs))
     }
 }
 
@@ -123,7 +190,7 @@ pub struct SetByIndex {
 }
 
 impl TryParse for SetByIndex {
-    fn try_parse<'a>(pairs: &'a [Pair<'a>]) -> ParseResult<Self> {
+    fn try_parse<'a>(pairs: &'a [Pair<'a>]) -> ParseResult<'a, Self> {
         let (get_by_index, pairs) = try_parse(pairs)?;
 
         let pairs = expect_symbol(pairs, '=')?;
@@ -136,6 +203,12 @@ impl TryParse for SetByIndex {
     }
 }
 
+#[derive(Debug, PartialEq)]
+pub struct SetMember {
+    pub member: Member,
+    pub expr: BoxedExpr,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -167,6 +240,41 @@ mod tests {
                 expr: make("b"),
             })
         );
+        assert_eq!(
+            make::<TopExpr>("m[i][j] = a[i] > temp"),
+            TopExpr::SetByIndex(SetByIndex {
+                get_by_index: make("m[i][j]"),
+                expr: make("a[i] > temp"),
+            })
+        );
+    }
+
+    #[test]
+    fn set_member() {
+        assert_eq!(
+            make::<TopExpr>("this.y = y + 1"),
+            TopExpr::SetMember(SetMember {
+                member: Member {
+                    expr: Box::new(make("this")),
+                    name: make("y"),
+                    is_safe: false,
+                },
+                expr: make("y + 1"),
+            })
+        );
+        make::<TopExpr>("points[0].x = 1");
+    }
+
+    #[test]
+    fn destructuring() {
+        assert_eq!(
+            make::<TopExpr>("val (a, b) = pair"),
+            TopExpr::Destructuring(Destructuring {
+                is_mut: false,
+                names: vec![make("a"), make("b")],
+                expr: make("pair"),
+            })
+        );
     }
 
     #[test]
@@ -176,6 +284,7 @@ mod tests {
             val,
             TopExpr::Binding(Binding {
                 is_mut: false,
+                ty: None,
                 set: make("hello = 0")
             })
         );
@@ -185,8 +294,19 @@ mod tests {
             var,
             TopExpr::Binding(Binding {
                 is_mut: true,
+                ty: None,
                 set: make("test = 4")
             })
         );
+
+        let typed: TopExpr = make("val name: String? = null");
+        assert_eq!(
+            typed,
+            TopExpr::Binding(Binding {
+                is_mut: false,
+                ty: Some(make("String?")),
+                set: make("name = null")
+            })
+        );
     }
 }
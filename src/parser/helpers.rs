@@ -1,20 +1,29 @@
-use super::{parse_error::*, Body};
+use super::recovery::{is_declaration_start, skip_until, syntax_error, Sync};
+use super::{parse_error::*, Body, Spanned};
 use crate::lexer::{Pair, Token};
-use crate::parser::try_parse;
+use crate::parser::expr::{parse_statement, Expr, Ident, Invalid};
 
-pub fn expect_token<'a>(pairs: &'a [Pair<'a>], token: Token) -> ParseResult<Pair> {
-    let pair = pairs.first().ok_or(ParseError::UnexpectedEndOfInput)?;
-    if pair.token == token {
-        Ok((*pair, &pairs[1..]))
-    } else {
-        Err(ParseError::UnexpectedToken(*pair, token))
+pub fn expect_token<'a>(pairs: &'a [Pair<'a>], token: Token) -> ParseResult<'a, Pair<'a>> {
+    match pairs.first() {
+        Some(pair) if pair.token == token => Ok((*pair, &pairs[1..])),
+        _ => Err(ParseError::new(pairs, token)),
     }
 }
 
+pub fn expect_pair<'a>(
+    pairs: &'a [Pair<'a>],
+    construct: &'static str,
+) -> Result<Pair<'a>, ParseError<'a>> {
+    pairs
+        .first()
+        .copied()
+        .ok_or_else(|| ParseError::new(pairs, Expected::Construct(construct)))
+}
+
 pub fn expect_symbol<'a>(
     pairs: &'a [Pair<'a>],
     symbol: char,
-) -> Result<&'a [Pair<'a>], ParseError> {
+) -> Result<&'a [Pair<'a>], ParseError<'a>> {
     let pairs = expect_token(pairs, symbol.into())?;
     Ok(pairs.1)
 }
@@ -25,9 +34,9 @@ pub fn expect_sequence<'a, T, F>(
     end: Token,
     separator: Token,
     parse_seq_el: F,
-) -> ParseResult<Vec<T>>
+) -> ParseResult<'a, Vec<T>>
 where
-    F: Fn(&'a [Pair<'a>]) -> ParseResult<T>,
+    F: Fn(&'a [Pair<'a>]) -> ParseResult<'a, T>,
 {
     let (_, pairs) = expect_token(pairs, start)?;
 
@@ -37,34 +46,27 @@ where
     loop {
         mut_pairs = ignore_newlines(mut_pairs);
 
-        match expect_token(mut_pairs, end) {
+        let end_err = match expect_token(mut_pairs, end) {
             Ok((_, pairs)) => {
                 return Ok((sequence, pairs));
             }
-            Err(err) => match err {
-                ParseError::UnexpectedToken(_, _) => {}
-                _ => return Err(err),
-            },
-        }
+            Err(err) => err,
+        };
 
-        let (element, pairs) = parse_seq_el(mut_pairs)?;
+        let (element, pairs) = parse_seq_el(mut_pairs).map_err(|err| err.or(end_err))?;
         sequence.push(element);
 
-        match expect_token(pairs, separator) {
-            Ok((_, pairs)) => mut_pairs = pairs,
-            Err(err) => match err {
-                ParseError::UnexpectedToken(_, _) => {
-                    mut_pairs = pairs;
-                }
-                _ => return Err(err),
-            },
-        }
+        mut_pairs = match expect_token(pairs, separator) {
+            Ok((_, pairs)) => pairs,
+            Err(_) if expect_token(ignore_newlines(pairs), end).is_ok() => pairs,
+            Err(err) => return Err(err.or(ParseError::new(pairs, end))),
+        };
     }
 }
 
 pub fn ignore_newlines<'a>(mut pairs: &'a [Pair<'a>]) -> &'a [Pair<'a>] {
     loop {
-        match pairs.get(0) {
+        match pairs.first() {
             Some(Pair {
                 token: Token::NewLine,
                 ..
@@ -74,29 +76,87 @@ pub fn ignore_newlines<'a>(mut pairs: &'a [Pair<'a>]) -> &'a [Pair<'a>] {
     }
 }
 
-pub fn expect_body<'a>(pairs: &'a [Pair<'a>]) -> ParseResult<Body> {
+pub fn expect_body<'a>(pairs: &'a [Pair<'a>]) -> ParseResult<'a, Body> {
     let pairs = expect_symbol(pairs, '{')?;
+    expect_statements(pairs)
+}
 
+pub fn expect_statements<'a>(pairs: &'a [Pair<'a>]) -> ParseResult<'a, Body> {
     let mut mut_pairs = pairs;
     let mut sequence = vec![];
 
     loop {
         mut_pairs = ignore_newlines(mut_pairs);
 
-        match expect_symbol(mut_pairs, '}') {
-            Ok(pairs) => {
-                return Ok((sequence, pairs));
+        if let Ok(pairs) = expect_symbol(mut_pairs, '}') {
+            return Ok((sequence, pairs));
+        }
+
+        let close_err = ParseError::new(mut_pairs, '}');
+        let err = if mut_pairs.is_empty() || is_declaration_start(mut_pairs) {
+            // The block isn't closed, the declaration is left to the enclosing parser
+            close_err
+        } else {
+            match expect_statement(mut_pairs).and_then(|(element, pairs)| {
+                expect_statement_end(pairs)?;
+                Ok((element, pairs))
+            }) {
+                Ok((element, pairs)) => {
+                    sequence.push(element);
+                    mut_pairs = pairs;
+                    continue;
+                }
+                Err(err) => err.or(close_err),
             }
-            Err(err) => match err {
-                ParseError::UnexpectedToken(_, _) => {}
-                _ => return Err(err),
-            },
+        };
+
+        let rest = if err.is_end_of_input() {
+            &[]
+        } else if is_declaration_start(mut_pairs) {
+            mut_pairs
+        } else {
+            skip_until(mut_pairs, Sync::Statement)
+        };
+        let binding = match mut_pairs {
+            [declaration, name, ..]
+                if matches!(declaration.token, Token::Val | Token::Var)
+                    && name.token == Token::Ident =>
+            {
+                Some(Ident(name.str().to_owned()))
+            }
+            _ => None,
+        };
+        let invalid = Invalid {
+            error: syntax_error(&err, pairs),
+            binding,
+        };
+        sequence.push(Spanned::consumed(Expr::Invalid(invalid), mut_pairs, rest));
+
+        if rest.is_empty() || is_declaration_start(rest) {
+            return Ok((sequence, rest));
         }
+        mut_pairs = rest;
+    }
+}
 
-        let (element, pairs) = try_parse(mut_pairs)?;
-        sequence.push(element);
+pub fn expect_statement<'a>(pairs: &'a [Pair<'a>]) -> ParseResult<'a, Spanned<Expr>> {
+    let (expr, rest) = parse_statement(pairs)?;
+    Ok((Spanned::consumed(expr, pairs, rest), rest))
+}
 
-        mut_pairs = pairs;
+fn expect_statement_end<'a>(pairs: &'a [Pair<'a>]) -> Result<(), ParseError<'a>> {
+    match pairs.first().map(|pair| pair.token) {
+        None | Some(Token::NewLine | Token::Symbol('}')) => Ok(()),
+        _ => Err(ParseError::new(pairs, Token::NewLine).or(ParseError::new(pairs, '}'))),
+    }
+}
+
+pub fn expect_branch<'a>(pairs: &'a [Pair<'a>]) -> ParseResult<'a, Body> {
+    if expect_symbol(pairs, '{').is_ok() {
+        expect_body(pairs)
+    } else {
+        let (statement, pairs) = expect_statement(pairs)?;
+        Ok((vec![statement], pairs))
     }
 }
 
@@ -104,9 +164,9 @@ pub fn expect_body<'a>(pairs: &'a [Pair<'a>]) -> ParseResult<Body> {
 pub mod test_helpers {
     use super::*;
     use crate::lexer::Lexer;
-    use crate::parser::TryParse;
+    use crate::parser::{try_parse, TryParse};
 
-    pub fn pairs(source: &str) -> Vec<Pair> {
+    pub fn pairs(source: &str) -> Vec<Pair<'_>> {
         Lexer::new(source).collect()
     }
 
@@ -115,10 +175,21 @@ pub mod test_helpers {
         T: TryParse,
     {
         let pairs = &pairs(source);
-        let (res, pairs) = try_parse(&pairs).unwrap_or_else(|e| panic!("{e}"));
+        let (res, pairs) = try_parse(pairs).unwrap_or_else(|e| panic!("{e}"));
         assert!(pairs.is_empty(), "source is not fully parsed");
         res
     }
+
+    pub fn fail<T>(source: &str) -> String
+    where
+        T: TryParse,
+    {
+        let pairs = &pairs(source);
+        match try_parse::<T>(pairs) {
+            Ok(_) => panic!("source is parsed"),
+            Err(err) => err.to_string(),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -174,4 +245,19 @@ mod tests {
             assert!(expect_body(&pairs(body)).unwrap().1.is_empty());
         }
     }
+
+    #[test]
+    fn nested_lambdas() {
+        // Each statement is parsed once, otherwise the time grows exponentially with the depth
+        let depth = 12;
+        let body = format!(
+            "{{\n{}x = 1\n{}}}",
+            "run {\n".repeat(depth),
+            "}\n".repeat(depth)
+        );
+        let pairs = pairs(&body);
+        let (statements, rest) = expect_body(&pairs).unwrap();
+        assert!(rest.is_empty());
+        assert_eq!(statements.len(), 1);
+    }
 }
@@ -1,42 +1,147 @@
 pub mod expr;
 mod helpers;
 mod parse_error;
+mod recovery;
 
 use crate::lexer::*;
 
 use self::helpers::*;
+use self::recovery::*;
 use expr::*;
-pub use parse_error::{ParseError, ParseResult};
+pub use parse_error::{Expected, ParseError, ParseResult, SyntaxError};
 
 #[derive(Debug)]
 pub struct Program {
     pub functions: Vec<Fun>,
+    pub classes: Vec<Class>,
+    pub errors: Vec<SyntaxError>,
 }
 
 #[derive(Debug, PartialEq)]
 pub enum Type {
     Simple(Ident),
     Generic(Ident, Vec<Type>),
+    Nullable(Box<Type>),
+    Function(Vec<Type>, Box<Type>),
 }
 
-pub type Body = Vec<Spanned<TopExpr>>;
+pub type Body = Vec<Spanned<Expr>>;
 pub type BoxedExpr = Box<Spanned<Expr>>;
 
 #[derive(Debug, PartialEq)]
 pub struct Fun {
+    pub name: Spanned<Ident>,
+    pub modifier: Option<MemberModifier>,
+    pub ret_type: Option<Spanned<Type>>,
+    pub args: Vec<(Ident, Spanned<Type>)>,
+    pub body: Option<Body>,
+}
+
+impl Fun {
+    pub fn is(&self, modifier: MemberModifier) -> bool {
+        self.modifier == Some(modifier)
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum MemberModifier {
+    Open,
+    Abstract,
+    Override,
+}
+
+impl MemberModifier {
+    /// Member modifiers are soft keywords too, they are only special before `fun`
+    fn parse(pairs: &[Pair]) -> Option<Self> {
+        let [pair, next, ..] = pairs else {
+            return None;
+        };
+        if pair.token != Token::Ident || next.token != Token::Fun {
+            return None;
+        }
+        match pair.str() {
+            "open" => Some(Self::Open),
+            "abstract" => Some(Self::Abstract),
+            "override" => Some(Self::Override),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Class {
+    pub name: Spanned<Ident>,
+    pub modifier: Option<Modifier>,
+    pub is_interface: bool,
+    pub params: Vec<ClassParam>,
+    pub supertypes: Vec<Supertype>,
+    pub entries: Vec<EnumEntry>,
+    pub properties: Vec<Spanned<Binding>>,
+    pub methods: Vec<Fun>,
+    pub errors: Vec<SyntaxError>,
+}
+
+impl Class {
+    pub fn is(&self, modifier: Modifier) -> bool {
+        self.modifier == Some(modifier)
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Modifier {
+    Data,
+    Sealed,
+    Enum,
+    Open,
+    Abstract,
+}
+
+impl Modifier {
+    const KEYWORDS: [&'static str; 5] = ["data", "enum", "sealed", "open", "abstract"];
+
+    /// Modifiers are soft keywords, they are only special before `class` and `interface`
+    fn parse(pairs: &[Pair]) -> Option<Self> {
+        let [pair, next, ..] = pairs else {
+            return None;
+        };
+        if pair.token != Token::Ident || !matches!(next.token, Token::Class | Token::Interface) {
+            return None;
+        }
+        match pair.str() {
+            "data" => Some(Self::Data),
+            "sealed" => Some(Self::Sealed),
+            "enum" => Some(Self::Enum),
+            "open" => Some(Self::Open),
+            "abstract" => Some(Self::Abstract),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Supertype {
+    pub name: Spanned<Ident>,
+    pub args: Option<Vec<Spanned<Expr>>>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct EnumEntry {
+    pub name: Spanned<Ident>,
+    pub args: Vec<Spanned<Expr>>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct ClassParam {
     pub name: Ident,
-    pub ret_type: Option<Type>,
-    pub args: Vec<(Ident, Type)>,
-    pub body: Body,
+    pub ty: Spanned<Type>,
+    pub is_property: bool,
+    pub is_mut: bool,
 }
 
 pub trait TryParse {
-    fn try_parse<'a>(_pairs: &'a [Pair<'a>]) -> ParseResult<Self>
+    fn try_parse<'a>(pairs: &'a [Pair<'a>]) -> ParseResult<'a, Self>
     where
-        Self: Sized,
-    {
-        Err(ParseError::NotImplementedYet)
-    }
+        Self: Sized;
 }
 
 pub fn try_parse<'a, T>(pairs: &'a [Pair<'a>]) -> ParseResult<'a, T>
@@ -73,19 +178,25 @@ impl<E> TryParse for Spanned<E>
 where
     E: TryParse,
 {
-    fn try_parse<'a>(pairs: &'a [Pair<'a>]) -> ParseResult<Self> {
-        let lo = pairs
-            .first()
-            .ok_or(ParseError::UnexpectedEndOfInput)?
-            .span
-            .lo;
+    fn try_parse<'a>(pairs: &'a [Pair<'a>]) -> ParseResult<'a, Self> {
+        let (expr, rest) = try_parse(pairs)?;
 
-        let (expr, pairs) = try_parse(pairs)?;
+        Ok((Self::consumed(expr, pairs, rest), rest))
+    }
+}
 
-        let hi = pairs.first().map(|p| p.span.lo).unwrap_or(lo);
+impl<E> Spanned<E> {
+    pub fn consumed<'a>(expr: E, pairs: &'a [Pair<'a>], rest: &'a [Pair<'a>]) -> Self {
+        let lo = pairs.first().map(|pair| pair.span.lo).unwrap_or_default();
+
+        let consumed = pairs.len() - rest.len();
+        let hi = consumed
+            .checked_sub(1)
+            .map(|last| pairs[last].span.hi)
+            .unwrap_or(lo);
 
         let span = Span { lo, hi };
-        Ok((Self { span, expr }, pairs))
+        Self { span, expr }
     }
 }
 
@@ -101,67 +212,140 @@ impl<T> TryParse for Box<T>
 where
     T: TryParse,
 {
-    fn try_parse<'a>(pairs: &'a [Pair<'a>]) -> ParseResult<Self> {
+    fn try_parse<'a>(pairs: &'a [Pair<'a>]) -> ParseResult<'a, Self> {
         let (res, pairs) = try_parse(pairs)?;
         Ok((Box::new(res), pairs))
     }
 }
 
 impl TryParse for Program {
-    fn try_parse<'a>(mut pairs: &'a [Pair<'a>]) -> ParseResult<Self> {
-        let mut functions = vec![];
+    fn try_parse<'a>(mut pairs: &'a [Pair<'a>]) -> ParseResult<'a, Self> {
+        let mut program = Self {
+            functions: vec![],
+            classes: vec![],
+            errors: vec![],
+        };
         loop {
             pairs = ignore_newlines(pairs);
-            if pairs.is_empty() {
-                return Ok((Self { functions }, &[]));
-            }
-            let (fun, p) = try_parse(pairs)?;
-            pairs = p;
-            functions.push(fun);
+            let result = match pairs.first() {
+                None => return Ok((program, &[])),
+                Some(pair)
+                    if matches!(pair.token, Token::Class | Token::Interface)
+                        || Modifier::parse(pairs).is_some() =>
+                {
+                    try_parse(pairs).map(|(class, p)| {
+                        program.classes.push(class);
+                        p
+                    })
+                }
+                Some(_) => try_parse(pairs)
+                    .map(|(fun, p)| {
+                        program.functions.push(fun);
+                        p
+                    })
+                    .map_err(|err| {
+                        let declarations = [Token::Class, Token::Interface]
+                            .map(Expected::Token)
+                            .into_iter()
+                            .chain(Modifier::KEYWORDS.map(Expected::Keyword));
+                        declarations.fold(err, |err, expected| {
+                            err.or(ParseError::new(pairs, expected))
+                        })
+                    }),
+            };
+
+            pairs = match result {
+                Ok(p) => p,
+                Err(err) => {
+                    program.errors.push(syntax_error(&err, pairs));
+                    skip_until(pairs, Sync::Declaration)
+                }
+            };
         }
     }
 }
 
 impl TryParse for Type {
-    fn try_parse<'a>(pairs: &'a [Pair<'a>]) -> ParseResult<Self> {
+    fn try_parse<'a>(pairs: &'a [Pair<'a>]) -> ParseResult<'a, Self> {
+        let (ty, pairs) = if expect_symbol(pairs, '(').is_ok() {
+            let (mut types, pairs) =
+                expect_sequence(pairs, '('.into(), ')'.into(), ','.into(), Type::try_parse)?;
+
+            match expect_token(pairs, Token::Arrow) {
+                Ok((_, pairs)) => {
+                    let (ret_type, pairs) = Type::try_parse(pairs)?;
+                    (Self::Function(types, Box::new(ret_type)), pairs)
+                }
+                // Parentheses only group a type, like in `((Int) -> Int)?`
+                Err(err) if types.len() != 1 => return Err(err),
+                Err(_) => (types.remove(0), pairs),
+            }
+        } else {
+            Self::parse_named(pairs)?
+        };
+
+        match expect_symbol(pairs, '?') {
+            Ok(pairs) => Ok((Self::Nullable(Box::new(ty)), pairs)),
+            Err(_) => Ok((ty, pairs)),
+        }
+    }
+}
+
+impl Type {
+    fn parse_named<'a>(pairs: &'a [Pair<'a>]) -> ParseResult<'a, Self> {
         let (ident, pairs) = try_parse(pairs)?;
 
         if expect_symbol(pairs, '<').is_ok() {
             let (types, pairs) =
                 expect_sequence(pairs, '<'.into(), '>'.into(), ','.into(), Type::try_parse)?;
-            let ty = Self::Generic(ident, types);
-            Ok((ty, pairs))
+            Ok((Self::Generic(ident, types), pairs))
         } else {
-            let ty = Self::Simple(ident);
-            Ok((ty, pairs))
+            Ok((Self::Simple(ident), pairs))
         }
     }
 }
 
 impl TryParse for Fun {
-    fn try_parse<'a>(pairs: &'a [Pair<'a>]) -> ParseResult<Self> {
+    fn try_parse<'a>(pairs: &'a [Pair<'a>]) -> ParseResult<'a, Self> {
+        let modifier = MemberModifier::parse(pairs);
+        let pairs = if modifier.is_some() {
+            &pairs[1..]
+        } else {
+            pairs
+        };
+
+        let start = pairs;
+        let within = |err: ParseError<'a>| err.within("function", start);
+
         let (_, pairs) = expect_token(pairs, Token::Fun)?;
-        let (name, pairs) = try_parse(pairs)?;
+        let (name, pairs) = try_parse(pairs).map_err(within)?;
 
         let (args, pairs) = expect_sequence(pairs, '('.into(), ')'.into(), ','.into(), |pairs| {
             let (arg_name, pairs) = try_parse(pairs)?;
             let pairs = expect_symbol(pairs, ':')?;
             let (arg_type, pairs) = try_parse(pairs)?;
             Ok(((arg_name, arg_type), pairs))
-        })?;
+        })
+        .map_err(within)?;
 
         let (ret_type, pairs) = match expect_symbol(pairs, ':') {
             Ok(pairs) => {
-                let (ty, pairs) = try_parse(pairs)?;
+                let (ty, pairs) = try_parse(pairs).map_err(within)?;
                 (Some(ty), pairs)
             }
             Err(_) => (None, pairs),
         };
 
-        let (body, pairs) = expect_body(pairs)?;
+        let (body, pairs) = if expect_symbol(pairs, '{').is_ok() {
+            let (body, pairs) = expect_body(pairs).map_err(within)?;
+            (Some(body), pairs)
+        } else {
+            (None, pairs)
+        };
 
         let fun = Fun {
             name,
+            modifier,
             ret_type,
             args,
             body,
@@ -171,6 +355,159 @@ impl TryParse for Fun {
     }
 }
 
+impl TryParse for Class {
+    fn try_parse<'a>(pairs: &'a [Pair<'a>]) -> ParseResult<'a, Self> {
+        let modifier = Modifier::parse(pairs);
+        let pairs = if modifier.is_some() {
+            &pairs[1..]
+        } else {
+            pairs
+        };
+
+        let start = pairs;
+        let within = |err: ParseError<'a>| err.within("class", start);
+
+        let (is_interface, pairs) = match expect_token(pairs, Token::Interface) {
+            Ok((_, pairs)) => (true, pairs),
+            Err(_) => (false, expect_token(pairs, Token::Class)?.1),
+        };
+        let (name, pairs) = try_parse(pairs).map_err(within)?;
+
+        let (params, pairs) = if expect_symbol(pairs, '(').is_ok() && !is_interface {
+            expect_sequence(pairs, '('.into(), ')'.into(), ','.into(), try_parse).map_err(within)?
+        } else {
+            (vec![], pairs)
+        };
+
+        let mut supertypes = vec![];
+        let mut pairs = pairs;
+        if let Ok(mut p) = expect_symbol(pairs, ':') {
+            loop {
+                let (supertype, rest) = try_parse(p).map_err(within)?;
+                supertypes.push(supertype);
+                match expect_symbol(rest, ',') {
+                    Ok(rest) => p = rest,
+                    Err(_) => break pairs = rest,
+                }
+            }
+        }
+
+        let mut class = Class {
+            name,
+            modifier,
+            is_interface,
+            params,
+            supertypes,
+            entries: vec![],
+            properties: vec![],
+            methods: vec![],
+            errors: vec![],
+        };
+
+        let Ok(mut pairs) = expect_symbol(pairs, '{') else {
+            return Ok((class, pairs));
+        };
+
+        if class.is(Modifier::Enum) {
+            let (entries, rest) = parse_enum_entries(pairs).map_err(within)?;
+            class.entries = entries;
+            pairs = rest;
+        }
+
+        loop {
+            pairs = ignore_newlines(pairs);
+            let pair = pairs
+                .first()
+                .ok_or_else(|| within(ParseError::new(pairs, '}')))?;
+            let result = match pair.token {
+                Token::Symbol('}') => return Ok((class, &pairs[1..])),
+                _ if pair.token == Token::Fun || MemberModifier::parse(pairs).is_some() => {
+                    try_parse(pairs).map(|(method, p)| {
+                        class.methods.push(method);
+                        p
+                    })
+                }
+                Token::Val | Token::Var => try_parse(pairs).map(|(property, p)| {
+                    class.properties.push(property);
+                    p
+                }),
+                _ => Err(ParseError::new(pairs, Expected::Construct("class member"))
+                    .or(ParseError::new(pairs, '}'))),
+            };
+
+            pairs = match result {
+                Ok(p) => p,
+                Err(err) => {
+                    class.errors.push(syntax_error(&err, pairs));
+                    skip_until(pairs, Sync::Statement)
+                }
+            };
+        }
+    }
+}
+
+fn parse_enum_entries<'a>(mut pairs: &'a [Pair<'a>]) -> ParseResult<'a, Vec<EnumEntry>> {
+    let mut entries = vec![];
+    loop {
+        pairs = ignore_newlines(pairs);
+        let pair = pairs.first().ok_or_else(|| ParseError::new(pairs, '}'))?;
+        match pair.token {
+            Token::Symbol('}') => return Ok((entries, pairs)),
+            Token::Symbol(';') => return Ok((entries, &pairs[1..])),
+            _ => {}
+        }
+
+        let (name, rest) = try_parse(pairs)?;
+        let (args, rest) = if expect_symbol(rest, '(').is_ok() {
+            expect_sequence(rest, '('.into(), ')'.into(), ','.into(), try_parse)?
+        } else {
+            (vec![], rest)
+        };
+        entries.push(EnumEntry { name, args });
+
+        pairs = expect_symbol(rest, ',').unwrap_or(rest);
+    }
+}
+
+impl TryParse for Supertype {
+    fn try_parse<'a>(pairs: &'a [Pair<'a>]) -> ParseResult<'a, Self> {
+        let (name, pairs) = try_parse(pairs)?;
+
+        let (args, pairs) = if expect_symbol(pairs, '(').is_ok() {
+            let (args, pairs) =
+                expect_sequence(pairs, '('.into(), ')'.into(), ','.into(), try_parse)?;
+            (Some(args), pairs)
+        } else {
+            (None, pairs)
+        };
+
+        Ok((Supertype { name, args }, pairs))
+    }
+}
+
+impl TryParse for ClassParam {
+    fn try_parse<'a>(pairs: &'a [Pair<'a>]) -> ParseResult<'a, Self> {
+        let (is_property, is_mut, pairs) = match pairs.first().map(|pair| pair.token) {
+            Some(Token::Val) => (true, false, &pairs[1..]),
+            Some(Token::Var) => (true, true, &pairs[1..]),
+            _ => (false, false, pairs),
+        };
+
+        let (name, pairs) = try_parse(pairs)?;
+        let pairs = expect_symbol(pairs, ':')?;
+        let (ty, pairs) = try_parse(pairs)?;
+
+        let param = ClassParam {
+            name,
+            ty,
+            is_property,
+            is_mut,
+        };
+
+        Ok((param, pairs))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -194,21 +531,195 @@ mod tests {
         assert_eq!(ty, Type::Generic(make("Array"), vec![make("Int")]));
     }
 
+    #[test]
+    fn type_nullable() {
+        let ty: Type = make("Array<Int?>?");
+        assert_eq!(
+            ty,
+            Type::Nullable(Box::new(Type::Generic(
+                make("Array"),
+                vec![Type::Nullable(Box::new(make("Int")))]
+            )))
+        );
+    }
+
+    #[test]
+    fn type_function() {
+        let int = || make::<Type>("Int");
+        let ty: Type = make("(Int, Int) -> Boolean");
+        assert_eq!(
+            ty,
+            Type::Function(vec![int(), int()], Box::new(make("Boolean")))
+        );
+
+        let ty: Type = make("((Int) -> Unit)?");
+        assert_eq!(
+            ty,
+            Type::Nullable(Box::new(Type::Function(
+                vec![int()],
+                Box::new(make("Unit"))
+            )))
+        );
+
+        let ty: Type = make("() -> (Int) -> Int");
+        assert_eq!(
+            ty,
+            Type::Function(
+                vec![],
+                Box::new(Type::Function(vec![int()], Box::new(int())))
+            )
+        );
+    }
+
     #[test]
     fn fun() {
         let fun: Fun = make("fun test(array: Array<Int>): Int {}");
 
         assert_eq!(&fun.name.0, "test");
-        assert_eq!(fun.ret_type.unwrap(), make("Int"));
-        assert_eq!(fun.args[..], [(Ident("array".into()), make("Array<Int>"))]);
-        assert!(fun.body.is_empty())
+        assert_eq!(*fun.ret_type.unwrap(), make("Int"));
+        assert_eq!(fun.args[0].0, Ident("array".into()));
+        assert_eq!(*fun.args[0].1, make("Array<Int>"));
+        assert_eq!(fun.body, Some(vec![]));
+        assert_eq!(fun.modifier, None);
+    }
+
+    #[test]
+    fn class() {
+        let class: Class = make(
+            "class Point(val x: Int, var y: Int, scale: Int) {
+                val size = x * scale
+
+                fun len(): Int {
+                    return x + y
+                }
+            }",
+        );
+
+        assert_eq!(class.name, make("Point"));
+        assert_eq!(
+            class.params[1],
+            ClassParam {
+                name: make("y"),
+                ty: make("Int"),
+                is_property: true,
+                is_mut: true,
+            }
+        );
+        assert!(!class.params[2].is_property);
+        assert_eq!(class.properties[0].set, make("size = x * scale"));
+        assert_eq!(class.methods[0].name, make("len"));
+
+        let empty: Class = make("class Empty");
+        assert!(empty.params.is_empty() && empty.methods.is_empty());
+        assert_eq!(empty.modifier, None);
+
+        let data: Class = make("data class Pair(val first: Int, val second: Int)");
+        assert!(data.is(Modifier::Data));
+        assert_eq!(data.params.len(), 2);
+    }
+
+    #[test]
+    fn class_hierarchy() {
+        let shape: Class = make("sealed interface Shape");
+        assert!(shape.is_interface && shape.is(Modifier::Sealed));
+
+        let circle: Class = make("data class Circle(val r: Double) : Shape, Figure(r, 1)");
+        assert_eq!(
+            circle.supertypes,
+            [
+                Supertype {
+                    name: make("Shape"),
+                    args: None
+                },
+                Supertype {
+                    name: make("Figure"),
+                    args: Some(vec![make("r"), make("1")])
+                },
+            ]
+        );
+
+        let color: Class = make(
+            "enum class Color(val rgb: Int) {
+                RED(0xff0000),
+                GREEN(0x00ff00);
+
+                fun hex(): Int {
+                    return rgb
+                }
+            }",
+        );
+        assert_eq!(color.entries[0].name, make("RED"));
+        assert_eq!(color.entries[1].args, [make::<Spanned<Expr>>("0x00ff00")]);
+        assert_eq!(color.methods.len(), 1);
+
+        let plain: Class = make("enum class Direction { UP, DOWN, }");
+        assert_eq!(plain.entries.len(), 2);
+    }
+
+    #[test]
+    fn inheritance() {
+        let animal: Class = make(
+            "abstract class Animal(val name: String) : Named {
+                abstract fun sound(): String
+                open fun describe(): String {
+                    return name
+                }
+                override fun id(): Int {
+                    return 0
+                }
+            }",
+        );
+        assert!(animal.is(Modifier::Abstract));
+
+        let methods = &animal.methods;
+        assert!(methods[0].is(MemberModifier::Abstract) && methods[0].body.is_none());
+        assert!(methods[1].is(MemberModifier::Open) && methods[1].body.is_some());
+        assert!(methods[2].is(MemberModifier::Override));
+
+        let base: Class = make("open class Base");
+        assert!(base.is(Modifier::Open));
+
+        let named: Class = make("interface Named { fun id(): Int }");
+        assert_eq!(named.methods[0].body, None);
+    }
+
+    #[test]
+    fn program_error() {
+        let program: Program = make("fun main() {}\nval x = 1");
+        let errors: Vec<_> = program
+            .syntax_errors()
+            .iter()
+            .map(|error| error.message.as_str())
+            .collect();
+        assert_eq!(
+            errors,
+            ["expected `fun`, `class`, `interface`, `data`, `enum`, `sealed`, `open` or `abstract`, found keyword `val`"]
+        );
     }
 
     #[test]
     fn program() {
-        make::<Program>(include_str!("../samples/hello.kt"));
-        make::<Program>(include_str!("../samples/arrays.kt"));
-        make::<Program>(include_str!("../samples/factorial.kt"));
-        make::<Program>(include_str!("../samples/sort.kt"));
+        fn parse(source: &str) {
+            let program: Program = make(source);
+            assert!(program.syntax_errors().is_empty());
+        }
+
+        parse(include_str!("../samples/hello.kt"));
+        parse(include_str!("../samples/arrays.kt"));
+        parse(include_str!("../samples/factorial.kt"));
+        parse(include_str!("../samples/sort.kt"));
+        parse(include_str!("../samples/loops.kt"));
+        parse(include_str!("../samples/when.kt"));
+        parse(include_str!("../samples/numbers.kt"));
+        parse(include_str!("../samples/nullable.kt"));
+        parse(include_str!("../samples/classes.kt"));
+        parse(include_str!("../samples/data.kt"));
+        parse(include_str!("../samples/enums.kt"));
+        parse(include_str!("../samples/sealed.kt"));
+        parse(include_str!("../samples/inheritance.kt"));
+        parse(include_str!("../samples/lambdas.kt"));
+        parse(include_str!("../samples/collections.kt"));
+        parse(include_str!("../samples/strings.kt"));
+        parse(include_str!("../samples/matrix.kt"));
     }
 }
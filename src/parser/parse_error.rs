@@ -1,36 +1,242 @@
 use std::error::Error;
 use std::fmt::Display;
 
-use crate::lexer::{Pair, Token};
+use crate::lexer::{Pair, Span, Token};
 
-#[derive(Debug)]
-pub enum ParseError<'a> {
-    UnexpectedEndOfInput,
-    NotImplementedYet,
-    WrongExprType(Pair<'a>, &'static str),
-    UnexpectedToken(Pair<'a>, Token),
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Expected {
+    Token(Token),
+    /// Soft keyword, which is lexed as an identifier
+    Keyword(&'static str),
+    Construct(&'static str),
+}
+
+impl From<Token> for Expected {
+    fn from(token: Token) -> Self {
+        Expected::Token(token)
+    }
+}
+
+impl From<char> for Expected {
+    fn from(symbol: char) -> Self {
+        Expected::Token(Token::Symbol(symbol))
+    }
 }
 
-impl<'a> Display for ParseError<'a> {
+impl Display for Expected {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            ParseError::UnexpectedEndOfInput => write!(f, "Unexpected end of input"),
-            ParseError::NotImplementedYet => write!(f, "Not implemented yer"),
-            ParseError::WrongExprType(_, expected) => {
-                write!(f, "Wrong expression type, expected {expected}")
+            Expected::Token(token) => write!(f, "{}", describe_token(*token)),
+            Expected::Keyword(keyword) => write!(f, "`{keyword}`"),
+            Expected::Construct(name) => write!(f, "{name}"),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct ParseError<'a> {
+    /// Pair where parsing failed, `None` at the end of input
+    pub found: Option<Pair<'a>>,
+    pub expected: Vec<Expected>,
+    /// Innermost construct the error happened in, spanning from its start to the error
+    pub enclosing: Option<(&'static str, Span)>,
+}
+
+impl<'a> ParseError<'a> {
+    pub fn new(pairs: &[Pair<'a>], expected: impl Into<Expected>) -> Self {
+        Self {
+            found: pairs.first().copied(),
+            expected: vec![expected.into()],
+            enclosing: None,
+        }
+    }
+
+    pub fn is_end_of_input(&self) -> bool {
+        self.found.is_none()
+    }
+
+    fn position(&self) -> usize {
+        self.found.map(|pair| pair.span.lo).unwrap_or(usize::MAX)
+    }
+
+    /// Error of whichever alternative got further, at the same position alternatives are merged
+    pub fn or(mut self, other: Self) -> Self {
+        match self.position().cmp(&other.position()) {
+            std::cmp::Ordering::Less => other,
+            std::cmp::Ordering::Greater => self,
+            std::cmp::Ordering::Equal => {
+                for expected in other.expected {
+                    if !self.expected.contains(&expected) {
+                        self.expected.push(expected);
+                    }
+                }
+                self.enclosing = self.enclosing.or(other.enclosing);
+                self
             }
-            ParseError::UnexpectedToken(pair, token) => {
-                let unexpected_token = pair.token;
-                let string = pair.str();
-                write!(
-                    f,
-                    "Expected {token:?} but got {unexpected_token:?} : {string}"
-                )
+        }
+    }
+
+    pub fn within(mut self, construct: &'static str, pairs: &[Pair]) -> Self {
+        if self.enclosing.is_some() {
+            return self;
+        }
+        let (Some(start), Some(last)) = (pairs.first(), pairs.last()) else {
+            return self;
+        };
+        let hi = self.found.unwrap_or(*last).span.hi;
+        self.enclosing = Some((
+            construct,
+            Span {
+                lo: start.span.lo,
+                hi,
+            },
+        ));
+        self
+    }
+
+    pub fn to_syntax_error(&self, end: Span) -> SyntaxError {
+        let span = self.found.map(|pair| pair.span).unwrap_or(end);
+        let note = self
+            .enclosing
+            .map(|(construct, span)| (span, format!("while parsing this {construct}")));
+        SyntaxError {
+            span,
+            message: self.to_string(),
+            note,
+        }
+    }
+}
+
+impl Display for ParseError<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "expected ")?;
+        for (index, expected) in self.expected.iter().enumerate() {
+            match index {
+                0 => {}
+                _ if index + 1 == self.expected.len() => write!(f, " or ")?,
+                _ => write!(f, ", ")?,
             }
+            write!(f, "{expected}")?;
+        }
+        match self.found {
+            Some(pair) => write!(f, ", found {}", describe_pair(&pair)),
+            None => write!(f, ", found end of input"),
         }
     }
 }
 
 impl Error for ParseError<'_> {}
 
+fn describe_token(token: Token) -> String {
+    let text = match token {
+        Token::Ident => return "identifier".to_string(),
+        Token::Char => return "character".to_string(),
+        Token::Str => return "string".to_string(),
+        Token::Int(_) | Token::Long(_) | Token::Float(_) | Token::Double(_) => {
+            return "number".to_string()
+        }
+        Token::Bool(_) => return "boolean".to_string(),
+        Token::NewLine => return "end of line".to_string(),
+        Token::End => return "end of input".to_string(),
+        Token::WhiteSpace | Token::Unexpected => return "unexpected character".to_string(),
+        Token::Symbol(symbol) => return format!("`{symbol}`"),
+        Token::AndOp => "&&",
+        Token::OrOp => "||",
+        Token::EqOp => "==",
+        Token::NotEqOp => "!=",
+        Token::LessEqOp => "<=",
+        Token::MoreEqOp => ">=",
+        Token::RangeOp => "..",
+        Token::Arrow => "->",
+        Token::SafeCall => "?.",
+        Token::Elvis => "?:",
+        Token::NotNullOp => "!!",
+        Token::Fun => "fun",
+        Token::If => "if",
+        Token::Else => "else",
+        Token::For => "for",
+        Token::In => "in",
+        Token::While => "while",
+        Token::When => "when",
+        Token::Var => "var",
+        Token::Val => "val",
+        Token::Return => "return",
+        Token::Break => "break",
+        Token::Continue => "continue",
+        Token::Null => "null",
+        Token::Class => "class",
+        Token::Interface => "interface",
+        Token::This => "this",
+        Token::Is => "is",
+    };
+    format!("`{text}`")
+}
+
+fn describe_pair(pair: &Pair) -> String {
+    let text = pair.str();
+    match pair.token {
+        Token::Ident => format!("identifier `{text}`"),
+        Token::Int(_) | Token::Long(_) | Token::Float(_) | Token::Double(_) => {
+            format!("number `{text}`")
+        }
+        Token::Char => format!("character {text}"),
+        Token::Str => format!("string {text}"),
+        Token::Bool(_) => format!("boolean `{text}`"),
+        Token::Unexpected => format!("unexpected character `{text}`"),
+        Token::Fun
+        | Token::If
+        | Token::Else
+        | Token::For
+        | Token::In
+        | Token::While
+        | Token::When
+        | Token::Var
+        | Token::Val
+        | Token::Return
+        | Token::Break
+        | Token::Continue
+        | Token::Null
+        | Token::Class
+        | Token::Interface
+        | Token::This
+        | Token::Is => format!("keyword `{text}`"),
+        token => describe_token(token),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SyntaxError {
+    pub span: Span,
+    pub message: String,
+    pub note: Option<(Span, String)>,
+}
+
 pub type ParseResult<'a, T> = Result<(T, &'a [Pair<'a>]), ParseError<'a>>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::helpers::test_helpers::pairs;
+
+    #[test]
+    fn message() {
+        let pairs = pairs("foo )");
+        let error = ParseError::new(&pairs[2..], ')').or(ParseError::new(&pairs[2..], ','));
+        assert_eq!(error.to_string(), "expected `)` or `,`, found end of input");
+
+        let error = ParseError::new(&pairs[..], Expected::Construct("expression"));
+        assert_eq!(
+            error.to_string(),
+            "expected expression, found identifier `foo`"
+        );
+    }
+
+    #[test]
+    fn furthest() {
+        let pairs = pairs("a + )");
+        let near = ParseError::new(&pairs[1..], '=');
+        let far = ParseError::new(&pairs[2..], Expected::Construct("expression"));
+        let error = near.or(far).or(ParseError::new(&pairs[..], Token::Val));
+        assert_eq!(error.to_string(), "expected expression, found `)`");
+    }
+}
@@ -0,0 +1,405 @@
+//! Panic-mode error recovery: a part of the program that failed to parse is skipped
+//! up to a synchronisation point, and the parser continues after it
+
+use super::*;
+
+#[derive(Clone, Copy)]
+pub enum Sync {
+    /// Newline or `}` of the enclosing block, for statements and class members
+    Statement,
+    /// Start of the next declaration, for top-level declarations
+    Declaration,
+}
+
+/// Skips at least one pair, blocks opened in the skipped part are skipped until they close.
+/// Parentheses are not tracked, so an unclosed one doesn't hide the rest of the block,
+/// and `fun` and `class` end any skipped part, because declarations never nest in statements
+pub fn skip_until<'a>(pairs: &'a [Pair<'a>], sync: Sync) -> &'a [Pair<'a>] {
+    let mut braces = 0usize;
+
+    for (index, pair) in pairs.iter().enumerate() {
+        if index > 0 {
+            let is_sync = match pair.token {
+                _ if is_declaration_start(&pairs[index..]) => true,
+                Token::NewLine | Token::Symbol('}') => {
+                    braces == 0 && matches!(sync, Sync::Statement)
+                }
+                _ => false,
+            };
+            if is_sync {
+                return &pairs[index..];
+            }
+        }
+
+        match pair.token {
+            Token::Symbol('{') => braces += 1,
+            Token::Symbol('}') => braces = braces.saturating_sub(1),
+            _ => {}
+        }
+    }
+
+    &[]
+}
+
+pub fn is_declaration_start(pairs: &[Pair]) -> bool {
+    match pairs.first() {
+        Some(pair) => {
+            matches!(pair.token, Token::Fun | Token::Class | Token::Interface)
+                || Modifier::parse(pairs).is_some()
+                || MemberModifier::parse(pairs).is_some()
+        }
+        None => false,
+    }
+}
+
+pub fn syntax_error(error: &ParseError, pairs: &[Pair]) -> SyntaxError {
+    let end = pairs
+        .last()
+        .map(|pair| Span {
+            lo: pair.span.hi,
+            hi: pair.span.hi,
+        })
+        .unwrap_or(Span { lo: 0, hi: 0 });
+    error.to_syntax_error(end)
+}
+
+impl Program {
+    pub fn syntax_errors(&self) -> Vec<&SyntaxError> {
+        let mut errors: Vec<_> = self.errors.iter().collect();
+
+        for class in &self.classes {
+            errors.extend(&class.errors);
+            for property in &class.properties {
+                expr_errors(&property.set.expr, &mut errors);
+            }
+            for entry in &class.entries {
+                exprs_errors(&entry.args, &mut errors);
+            }
+            for supertype in &class.supertypes {
+                exprs_errors(supertype.args.iter().flatten(), &mut errors);
+            }
+            for method in &class.methods {
+                exprs_errors(method.body.iter().flatten(), &mut errors);
+            }
+        }
+        for fun in &self.functions {
+            exprs_errors(fun.body.iter().flatten(), &mut errors);
+        }
+
+        errors.sort_by_key(|error| error.span.lo);
+        errors
+    }
+}
+
+fn exprs_errors<'a>(
+    exprs: impl IntoIterator<Item = &'a Spanned<Expr>>,
+    errors: &mut Vec<&'a SyntaxError>,
+) {
+    for expr in exprs {
+        expr_errors(expr, errors);
+    }
+}
+
+fn expr_errors<'a>(expr: &'a Expr, errors: &mut Vec<&'a SyntaxError>) {
+    match expr {
+        Expr::Invalid(Invalid { error, .. }) => errors.push(error),
+        Expr::ComparisonExpr(expr) => {
+            let (left, right) = match expr {
+                ComparisonExpr::And(And { left, right })
+                | ComparisonExpr::Or(Or { left, right })
+                | ComparisonExpr::Equal(Equal { left, right })
+                | ComparisonExpr::NotEqual(NotEqual { left, right })
+                | ComparisonExpr::LessThan(LessThan { left, right })
+                | ComparisonExpr::MoreThan(MoreThan { left, right })
+                | ComparisonExpr::LessOrEqual(LessOrEqual { left, right })
+                | ComparisonExpr::MoreOrEqual(MoreOrEqual { left, right }) => (left, right),
+            };
+            expr_errors(left, errors);
+            expr_errors(right, errors);
+        }
+        Expr::Elvis(Elvis { left, right }) => {
+            expr_errors(left, errors);
+            expr_errors(right, errors);
+        }
+        Expr::MathExpr(expr) => match expr {
+            MathExpr::Neg(Neg(expr)) | MathExpr::BoolNeg(BoolNeg(expr)) => {
+                expr_errors(expr, errors)
+            }
+            MathExpr::Range(Range { left, right })
+            | MathExpr::Sub(Sub { left, right })
+            | MathExpr::Add(Add { left, right })
+            | MathExpr::Mul(Mul { left, right })
+            | MathExpr::Div(Div { left, right })
+            | MathExpr::Rem(Rem { left, right }) => {
+                expr_errors(left, errors);
+                expr_errors(right, errors);
+            }
+        },
+        Expr::TopExpr(expr) => top_expr_errors(expr, errors),
+        Expr::ShortExpr(expr) => short_expr_errors(expr, errors),
+    }
+}
+
+fn top_expr_errors<'a>(expr: &'a TopExpr, errors: &mut Vec<&'a SyntaxError>) {
+    match expr {
+        TopExpr::ControlExpr(expr) => match expr {
+            ControlExpr::If(If {
+                expr,
+                body,
+                else_branch,
+            }) => {
+                expr_errors(expr, errors);
+                exprs_errors(body, errors);
+                exprs_errors(else_branch, errors);
+            }
+            ControlExpr::For(For { iterable, body, .. }) => {
+                expr_errors(iterable, errors);
+                exprs_errors(body, errors);
+            }
+            ControlExpr::While(While { expr, body }) => {
+                expr_errors(expr, errors);
+                exprs_errors(body, errors);
+            }
+            ControlExpr::When(When {
+                subject,
+                branches,
+                else_branch,
+            }) => {
+                exprs_errors(subject.iter().map(|subject| subject.as_ref()), errors);
+                for branch in branches {
+                    for condition in &branch.conditions {
+                        match &condition.expr {
+                            WhenCondition::Expr(expr) => expr_errors(expr, errors),
+                            WhenCondition::In(expr) => expr_errors(expr, errors),
+                            WhenCondition::Is(_) => {}
+                        }
+                    }
+                    exprs_errors(&branch.body, errors);
+                }
+                exprs_errors(else_branch.iter().flatten(), errors);
+            }
+            ControlExpr::Return(Return(expr)) => {
+                exprs_errors(expr.iter().map(|expr| expr.as_ref()), errors)
+            }
+            ControlExpr::Break(_) | ControlExpr::Continue(_) => {}
+        },
+        TopExpr::Binding(Binding { set, .. }) => expr_errors(&set.expr, errors),
+        TopExpr::Set(Set { expr, .. }) | TopExpr::Destructuring(Destructuring { expr, .. }) => {
+            expr_errors(expr, errors)
+        }
+        TopExpr::SetByIndex(SetByIndex { get_by_index, expr }) => {
+            short_expr_errors(&get_by_index.expr, errors);
+            expr_errors(&get_by_index.index, errors);
+            expr_errors(expr, errors);
+        }
+        TopExpr::SetMember(SetMember { member, expr }) => {
+            short_expr_errors(&member.expr, errors);
+            expr_errors(expr, errors);
+        }
+    }
+}
+
+fn short_expr_errors<'a>(expr: &'a ShortExpr, errors: &mut Vec<&'a SyntaxError>) {
+    match expr {
+        ShortExpr::Call(Call { args, .. }) => exprs_errors(args, errors),
+        ShortExpr::Parens(Parens(expr)) => expr_errors(expr, errors),
+        ShortExpr::GetByIndex(GetByIndex { expr, index }) => {
+            short_expr_errors(expr, errors);
+            expr_errors(index, errors);
+        }
+        ShortExpr::Template(Template(parts)) => {
+            for part in parts {
+                if let TemplatePart::Expr(expr) = part {
+                    expr_errors(expr, errors);
+                }
+            }
+        }
+        ShortExpr::Member(Member { expr, .. }) | ShortExpr::NotNull(NotNull(expr)) => {
+            short_expr_errors(expr, errors)
+        }
+        ShortExpr::MethodCall(MethodCall {
+            expr,
+            args,
+            named_args,
+            ..
+        }) => {
+            short_expr_errors(expr, errors);
+            exprs_errors(args, errors);
+            exprs_errors(named_args.iter().map(|(_, arg)| arg), errors);
+        }
+        ShortExpr::Invoke(Invoke { expr, args }) => {
+            short_expr_errors(expr, errors);
+            exprs_errors(args, errors);
+        }
+        ShortExpr::Lambda(Lambda { body, .. }) => exprs_errors(body.iter(), errors),
+        ShortExpr::Ident(_) | ShortExpr::Literal(_) | ShortExpr::This => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use helpers::test_helpers::*;
+
+    fn errors<'a>(source: &'a str, program: &'a Program) -> Vec<(&'a str, &'a str)> {
+        let errors = program.syntax_errors();
+        errors
+            .iter()
+            .map(|error| {
+                (
+                    &source[error.span.lo..error.span.hi],
+                    error.message.as_str(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn statements() {
+        let source = "fun main() {
+                val a = 1 +* 2
+                println(a)
+                if (a > ) {
+                    println(a)
+                }
+                val b = (2
+                println(b)
+            }";
+        let program: Program = make(source);
+
+        assert_eq!(
+            errors(source, &program),
+            [
+                ("*", "expected expression, found `*`"),
+                (")", "expected expression, found `)`"),
+                ("println", "expected `)`, found identifier `println`"),
+            ]
+        );
+
+        let notes: Vec<_> = program
+            .syntax_errors()
+            .iter()
+            .map(|error| error.note.as_ref().unwrap().1.as_str())
+            .collect();
+        assert_eq!(
+            notes,
+            [
+                "while parsing this variable declaration",
+                "while parsing this if expression",
+                "while parsing this parenthesized expression",
+            ]
+        );
+
+        let body = program.functions[0].body.as_ref().unwrap();
+        assert_eq!(body.len(), 5);
+        assert!(matches!(
+            &body[0].expr,
+            Expr::Invalid(Invalid {
+                binding: Some(Ident(name)),
+                ..
+            }) if name == "a"
+        ));
+        assert_eq!(body[1], make("println(a)"));
+        assert_eq!(body[4], make("println(b)"));
+    }
+
+    #[test]
+    fn nested_blocks() {
+        let source = "fun main() {
+                for (i in 0..10) {
+                    while (true) {
+                        val = 1
+                    }
+                    println(i)
+                }
+            }";
+        let program: Program = make(source);
+
+        assert_eq!(
+            errors(source, &program),
+            [("=", "expected identifier, found `=`")]
+        );
+
+        let lo = source.find("val =").unwrap();
+        let error = SyntaxError {
+            span: Span {
+                lo: lo + "val ".len(),
+                hi: lo + "val =".len(),
+            },
+            message: "expected identifier, found `=`".to_string(),
+            note: Some((
+                Span {
+                    lo,
+                    hi: lo + "val =".len(),
+                },
+                "while parsing this variable declaration".to_string(),
+            )),
+        };
+        assert_eq!(program.syntax_errors(), [&error]);
+    }
+
+    #[test]
+    fn declarations() {
+        let source = "fun broken(a Int) {
+                println(a)
+            }
+
+            class Point(val x: Int) {
+                val y = x +
+                fun len(): Int {
+                    return x
+                }
+            }
+
+            fun unclosed() {
+                println(1
+
+            fun main() {
+                println(0)
+            }";
+        let program: Program = make(source);
+
+        assert_eq!(program.errors.len(), 1);
+        assert_eq!(program.classes[0].errors.len(), 1);
+        assert_eq!(program.classes[0].methods.len(), 1);
+
+        let names: Vec<_> = program.functions.iter().map(|fun| &fun.name.0).collect();
+        assert_eq!(names, ["unclosed", "main"]);
+        assert_eq!(
+            errors(source, &program),
+            [
+                ("Int", "expected `:`, found identifier `Int`"),
+                ("fun", "expected expression, found keyword `fun`"),
+                ("\n", "expected `,` or `)`, found end of line"),
+                ("fun", "expected `}`, found keyword `fun`"),
+            ]
+        );
+    }
+
+    #[test]
+    fn trailing_tokens() {
+        let source = "fun main() {
+                val a = 1 2
+                println(a) a
+                a[0] = 1 2
+                println(a)
+            }
+            }";
+        let program: Program = make(source);
+
+        assert_eq!(
+            errors(source, &program),
+            [
+                ("2", "expected end of line or `}`, found number `2`"),
+                ("a", "expected end of line or `}`, found identifier `a`"),
+                ("2", "expected end of line or `}`, found number `2`"),
+                (
+                    "}",
+                    "expected `fun`, `class`, `interface`, `data`, `enum`, `sealed`, `open` or `abstract`, found `}`"
+                ),
+            ]
+        );
+        let body = program.functions[0].body.as_ref().unwrap();
+        assert_eq!(body[3], make("println(a)"));
+    }
+}
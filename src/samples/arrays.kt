@@ -1,11 +1,3 @@
-fun size(arr: Array<Int>): Int {
-    var size = 0
-    for (el in arr) {
-        size = size + 1
-    }
-    return size
-}
-
 fun printArray(array: Array<Int>) {
     println("[")
     for (item in array) {
@@ -30,23 +22,10 @@ fun mul(numbers: Array<Int>): Int {
     return mul
 }
 
-fun eq(l: Int, r: Int): Boolean {
-    return !(l < r || l > r)
-}
-
-fun contains(arr: Array<Int>, item: Int): Boolean {
-    for (i in arr) {
-        if (eq(i, item)) {
-            return true
-        }
-    }
-    return false
-}
-
 fun withoutDuplicates(arr: Array<Int>): Array<Int> {
     var res = arrayOf(arr[0])
     for (i in arr) {
-        if (!contains(res, i)) {
+        if (!res.contains(i)) {
             res = res + arrayOf(i)
         }
     }
@@ -54,9 +33,7 @@ fun withoutDuplicates(arr: Array<Int>): Array<Int> {
 }
 
 fun insertionSort(arr: Array<Int>) {
-    val lastIndex = size(arr) - 1
-
-    for (i in 1..lastIndex) {
+    for (i in 1..arr.lastIndex) {
         val temp = arr[i]
         var holePosition = i
 
@@ -69,7 +46,7 @@ fun insertionSort(arr: Array<Int>) {
 }
 
 fun uberFun(left: Array<Int>, right: Array<Int>) {
-    val array = left + right
+    var array = left + right
     insertionSort(array)
     array = withoutDuplicates(array)
 
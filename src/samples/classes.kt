@@ -0,0 +1,41 @@
+class Point(val x: Int, var y: Int) {
+    val sum = x + y
+
+    fun len(): Int {
+        return x * x + y * y
+    }
+
+    fun moveBy(dx: Int, dy: Int) {
+        this.y = y + dy
+        println("moved by " + dx + ", " + dy)
+    }
+}
+
+class Segment(val start: Point, val end: Point) {
+    fun len(): Int {
+        val dx = end.x - start.x
+        val dy = end.y - start.y
+        return dx * dx + dy * dy
+    }
+}
+
+fun origin(): Point {
+    return Point(0, 0)
+}
+
+fun main() {
+    val p = Point(3, 4)
+    println(p.len())
+    println(p.sum)
+
+    p.moveBy(0, 1)
+    p.y = p.y * 2
+    println(p.y)
+
+    val segment = Segment(origin(), p)
+    println(segment.len())
+    println(segment.end == p)
+
+    val maybe: Point? = null
+    println(maybe?.len() ?: -1)
+}
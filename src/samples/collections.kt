@@ -0,0 +1,35 @@
+fun main() {
+    val numbers = arrayOf(5, 3, 8, 1, 9, 2)
+    println("size = ${numbers.size}, lastIndex = ${numbers.lastIndex}")
+    println("sum = ${numbers.sum()}, min = ${numbers.min()}, max = ${numbers.max()}")
+    println(numbers.sorted().joinToString(" < "))
+    println(numbers.reversed().joinToString())
+    println("index of 8 is ${numbers.indexOf(8)}, index of 7 is ${numbers.indexOf(7)}")
+    println("contains 9: ${numbers.contains(9)}")
+
+    for (i in numbers.indices) {
+        numbers[i] = numbers[i] * 10
+    }
+    println(numbers.joinToString(", "))
+
+    val copy = numbers.copyOf()
+    copy.fill(0)
+    println("copy: ${copy.joinToString()}, original: ${numbers.joinToString()}")
+
+    val zeros = IntArray(3)
+    println(zeros.joinToString())
+
+    val squares = Array(5) { it * it }
+    println(squares.joinToString())
+
+    val labels = Array(3) { i -> "item$i" }
+    println(labels.joinToString(" | "))
+
+    val words = arrayOf("pear", "apple", "fig")
+    println("${words.sorted().joinToString()} from ${words.min()} to ${words.max()}")
+
+    val prices = DoubleArray(2)
+    prices[0] = 1.5
+    prices[1] = 2.25
+    println(prices.sum())
+}
@@ -0,0 +1,32 @@
+data class User(val name: String, val age: Int)
+
+data class Line(val from: User, val to: User, var weight: Double) {
+    fun describe(): String {
+        return from.name + " -> " + to.name
+    }
+}
+
+fun older(user: User): User {
+    return user.copy(age = user.age + 1)
+}
+
+fun main() {
+    val alice = User("Alice", 30)
+    println(alice)
+    val twin = User("Alice", 30)
+    println(alice == twin)
+    println(alice.hashCode() == twin.hashCode())
+
+    val (name, age) = older(alice)
+    println(name + " is " + age)
+
+    val bob = alice.copy("Bob")
+    println(bob.equals(alice))
+    println(bob.toString())
+
+    val line = Line(alice, bob, 1.5)
+    line.weight = line.weight * 2
+    println(line)
+    println(line.describe())
+    println(line.component3())
+}
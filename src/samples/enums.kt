@@ -0,0 +1,41 @@
+enum class Color(val hex: String) {
+    RED("#ff0000"),
+    GREEN("#00ff00"),
+    BLUE("#0000ff");
+
+    fun isWarm(): Boolean {
+        return this == RED
+    }
+}
+
+enum class Direction {
+    NORTH, EAST, SOUTH, WEST
+}
+
+fun turn(direction: Direction): Direction {
+    return when (direction) {
+        Direction.NORTH -> Direction.EAST
+        Direction.EAST -> Direction.SOUTH
+        Direction.SOUTH -> Direction.WEST
+        Direction.WEST -> Direction.NORTH
+    }
+}
+
+fun main() {
+    for (color in Color.values()) {
+        println(color.name + " " + color.ordinal + " " + color.hex)
+    }
+
+    val green = Color.valueOf("GREEN")
+    println(green == Color.GREEN)
+    println(green.isWarm())
+
+    val red = Color.RED
+    println(red.isWarm())
+
+    var direction = Direction.NORTH
+    for (i in 1..5) {
+        direction = turn(direction)
+    }
+    println(direction)
+}
@@ -0,0 +1,75 @@
+interface Named {
+    fun name(): String
+
+    fun greet(): String {
+        return "Hello, " + name()
+    }
+}
+
+abstract class Animal(val nick: String) : Named {
+    abstract fun sound(): String
+
+    open fun legs(): Int {
+        return 4
+    }
+
+    override fun name(): String {
+        return nick
+    }
+
+    fun describe(): String {
+        return "$nick says ${sound()} on ${legs()} legs"
+    }
+}
+
+class Dog(nick: String) : Animal(nick) {
+    override fun sound(): String {
+        return "woof"
+    }
+}
+
+open class Bird(nick: String) : Animal(nick) {
+    override fun sound(): String {
+        return "tweet"
+    }
+
+    override fun legs(): Int {
+        return 2
+    }
+}
+
+class Parrot(nick: String, val word: String) : Bird(nick) {
+    override fun sound(): String {
+        return word
+    }
+
+    override fun greet(): String {
+        return word + ", " + name()
+    }
+
+    override fun toString(): String {
+        return "Parrot $nick"
+    }
+}
+
+fun loudest(animals: Array<Animal>): Animal {
+    var loudest = animals[0]
+    for (animal in animals) {
+        if (animal.sound().length > loudest.sound().length) {
+            loudest = animal
+        }
+    }
+    return loudest
+}
+
+fun main() {
+    val animals: Array<Animal> = arrayOf(Dog("Rex"), Bird("Tweety"), Parrot("Polly", "cracker"))
+    for (animal in animals) {
+        println(animal.describe())
+        println(animal.greet())
+    }
+
+    val named: Named = loudest(animals)
+    println(named)
+    println(named.name())
+}
@@ -0,0 +1,57 @@
+fun applyTwice(x: Int, f: (Int) -> Int): Int {
+    return f(f(x))
+}
+
+fun repeat(times: Int, action: (Int) -> Unit) {
+    for (i in 0..times - 1) {
+        action(i)
+    }
+}
+
+fun makeCounter(): () -> Int {
+    var count = 0
+    return {
+        count = count + 1
+        count
+    }
+}
+
+fun compose(f: (Int) -> Int, g: (Int) -> Int): (Int) -> Int {
+    return { x -> g(f(x)) }
+}
+
+class Button(val label: String, val onClick: (String) -> String)
+
+fun main() {
+    println(applyTwice(3) { it * 2 })
+    println(applyTwice(3, { x -> x + 10 }))
+
+    val add: (Int, Int) -> Int = { a, b -> a + b }
+    println(add(2, 3))
+
+    val square = { x: Int -> x * x }
+    println(square(7))
+
+    var sum = 0
+    repeat(5) { sum = sum + it }
+    println("sum = $sum")
+
+    val counter = makeCounter()
+    counter()
+    counter()
+    println("counter = ${counter()}")
+
+    val other = makeCounter()
+    println("other = ${other()}")
+
+    val incrementThenSquare = compose({ it + 1 }, square)
+    println(incrementThenSquare(4))
+
+    val printers = arrayOf({ x: Int -> "first $x" }, { x: Int -> "second $x" })
+    for (printer in printers) {
+        println(printer(1))
+    }
+
+    val button = Button("OK") { "clicked $it" }
+    println(button.onClick(button.label))
+}
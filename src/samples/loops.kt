@@ -0,0 +1,46 @@
+fun firstNegative(numbers: Array<Int>): Int {
+    var found = 0
+    for (number in numbers) {
+        if (number < 0) {
+            found = number
+            break
+        }
+    }
+    return found
+}
+
+fun countVowels(word: String): Int {
+    var count = 0
+    for (c in word) {
+        if (c != 'a' && c != 'e' && c != 'i' && c != 'o' && c != 'u') {
+            continue
+        }
+        count = count + 1
+    }
+    return count
+}
+
+fun main() {
+    for (i in 0..10) {
+        if (i % 2 == 1) {
+            continue
+        }
+        if (i > 6) {
+            break
+        }
+        println("even: " + i)
+    }
+
+    var n = 0
+    while (true) {
+        n = n + 1
+        if (n < 3) {
+            continue
+        }
+        println("n = " + n)
+        break
+    }
+
+    println("first negative: " + firstNegative(arrayOf(3, 1, -4, 1, -5)))
+    println("vowels: " + countVowels("interpreter"))
+}
@@ -0,0 +1,41 @@
+fun identity(n: Int): Array<Array<Int>> {
+    val m = Array(n) { Array(n) { 0 } }
+    for (i in m.indices) {
+        m[i][i] = 1
+    }
+    return m
+}
+
+fun multiply(a: Array<Array<Int>>, b: Array<Array<Int>>): Array<Array<Int>> {
+    val result = Array(a.size) { Array(b[0].size) { 0 } }
+    for (i in a.indices) {
+        for (j in b[0].indices) {
+            var sum = 0
+            for (k in b.indices) {
+                sum = sum + a[i][k] * b[k][j]
+            }
+            result[i][j] = sum
+        }
+    }
+    return result
+}
+
+fun printMatrix(m: Array<Array<Int>>) {
+    for (row in m) {
+        println(row.joinToString(" "))
+    }
+}
+
+fun main() {
+    val m = Array(3) { i -> arrayOf(i * 3 + 1, i * 3 + 2, i * 3 + 3) }
+    printMatrix(m)
+    println()
+
+    printMatrix(multiply(m, identity(3)))
+    println()
+
+    val squared = multiply(m, m)
+    printMatrix(squared)
+    println("trace = ${squared[0][0] + squared[1][1] + squared[2][2]}")
+    println("last = ${squared[squared.lastIndex][squared[0].lastIndex]}")
+}
@@ -0,0 +1,32 @@
+fun find(words: Array<String>, letter: Char): String? {
+    for (word in words) {
+        if (word[0] == letter) {
+            return word
+        }
+    }
+    return null
+}
+
+fun describe(word: String?): String {
+    if (word != null) {
+        return "found " + word + " of length " + word.length
+    }
+    return "nothing found"
+}
+
+fun main() {
+    val words = arrayOf("kotlin", "rust", "null")
+
+    println(describe(find(words, 'r')))
+    println(describe(find(words, 'x')))
+
+    val missing = find(words, 'x')
+    println(missing?.length ?: -1)
+    val found = find(words, 'k')
+    println(found!!.length)
+
+    var maybe: Int? = null
+    println(maybe)
+    maybe = 42
+    println(maybe ?: 0)
+}
@@ -0,0 +1,23 @@
+fun average(values: Array<Int>): Double {
+    var sum = 0L
+    for (value in values) {
+        sum = sum + value
+    }
+    return sum / 3.0
+}
+
+fun main() {
+    val big = 3000000000
+    println(big * 2)
+    println(2147483647L + 1)
+
+    println(average(arrayOf(1, 2, 4)))
+    println(1e3 / 8)
+    println(1.5f * 2)
+    println(10 % 3.5)
+
+    val half = 0.5
+    if (1 > half && 1L >= 1) {
+        println("promoted comparison")
+    }
+}
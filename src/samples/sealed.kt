@@ -0,0 +1,40 @@
+sealed interface Shape
+
+data class Circle(val radius: Double) : Shape
+
+data class Rectangle(val width: Double, val height: Double) : Shape
+
+sealed class Result(val code: Int)
+
+class Success(val value: Int) : Result(0)
+
+class Failure(val message: String) : Result(1)
+
+fun area(shape: Shape): Double {
+    return when (shape) {
+        is Circle -> 3.0 * shape.radius * shape.radius
+        is Rectangle -> shape.width * shape.height
+    }
+}
+
+fun describe(result: Result?): String {
+    return when (result) {
+        is Success -> "success " + result.value
+        is Failure -> "failure: " + result.message
+        null -> "nothing"
+    }
+}
+
+fun main() {
+    val shapes = arrayOf(Circle(1.0), Rectangle(2.0, 3.5))
+    for (shape in shapes) {
+        println(shape)
+        println(area(shape))
+    }
+
+    val success = Success(42)
+    println(success.code)
+    println(describe(success))
+    println(describe(Failure("timeout")))
+    println(describe(null))
+}
@@ -1,19 +1,9 @@
-fun size(arr: Array<String>): Int {
-    var size = 0
-    for (el in arr) {
-        size = size + 1
-    }
-    return size
-}
-
-fun insertionSort(arr: Array<String>) {
-    val lastIndex = size(arr) - 1
-
-    for (i in 1..lastIndex) {
+fun insertionSort(arr: Array<String>, isLess: (String, String) -> Boolean) {
+    for (i in 1..arr.lastIndex) {
         val temp = arr[i]
         var holePosition = i
 
-        while(holePosition > 0 && arr[holePosition - 1] > temp) {
+        while(holePosition > 0 && isLess(temp, arr[holePosition - 1])) {
             arr[holePosition] = arr[holePosition - 1]
             holePosition = holePosition - 1
         }
@@ -33,10 +23,15 @@ fun main() {
     println("Before Sorting: ")
     printArray(arr)
 
-    insertionSort(arr)
+    insertionSort(arr, { a, b -> a < b })
     
     println("After Sorting: ")
     printArray(arr)
+
+    insertionSort(arr) { a, b -> a.length < b.length }
+
+    println("After Sorting by length: ")
+    printArray(arr)
 }
 
 fun sampleStrings(): Array<String> {
@@ -0,0 +1,40 @@
+data class Word(val text: String) {
+    fun shout(): Word {
+        return Word(text.uppercase() + "!")
+    }
+}
+
+fun capitalize(word: String): String {
+    if (word.isEmpty()) {
+        return word
+    }
+    return word.substring(0, 1).uppercase() + word.substring(1)
+}
+
+fun main() {
+    val sentence = "  the quick brown fox  "
+    val words = sentence.trim().split(" ")
+    println("${words.size} words, ${sentence.trim().length} characters")
+
+    var title = ""
+    for (word in words) {
+        title = "$title${capitalize(word)} "
+    }
+    println(title.trim())
+
+    println(Word("hey").shout().shout().text)
+    println("racecar".reversed() == "racecar")
+    println("level".indexOf("v"))
+    println("ab".repeat(3).lowercase())
+    println(("4" + "2").toInt() / 2)
+    println("abc".toIntOrNull() ?: -1)
+
+    val digits = 1..9
+    println("${digits.first}..${digits.last} sums to ${digits.sum()}")
+    println((10..1).isEmpty())
+
+    val csv = "3,1,2"
+        .split(",")
+        .joinToString(" + ")
+    println(csv)
+}
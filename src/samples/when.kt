@@ -0,0 +1,39 @@
+fun describe(number: Int): String {
+    return when (number) {
+        0 -> "zero"
+        1, 2 -> "small"
+        in 3..9 -> {
+            val kind = "single"
+            kind + " digit"
+        }
+        else -> "large"
+    }
+}
+
+fun sign(number: Int): Int {
+    return when {
+        number < 0 -> -1
+        number > 0 -> 1
+        else -> 0
+    }
+}
+
+fun main() {
+    for (number in arrayOf(0, 2, 7, 42)) {
+        println("$number is ${describe(number)}")
+    }
+
+    when (sign(-5)) {
+        -1 -> println("negative")
+        1 -> println("positive")
+    }
+
+    for (c in "when") {
+        when (c) {
+            in "aeiou" -> println("$c is a vowel")
+            'w' -> {
+                println("$c is w")
+            }
+        }
+    }
+}